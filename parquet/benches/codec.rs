@@ -0,0 +1,426 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+
+//! Benchmarks for the `Codec` interface and the `ColumnData` byte conversions it is
+//! built on.
+//!
+//! ```shell
+//! cargo bench --bench codec
+//! ```
+//!
+//! The `convert_f64` group converts both byte orders. Run it with and without
+//! `--features bytemuck` to compare the bulk conversion fast path, taken for the
+//! host's byte order, against the per-element byteorder path.
+//!
+//! Set `CODEC_LEVEL_SWEEP=1` to also sweep the compression levels of every compiled-in
+//! codec that has levels, as reported by `level_range`, and print their
+//! ratio/throughput frontier:
+//!
+//! ```shell
+//! CODEC_LEVEL_SWEEP=1 cargo bench --bench codec -- level_sweep
+//! ```
+
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{thread_rng, Rng};
+
+use parquet::basic::Compression as CodecType;
+use parquet::compression::{
+    available_codecs, create_codec, create_codec_enum, level_range, CodecOptions,
+    CodecOptionsBuilder,
+};
+use parquet::data_type::{ColumnData, DataTypeConstraint};
+
+const NUM_VALUES: usize = 1_000_000;
+
+fn random_f64_column() -> ColumnData {
+    let mut rng = thread_rng();
+    let values: Vec<f64> = (0..NUM_VALUES).map(|_| rng.gen()).collect();
+    ColumnData::VecF64(values)
+}
+
+fn bench_convert_f64(c: &mut Criterion) {
+    let data = random_f64_column();
+    let mut bytes = Vec::new();
+    data.convert_to_u8(&mut bytes).unwrap();
+
+    let mut group = c.benchmark_group("convert_f64");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+    group.bench_function("convert_to_u8", |b| {
+        let mut output = Vec::with_capacity(bytes.len());
+        b.iter(|| {
+            output.clear();
+            data.convert_to_u8(&mut output).unwrap();
+        })
+    });
+
+    group.bench_function("convert_from_u8", |b| {
+        let mut output = ColumnData::VecF64(Vec::with_capacity(NUM_VALUES));
+        b.iter(|| {
+            output.clear();
+            output.convert_from_u8(&bytes).unwrap();
+        })
+    });
+
+    let mut le_bytes = Vec::new();
+    data.convert_to_u8_ordered(&mut le_bytes, true).unwrap();
+
+    group.bench_function("convert_to_u8_little_endian", |b| {
+        let mut output = Vec::with_capacity(le_bytes.len());
+        b.iter(|| {
+            output.clear();
+            data.convert_to_u8_ordered(&mut output, true).unwrap();
+        })
+    });
+
+    group.bench_function("convert_from_u8_little_endian", |b| {
+        let mut output = ColumnData::VecF64(Vec::with_capacity(NUM_VALUES));
+        b.iter(|| {
+            output.clear();
+            output.convert_from_u8_ordered(&le_bytes, true).unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_decompress_size_hint(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let values: Vec<i64> = (0..NUM_VALUES).map(|_| rng.gen_range(0..1_000_000)).collect();
+    let data = ColumnData::VecI64(values);
+    let uncompress_size = NUM_VALUES * std::mem::size_of::<i64>();
+
+    let options = CodecOptionsBuilder::default().build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+
+    let mut group = c.benchmark_group("decompress_i64");
+    group.throughput(Throughput::Bytes(uncompress_size as u64));
+
+    // without a size hint the byte and typed buffers grow by reallocation
+    group.bench_function("without uncompress_size", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecI64(Vec::new());
+            codec.decompress(&compressed, &mut output, None).unwrap();
+        })
+    });
+
+    group.bench_function("with uncompress_size", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecI64(Vec::new());
+            codec
+                .decompress(&compressed, &mut output, Some(uncompress_size))
+                .unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_snappy_decompress_u32(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let values: Vec<u32> = (0..NUM_VALUES).map(|_| rng.gen_range(0..1_000_000)).collect();
+    let data = ColumnData::VecU32(values);
+    let uncompress_size = NUM_VALUES * std::mem::size_of::<u32>();
+
+    let options = CodecOptionsBuilder::default().build();
+    let mut codec = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+
+    let mut group = c.benchmark_group("snappy_decompress_u32");
+    group.throughput(Throughput::Bytes(uncompress_size as u64));
+
+    group.bench_function("with uncompress_size", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecU32(Vec::new());
+            codec
+                .decompress(&compressed, &mut output, Some(uncompress_size))
+                .unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+/// Number of values compressed by `bench_static_dispatch`.
+const DISPATCH_NUM_VALUES: usize = 100_000;
+
+/// Compares SNAPPY behind the `Box<dyn Codec>` of `create_codec` with the statically
+/// dispatched `CodecKind` of `create_codec_enum`.
+fn bench_static_dispatch(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let values: Vec<u64> = (0..DISPATCH_NUM_VALUES)
+        .map(|_| rng.gen_range(0..1_000_000))
+        .collect();
+    let data = ColumnData::VecU64(values);
+    let uncompress_size = DISPATCH_NUM_VALUES * std::mem::size_of::<u64>();
+
+    // without block splitting, so both paths run the bare codec
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let mut boxed = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let mut kind = create_codec_enum(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    kind.compress(&data, &mut compressed).unwrap();
+
+    let mut group = c.benchmark_group("snappy_dispatch_u64");
+    group.throughput(Throughput::Bytes(uncompress_size as u64));
+
+    group.bench_function("compress boxed", |b| {
+        let mut output = Vec::new();
+        b.iter(|| {
+            output.clear();
+            boxed.compress(&data, &mut output).unwrap();
+        })
+    });
+
+    group.bench_function("compress enum", |b| {
+        let mut output = Vec::new();
+        b.iter(|| {
+            output.clear();
+            kind.compress(&data, &mut output).unwrap();
+        })
+    });
+
+    group.bench_function("decompress boxed", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecU64(Vec::new());
+            boxed
+                .decompress(&compressed, &mut output, Some(uncompress_size))
+                .unwrap();
+        })
+    });
+
+    group.bench_function("decompress enum", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecU64(Vec::new());
+            kind.decompress(&compressed, &mut output, Some(uncompress_size))
+                .unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+/// Benchmarks compressing and decompressing an i64 column with every codec compiled
+/// into this build, as reported by `available_codecs`.
+fn bench_available_codecs(c: &mut Criterion) {
+    let codecs = available_codecs();
+    println!("Benchmarking compiled-in codecs: {:?}", codecs);
+
+    let mut rng = thread_rng();
+    let values: Vec<i64> = (0..NUM_VALUES).map(|_| rng.gen_range(0..1_000_000)).collect();
+    let data = ColumnData::VecI64(values);
+    let uncompress_size = NUM_VALUES * std::mem::size_of::<i64>();
+    let options = CodecOptionsBuilder::default().build();
+
+    let mut group = c.benchmark_group("available_codecs_i64");
+    group.throughput(Throughput::Bytes(uncompress_size as u64));
+
+    for codec_type in codecs {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+
+        let name = codec.name();
+        group.bench_function(BenchmarkId::new("compress", name), |b| {
+            let mut output = Vec::new();
+            b.iter(|| {
+                output.clear();
+                codec.compress(&data, &mut output).unwrap();
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("decompress", name), |b| {
+            b.iter(|| {
+                let mut output = ColumnData::VecI64(Vec::new());
+                codec
+                    .decompress(&compressed, &mut output, Some(uncompress_size))
+                    .unwrap();
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Reads the values of `data` back as `T` one element at a time through `dyn Any`,
+/// the way generic `DataTypeConstraint` code has to.
+fn generic_values<T: DataTypeConstraint>(data: &ColumnData) -> Vec<T> {
+    match data {
+        ColumnData::VecI64(x) => x
+            .iter()
+            .map(|v| *v.as_any().downcast_ref::<T>().unwrap())
+            .collect(),
+        _ => unreachable!(),
+    }
+}
+
+fn bench_typed_vs_generic(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let values: Vec<i64> = (0..NUM_VALUES).map(|_| rng.gen_range(0..1_000_000)).collect();
+
+    let options = CodecOptionsBuilder::default().build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec
+        .compress(&ColumnData::VecI64(values.clone()), &mut compressed)
+        .unwrap();
+
+    // per-element throughput, so the difference between the two paths reads as the
+    // per-element cost of the generic downcast
+    let mut group = c.benchmark_group("typed_vs_generic_i64");
+    group.throughput(Throughput::Elements(NUM_VALUES as u64));
+
+    group.bench_function("compress typed", |b| {
+        let mut output = Vec::new();
+        b.iter(|| {
+            output.clear();
+            let data = ColumnData::VecI64(values.clone());
+            codec.compress(&data, &mut output).unwrap();
+        })
+    });
+
+    group.bench_function("compress generic", |b| {
+        let mut output = Vec::new();
+        b.iter(|| {
+            output.clear();
+            let data = ColumnData::new(&values);
+            codec.compress(&data, &mut output).unwrap();
+        })
+    });
+
+    group.bench_function("decompress typed", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::VecI64(Vec::new());
+            codec.decompress(&compressed, &mut output, None).unwrap();
+            match output {
+                ColumnData::VecI64(x) => x,
+                _ => unreachable!(),
+            }
+        })
+    });
+
+    group.bench_function("decompress generic", |b| {
+        b.iter(|| {
+            let mut output = ColumnData::new(&Vec::<i64>::new());
+            codec.decompress(&compressed, &mut output, None).unwrap();
+            generic_values::<i64>(&output)
+        })
+    });
+
+    group.finish();
+}
+
+/// Number of values compressed at every level of the sweep; smaller than
+/// `NUM_VALUES` as the strongest levels are slow.
+const SWEEP_NUM_VALUES: usize = 100_000;
+
+/// Returns the default options with the level of `codec_type` set to `level`.
+fn options_at_level(codec_type: CodecType, level: i32) -> CodecOptions {
+    let options = CodecOptionsBuilder::default().build();
+    match codec_type {
+        CodecType::ZSTD => options.with_zstd_level(level),
+        CodecType::GZIP => options.with_gzip_level(level as u32),
+        CodecType::BROTLI => options.with_brotli_level(level as u32),
+        CodecType::XZ => options.with_xz_preset(level as u32),
+        CodecType::QCOM => options.with_qcom_level(level as usize),
+        _ => unreachable!(),
+    }
+}
+
+/// Sweeps every level of the compiled-in codecs that have levels, benchmarking each,
+/// then prints the levels on the ratio/throughput frontier: those no other level of
+/// the same codec beats on both ratio and throughput.
+fn bench_level_sweep(c: &mut Criterion) {
+    if std::env::var("CODEC_LEVEL_SWEEP").is_err() {
+        return;
+    }
+    // a random walk, so stronger levels have redundancy to find
+    let mut rng = thread_rng();
+    let mut value = 0i64;
+    let values: Vec<i64> = (0..SWEEP_NUM_VALUES)
+        .map(|_| {
+            value += rng.gen_range(-100..100);
+            value
+        })
+        .collect();
+    let data = ColumnData::VecI64(values);
+    let uncompress_size = SWEEP_NUM_VALUES * std::mem::size_of::<i64>();
+
+    let mut group = c.benchmark_group("level_sweep");
+    group.throughput(Throughput::Bytes(uncompress_size as u64));
+    group.sample_size(10);
+
+    for codec_type in available_codecs() {
+        let levels = match level_range(codec_type) {
+            Some(levels) => levels,
+            None => continue,
+        };
+        // (level, compression ratio, throughput in MB/s)
+        let mut points = Vec::new();
+        for level in levels {
+            let options = options_at_level(codec_type, level);
+            let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+            let mut compressed = Vec::new();
+            let start = Instant::now();
+            codec.compress(&data, &mut compressed).unwrap();
+            let throughput = uncompress_size as f64 / start.elapsed().as_secs_f64() / 1e6;
+            let ratio = uncompress_size as f64 / compressed.len() as f64;
+            points.push((level, ratio, throughput));
+
+            let id = BenchmarkId::new(codec_type.to_string(), level);
+            group.bench_function(id, |b| {
+                let mut output = Vec::new();
+                b.iter(|| {
+                    output.clear();
+                    codec.compress(&data, &mut output).unwrap();
+                })
+            });
+        }
+
+        println!("{} ratio/throughput frontier:", codec_type);
+        for &(level, ratio, throughput) in &points {
+            let dominated = points
+                .iter()
+                .any(|&(_, r, t)| r > ratio && t > throughput);
+            if !dominated {
+                println!("  level {:>2}: ratio {:.3}, {:.1} MB/s", level, ratio, throughput);
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_convert_f64,
+    bench_decompress_size_hint,
+    bench_snappy_decompress_u32,
+    bench_static_dispatch,
+    bench_typed_vs_generic,
+    bench_available_codecs
+);
+criterion_group!(level_sweep, bench_level_sweep);
+criterion_main!(benches, level_sweep);
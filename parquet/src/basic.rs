@@ -293,6 +293,8 @@ pub enum Compression {
     ZSTD,
     LZ4_RAW,
     QCOM,
+    BZIP2,
+    XZ,
 }
 
 // ----------------------------------------------------------------------
@@ -838,6 +840,8 @@ impl TryFrom<parquet::CompressionCodec> for Compression {
             parquet::CompressionCodec::ZSTD => Compression::ZSTD,
             parquet::CompressionCodec::LZ4_RAW => Compression::LZ4_RAW,
             parquet::CompressionCodec::QCOM => Compression::QCOM,
+            parquet::CompressionCodec::BZIP2 => Compression::BZIP2,
+            parquet::CompressionCodec::XZ => Compression::XZ,
             _ => {
                 return Err(general_err!(
                     "unexpected parquet compression codec: {}",
@@ -860,6 +864,8 @@ impl From<Compression> for parquet::CompressionCodec {
             Compression::ZSTD => parquet::CompressionCodec::ZSTD,
             Compression::LZ4_RAW => parquet::CompressionCodec::LZ4_RAW,
             Compression::QCOM => parquet::CompressionCodec::QCOM,
+            Compression::BZIP2 => parquet::CompressionCodec::BZIP2,
+            Compression::XZ => parquet::CompressionCodec::XZ,
         }
     }
 }
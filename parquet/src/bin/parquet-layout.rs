@@ -190,6 +190,9 @@ fn compression(compression: Compression) -> Option<&'static str> {
         Compression::LZ4 => Some("lz4"),
         Compression::ZSTD => Some("zstd"),
         Compression::LZ4_RAW => Some("lz4_raw"),
+        Compression::QCOM => Some("qcom"),
+        Compression::BZIP2 => Some("bzip2"),
+        Compression::XZ => Some("xz"),
     }
 }
 
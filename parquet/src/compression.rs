@@ -47,11 +47,22 @@ assert_eq!(output, data);
 ```
 "##
 )]
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use twox_hash::XxHash64;
+
 use crate::basic::Compression as CodecType;
 use crate::errors::{ParquetError, Result};
 
 // enum for columnar data
-use crate::data_type::ColumnData;
+use crate::data_type::{supported_typenames, ColumnData, DataTypeConstraint};
 
 /// Parquet compression codec interface.
 pub trait Codec: Send {
@@ -75,789 +86,10650 @@ pub trait Codec: Send {
         output_buf_columndata: &mut ColumnData,
         uncompress_size: Option<usize>,
     ) -> Result<usize>;
-}
-
-/// Struct to hold `Codec` creation options.
-#[derive(Debug, PartialEq, Eq)]
-pub struct CodecOptions {
-    /// Whether or not to fallback to other LZ4 older implementations on error in LZ4_HADOOP.
-    backward_compatible_lz4: bool,
-}
 
-impl Default for CodecOptions {
-    fn default() -> Self {
-        CodecOptionsBuilder::default().build()
+    /// Compresses `input_buf_columndata` within roughly `budget`, appending the result
+    /// to `output_buf`, and returns the number of bytes appended.
+    ///
+    /// Codecs with tunable levels time a sample at a high level first and drop to faster
+    /// levels when it would not finish in time. This is best-effort, not a hard
+    /// guarantee. Codecs without levels compress as usual.
+    fn compress_within(
+        &mut self,
+        input_buf_columndata: &ColumnData,
+        output_buf: &mut Vec<u8>,
+        _budget: Duration,
+    ) -> Result<usize> {
+        let offset = output_buf.len();
+        self.compress(input_buf_columndata, output_buf)?;
+        Ok(output_buf.len() - offset)
     }
-}
 
-pub struct CodecOptionsBuilder {
-    /// Whether or not to fallback to other LZ4 older implementations on error in LZ4_HADOOP.
-    backward_compatible_lz4: bool,
-}
+    /// Returns a new codec with the same configuration as this one, e.g. to compress on
+    /// another thread.
+    fn clone_codec(&self) -> Box<dyn Codec>;
 
-impl Default for CodecOptionsBuilder {
-    fn default() -> Self {
-        Self {
-            backward_compatible_lz4: true,
+    /// Compresses several non-contiguous chunks as if they were one column and appends
+    /// the compressed result to `output_buf`.
+    ///
+    /// The chunks are appended in order, so decompressing the output yields the
+    /// concatenation of `inputs`. All chunks must hold the same element type.
+    ///
+    /// Codecs with a streaming encoder feed the chunks to it one at a time through
+    /// `compress_stream`. The others need their input in one buffer, so by
+    /// default the chunks are copied into one column and passed to `compress`.
+    fn compress_vectored(
+        &mut self,
+        inputs: &[&ColumnData],
+        output_buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let mut combined = match inputs.first() {
+            Some(first) => (*first).clone(),
+            None => return Ok(()),
+        };
+        for input in &inputs[1..] {
+            combined.extend_from(input)?;
         }
+        self.compress(&combined, output_buf)
     }
-}
 
-impl CodecOptionsBuilder {
-    /// Enable/disable backward compatible LZ4.
+    /// Decompresses the file at `path` and appends the values to
+    /// `output_buf_columndata`.
     ///
-    /// If backward compatible LZ4 is enable, on LZ4_HADOOP error it will fallback
-    /// to the older versions LZ4 algorithms. That is LZ4_FRAME, for backward compatibility
-    /// with files generated by older versions of this library, and LZ4_RAW, for backward
-    /// compatibility with files generated by older versions of parquet-cpp.
+    /// Streaming codecs read and decode the file incrementally, so neither the whole
+    /// compressed file nor the whole decompressed byte stream is held in memory. Other
+    /// codecs read the file into memory and call `decompress`.
     ///
-    /// If backward compatible LZ4 is disabled, on LZ4_HADOOP error it will return the error.
-    pub fn set_backward_compatible_lz4(mut self, value: bool) -> CodecOptionsBuilder {
-        self.backward_compatible_lz4 = value;
-        self
+    /// Returns the total number of bytes decompressed.
+    fn decompress_file(
+        &mut self,
+        path: &Path,
+        output_buf_columndata: &mut ColumnData,
+        uncompress_size: Option<usize>,
+    ) -> Result<usize> {
+        let input_buf = std::fs::read(path)?;
+        self.decompress(&input_buf, output_buf_columndata, uncompress_size)
     }
 
-    pub fn build(self) -> CodecOptions {
-        CodecOptions {
-            backward_compatible_lz4: self.backward_compatible_lz4,
+    /// Decompresses `input_buf` like `decompress`, appending the values to
+    /// `output_buf_columndata`, and also returns the decompressed bytes the values were
+    /// converted from, e.g. to checksum them.
+    ///
+    /// Byte codecs keep the bytes they decode instead of converting the values back.
+    /// Codecs that decode typed values directly, such as QCOM, return the values'
+    /// big-endian bytes.
+    fn decompress_with_bytes(
+        &mut self,
+        input_buf: &[u8],
+        output_buf_columndata: &mut ColumnData,
+        uncompress_size: Option<usize>,
+    ) -> Result<Vec<u8>> {
+        let mut bytes = ColumnData::VecU8(Vec::new());
+        self.decompress(input_buf, &mut bytes, uncompress_size)?;
+        let mut bytes = match bytes {
+            ColumnData::VecU8(x) => x,
+            _ => unreachable!(),
+        };
+        if self.last_was_truncated() {
+            // drop the partial value a truncated stream may end with
+            let width = output_buf_columndata.type_width();
+            bytes.truncate(bytes.len() / width * width);
         }
+        output_buf_columndata.convert_from_u8(&bytes)?;
+        Ok(bytes)
     }
-}
 
-/// Given the compression type `codec`, returns a codec used to compress and decompress
-/// bytes for the compression type.
-/// This returns `None` if the codec type is `UNCOMPRESSED`.
-pub fn create_codec(
-    codec: CodecType,
-    _options: &CodecOptions,
-) -> Result<Option<Box<dyn Codec>>> {
-    match codec {
-        #[cfg(any(feature = "brotli", test))]
-        CodecType::BROTLI => Ok(Some(Box::new(BrotliCodec::new()))),
-        #[cfg(any(feature = "flate2", test))]
-        CodecType::GZIP => Ok(Some(Box::new(GZipCodec::new()))),
-        #[cfg(any(feature = "snap", test))]
-        CodecType::SNAPPY => Ok(Some(Box::new(SnappyCodec::new()))),
-        #[cfg(any(feature = "lz4", test))]
-        CodecType::LZ4 => Ok(Some(Box::new(LZ4HadoopCodec::new(
-            _options.backward_compatible_lz4,
-        )))),
-        #[cfg(any(feature = "zstd", test))]
-        CodecType::ZSTD => Ok(Some(Box::new(ZSTDCodec::new()))),
-        #[cfg(any(feature = "lz4", test))]
-        CodecType::LZ4_RAW => Ok(Some(Box::new(LZ4RawCodec::new()))),
-        #[cfg(any(feature = "q_compress", test))]
-        CodecType::QCOM => Ok(Some(Box::new(QComCodec::new()))),
-        CodecType::UNCOMPRESSED => Ok(None),
-        _ => Err(nyi_err!("The codec type {} is not supported yet", codec)),
+    /// Decompresses `input_buf` and returns the xxHash64 (seed 0) of the decompressed
+    /// bytes, which is stable across runs and platforms, e.g. to deduplicate columns.
+    ///
+    /// The bytes are hashed as decoded, without converting them to typed values.
+    /// Codecs that decode typed values directly, such as QCOM, cannot produce plain
+    /// bytes and return an error.
+    fn content_hash(&mut self, input_buf: &[u8], uncompress_size: Option<usize>) -> Result<u64> {
+        let mut bytes = ColumnData::VecU8(Vec::new());
+        self.decompress(input_buf, &mut bytes, uncompress_size)?;
+        let mut hasher = XxHash64::with_seed(0);
+        match bytes {
+            ColumnData::VecU8(x) => hasher.write(&x),
+            _ => unreachable!(),
+        }
+        Ok(hasher.finish())
     }
-}
 
-#[cfg(any(feature = "snap", test))]
-mod snappy_codec {
-    use snap::raw::{decompress_len, max_compress_len, Decoder, Encoder};
+    /// Decompresses `input_buf` as values of type `typename`, e.g. `"i64"`, and returns
+    /// their sum accumulated in `f64`, for callers that only need the aggregate.
+    ///
+    /// The values are only held in a temporary column, which is dropped before
+    /// returning.
+    fn decompress_and_sum(
+        &mut self,
+        input_buf: &[u8],
+        typename: &str,
+        uncompress_size: Option<usize>,
+    ) -> Result<f64> {
+        let mut values = ColumnData::from_typename(typename)
+            .ok_or_else(|| general_err!("Cannot sum values of unsupported type {}", typename))?;
+        self.decompress(input_buf, &mut values, uncompress_size)?;
+        Ok(values.sum_f64())
+    }
 
-    use crate::compression::Codec;
-    use crate::errors::Result;
+    /// Appends a header describing `schema`, followed by the compressed
+    /// `input_buf_columndata`, to `output_buf`. Returns the number of bytes appended.
+    ///
+    /// The header makes the buffer self-describing: read it back with [`read_schema`]
+    /// and the values with `decompress_with_schema`.
+    fn compress_with_schema(
+        &mut self,
+        schema: &ColumnSchema,
+        input_buf_columndata: &ColumnData,
+        output_buf: &mut Vec<u8>,
+    ) -> Result<usize> {
+        let offset = output_buf.len();
+        column_schema::write_schema(schema, output_buf)?;
+        self.compress(input_buf_columndata, output_buf)?;
+        Ok(output_buf.len() - offset)
+    }
 
-    // enum for columnar data
-    use crate::data_type::ColumnData;
+    /// Skips the schema header written by `compress_with_schema` and decompresses the
+    /// values after it.
+    fn decompress_with_schema(
+        &mut self,
+        input_buf: &[u8],
+        output_buf_columndata: &mut ColumnData,
+        uncompress_size: Option<usize>,
+    ) -> Result<usize> {
+        let (_, payload) = column_schema::split_schema(input_buf)?;
+        self.decompress(payload, output_buf_columndata, uncompress_size)
+    }
 
-    /// Codec for Snappy compression format.
-    pub struct SnappyCodec {
-        decoder: Decoder,
-        encoder: Encoder,
+    /// Returns whether this codec can compress and decompress columns of element type
+    /// `typename`, e.g. `"u32"`.
+    fn supports_type(&self, _typename: &str) -> bool {
+        true
     }
 
-    impl SnappyCodec {
-        /// Creates new Snappy compression codec.
-        pub(crate) fn new() -> Self {
-            Self {
-                decoder: Decoder::new(),
-                encoder: Encoder::new(),
-            }
-        }
+    /// Returns the number of bytes this codec's format adds to every compressed
+    /// buffer regardless of its content, such as headers, trailers and length prefixes.
+    ///
+    /// Inputs that compress to less than this are better stored uncompressed.
+    fn framing_overhead(&self) -> usize {
+        0
     }
 
-    impl Codec for SnappyCodec {
-        fn decompress(
-            &mut self,
-            input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            uncompress_size: Option<usize>,
-        ) -> Result<usize> {
+    /// Returns whether the previous `compress` call stored its input uncompressed
+    /// instead of compressing it, because it did not reach the minimum compression
+    /// ratio set in `CodecOptions`.
+    ///
+    /// Callers can use this to record the effective codec in metadata.
+    fn last_was_stored(&self) -> bool {
+        false
+    }
 
-            let mut output_buf = Vec::new();
+    /// Returns the largest input, in uncompressed bytes, that a single `compress` call
+    /// can handle. Callers should split larger columns into chunks.
+    ///
+    /// Block codecs are limited by their block format or size fields. Streaming codecs
+    /// have no limit and return `usize::MAX`.
+    fn max_input_len(&self) -> usize {
+        usize::MAX
+    }
 
-            let len = match uncompress_size {
-                Some(size) => size,
-                None => decompress_len(input_buf)?,
-            };
-            let offset = output_buf.len();
-            output_buf.resize(offset + len, 0);
-            let ret = self.decoder
-                .decompress(input_buf, &mut output_buf[offset..])
-                .map_err(|e| e.into());
-            
-            output_buf_columndata.convert_from_u8(&output_buf);
+    /// Returns whether the previous `decompress` call hit a truncated stream and
+    /// returned only the values decoded before it.
+    ///
+    /// This only happens when partial decompression is allowed in `CodecOptions`;
+    /// otherwise truncation is an error.
+    fn last_was_truncated(&self) -> bool {
+        false
+    }
 
-            ret
-        }
+    /// Returns the indexes of the frames the previous `decompress` call skipped
+    /// because they were corrupt, in the order they appear in the input.
+    ///
+    /// Frames are only skipped when `CodecOptions` enables skipping corrupt frames;
+    /// otherwise a corrupt frame is an error.
+    fn last_skipped_frames(&self) -> &[usize] {
+        &[]
+    }
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+    /// Returns an upper bound on how many times larger a decompressed buffer can be
+    /// than the compressed input it came from, or `None` if the format has no bound.
+    ///
+    /// Callers can multiply the compressed length by this to reject inputs that would
+    /// decompress to more than they are willing to allocate. Streaming codecs, whose
+    /// runs can repeat indefinitely, return `None`.
+    fn max_expansion_ratio(&self) -> Option<f64> {
+        None
+    }
 
-            let output_buf_len = output_buf.len();
-            let required_len = max_compress_len(input_buf.len());
-            output_buf.resize(output_buf_len + required_len, 0);
-            let n = self
-                .encoder
-                .compress(&input_buf, &mut output_buf[output_buf_len..])?;
-            output_buf.truncate(output_buf_len + n);
-            Ok(())
-        }
+    /// Returns a rough estimate of how long compressing `input_len_bytes` bytes takes,
+    /// so schedulers can plan without compressing.
+    ///
+    /// The estimate assumes a fixed throughput per codec, measured by compressing a
+    /// small sample the first time a thread asks about that codec. Actual times vary
+    /// with the data and the machine's load.
+    fn estimate_compress_duration(&self, input_len_bytes: usize) -> Duration {
+        let throughput =
+            calibrated_throughput(std::any::type_name::<Self>(), || self.clone_codec());
+        Duration::from_secs_f64(input_len_bytes as f64 / throughput)
     }
-}
-#[cfg(any(feature = "snap", test))]
-pub use snappy_codec::*;
 
-#[cfg(any(feature = "flate2", test))]
-mod gzip_codec {
+    /// Returns the number of elements, of the column's type rather than bytes, that
+    /// the previous `compress` call processed, e.g. for per-column metrics.
+    ///
+    /// Returns 0 before the first call.
+    fn last_element_count(&self) -> usize {
+        0
+    }
 
-    use std::io::{Read, Write};
+    /// Returns whether decompressing yields exactly the bits that were compressed.
+    ///
+    /// Options that rewrite values while preserving their meaning, such as NaN
+    /// canonicalization, make this return `false`.
+    fn is_lossless(&self) -> bool {
+        true
+    }
 
-    use flate2::{read, write, Compression};
+    /// Returns the version of the compression library behind this codec, for bug
+    /// reports and compatibility logs.
+    ///
+    /// ZSTD and the LZ4 codecs report the version of the C library they run. The
+    /// other libraries cannot report theirs at run time, so their codecs return the
+    /// version of the crate this build requires. Wrapping codecs return the version of
+    /// the codec they wrap.
+    fn backend_version(&self) -> &'static str {
+        "unknown"
+    }
 
-    use crate::compression::Codec;
-    use crate::errors::Result;
+    /// Returns the name of the compression algorithm, e.g. `"SNAPPY"`, for logging and
+    /// labelling metrics.
+    ///
+    /// Codecs of this crate return their `CodecType` name, or `"LZ4_HADOOP"` for the
+    /// Hadoop-framed LZ4 that `CodecType::LZ4` creates. Wrapping codecs return the name
+    /// of the codec they wrap. Other codecs default to their type name.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 
-    // enum for columnar data
-    use crate::data_type::ColumnData;
+    /// Returns whether single blocks of this codec's output can be decompressed with
+    /// `decompress_block`, without decompressing the blocks before them.
+    fn supports_seek(&self) -> bool {
+        false
+    }
 
-    /// Codec for GZIP compression algorithm.
-    pub struct GZipCodec {}
+    /// Decompresses only block `block_index` of `input_buf`, appending its values to
+    /// `output_buf_columndata`, and returns the number of bytes decompressed.
+    ///
+    /// `uncompress_size` is the size of the whole column, which tells the blocks
+    /// apart. Only codecs whose `supports_seek` returns `true` implement this; others
+    /// return an error.
+    fn decompress_block(
+        &mut self,
+        _input_buf: &[u8],
+        _block_index: usize,
+        _output_buf_columndata: &mut ColumnData,
+        _uncompress_size: Option<usize>,
+    ) -> Result<usize> {
+        Err(nyi_err!("{} does not support decompressing single blocks", self.name()))
+    }
 
-    impl GZipCodec {
-        /// Creates new GZIP compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
-        }
+    /// Returns a hash of the codec type and all of its configuration, such as flags,
+    /// byte orders and dictionaries, including that of wrapped codecs, e.g. as part
+    /// of the key of cached compressed data.
+    ///
+    /// Codecs configured alike return the same fingerprint on every run of the same
+    /// build, and changing any option changes it. The default only hashes the type
+    /// name, for codecs without configuration.
+    fn config_fingerprint(&self) -> u64 {
+        fingerprint(&std::any::type_name::<Self>())
     }
 
-    impl Codec for GZipCodec {
-        fn decompress(
-            &mut self,
-            input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            _uncompress_size: Option<usize>,
-        ) -> Result<usize> {
+    /// Merges `frames`, each the output of one `compress` call, into a single buffer
+    /// that decompresses to the concatenation of their values, without recompressing.
+    ///
+    /// Only codecs whose format allows appending frames support this; the others
+    /// return an error.
+    fn concat_frames(&self, _frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+        Err(nyi_err!("concat_frames is not supported by this codec"))
+    }
 
-            let mut output_buf: Vec<u8> = Vec::new();
+    /// Returns the codec to its freshly created state between independent buffers, so
+    /// that long-lived services can reuse it instead of creating a new one.
+    ///
+    /// Only state the codec owns is reset; output buffers the caller passed to earlier
+    /// calls are left untouched. The default does nothing, for stateless codecs.
+    fn reset(&mut self) {}
 
-            let mut decoder = read::GzDecoder::new(input_buf);
-            let ret = decoder.read_to_end(&mut output_buf).map_err(|e| e.into());
+    /// Decompresses `input_buf` as bytes into a buffer owned by the codec and returns
+    /// a view of them, e.g. to decompress many columns in a loop without allocating
+    /// an output buffer for each.
+    ///
+    /// The view borrows the codec, so it must be dropped before the codec is used
+    /// again; the next call overwrites the buffer, which keeps its capacity. Only
+    /// codecs that keep such a buffer, the byte codecs and the wrappers that pass
+    /// their bytes through unchanged, support this; the others return an error.
+    fn decompress_view(
+        &mut self,
+        _input_buf: &[u8],
+        _uncompress_size: Option<usize>,
+    ) -> Result<&[u8]> {
+        Err(nyi_err!("decompress_view is not supported by this codec"))
+    }
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+    /// Compresses everything read from `input` until its end and writes the compressed
+    /// stream to `output`, without holding either in memory, e.g. for columns too large
+    /// to materialize as a [`ColumnData`].
+    ///
+    /// Returns the number of uncompressed bytes read from `input`. The output is the
+    /// same format `compress` produces, so it can be decompressed either way. Only
+    /// codecs whose backend has a streaming encoder support this; the others, such as
+    /// LZ4_RAW and QCOM which need the whole buffer, return an error.
+    ///
+    /// Wrappers created by `create_codec` forward the stream to the codec they wrap.
+    /// Those that rewrite typed values, such as delta or float component splitting,
+    /// leave the untyped bytes unchanged, so a stream must be read back with
+    /// [`Codec::decompress_stream`]. A stream is consumed as it is read, so it is not
+    /// retried on transient errors.
+    fn compress_stream(
+        &mut self,
+        _input: &mut dyn Read,
+        _output: &mut dyn Write,
+    ) -> Result<u64> {
+        Err(nyi_err!("compress_stream is not supported by this codec"))
+    }
 
-            ret
-        }
+    /// Decompresses the stream read from `input` until its end and writes the
+    /// decompressed bytes to `output`, the inverse of [`Codec::compress_stream`].
+    ///
+    /// Returns the number of decompressed bytes written to `output`. Codecs that do
+    /// not support `compress_stream` return an error.
+    fn decompress_stream(
+        &mut self,
+        _input: &mut dyn Read,
+        _output: &mut dyn Write,
+    ) -> Result<u64> {
+        Err(nyi_err!("decompress_stream is not supported by this codec"))
+    }
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+    /// Serializes the state of a compression written through successive
+    /// [`Codec::compress_stream`] calls, so that another codec with the same
+    /// configuration can resume it with [`Codec::restore_state`], e.g. after an
+    /// interruption.
+    ///
+    /// Only codecs whose streams are sequences of self-contained frames support this:
+    /// GZIP, BZIP2, XZ and ZSTD. Each call ends its frame, so the state is how far
+    /// the input and output have got, and the remaining input is compressed with
+    /// further calls appending to the same output. The others return an error.
+    fn save_state(&self) -> Result<Vec<u8>> {
+        Err(nyi_err!("save_state is not supported by this codec"))
+    }
 
-            let mut encoder = write::GzEncoder::new(output_buf, Compression::default());
-            encoder.write_all(&input_buf)?;
-            encoder.try_finish().map_err(|e| e.into())
-        }
+    /// Resumes the compression whose state [`Codec::save_state`] returned as `bytes`.
+    ///
+    /// Returns an error if the state was saved by a codec with another configuration,
+    /// and for codecs that do not support `save_state`.
+    fn restore_state(&mut self, _bytes: &[u8]) -> Result<()> {
+        Err(nyi_err!("restore_state is not supported by this codec"))
     }
 }
-#[cfg(any(feature = "flate2", test))]
-pub use gzip_codec::*;
-
-#[cfg(any(feature = "brotli", test))]
-mod brotli_codec {
-
-    use std::io::{Read, Write};
-
-    use crate::compression::Codec;
-    use crate::errors::Result;
-
-    // enum for columnar data
-    use crate::data_type::ColumnData;
 
-    const BROTLI_DEFAULT_BUFFER_SIZE: usize = 4096;
-    const BROTLI_DEFAULT_COMPRESSION_QUALITY: u32 = 1; // supported levels 0-9
-    const BROTLI_DEFAULT_LG_WINDOW_SIZE: u32 = 22; // recommended between 20-22
-
-    /// Codec for Brotli compression algorithm.
-    pub struct BrotliCodec {}
-
-    impl BrotliCodec {
-        /// Creates new Brotli compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
-        }
+impl dyn Codec {
+    /// Compresses a typed slice and appends the result to `output`.
+    ///
+    /// This is the preferred entry point for callers holding a `&[T]`: the values are
+    /// copied once into a [`ColumnData`] and passed to [`Codec::compress`]. `T` must
+    /// be one of the element types a column can hold, otherwise an error is returned.
+    pub fn compress_typed<T: DataTypeConstraint + Copy>(
+        &mut self,
+        input: &[T],
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        let input = ColumnData::try_from_vec(input.to_vec())?;
+        self.compress(&input, output)
     }
 
-    impl Codec for BrotliCodec {
-        fn decompress(
-            &mut self,
-            input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            uncompress_size: Option<usize>,
-        ) -> Result<usize> {
-            let mut output_buf: Vec<u8> = Vec::new();
-
-            let buffer_size = uncompress_size.unwrap_or(BROTLI_DEFAULT_BUFFER_SIZE);
-            let ret = brotli::Decompressor::new(input_buf, buffer_size)
-                .read_to_end(&mut output_buf)
-                .map_err(|e| e.into());
+    /// Decompresses `input` into values of type `T` and appends them to `out`,
+    /// returning the number of decompressed bytes.
+    ///
+    /// `size` is the uncompressed size in bytes, as for [`Codec::decompress`].
+    pub fn decompress_typed<T: DataTypeConstraint + Copy>(
+        &mut self,
+        input: &[u8],
+        out: &mut Vec<T>,
+        size: Option<usize>,
+    ) -> Result<usize> {
+        let mut column = ColumnData::try_from_vec(Vec::<T>::new())?;
+        let n = self.decompress(input, &mut column, size)?;
+        out.extend(column.try_into_vec::<T>()?);
+        Ok(n)
+    }
+}
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+/// Attaches the codec, operation and column type to a failed byte/typed conversion, so
+/// that an error raised deep inside a codec still says where it came from, e.g.
+/// `ZSTD decompress: conversion to f64 failed: ...`.
+pub(crate) trait ConversionContext<T> {
+    fn conversion_context(self, codec: &str, op: &str, typename: &str) -> Result<T>;
+}
 
-            ret
-        }
+impl<T> ConversionContext<T> for Result<T> {
+    fn conversion_context(self, codec: &str, op: &str, typename: &str) -> Result<T> {
+        self.map_err(|e| {
+            let direction = if op == "decompress" { "to" } else { "from" };
+            let inner = match e {
+                ParquetError::General(message) => message,
+                e => e.to_string(),
+            };
+            general_err!(
+                "{} {}: conversion {} {} failed: {}",
+                codec,
+                op,
+                direction,
+                typename,
+                inner
+            )
+        })
+    }
+}
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+/// Checks that `codec` supports the element type of `output_buf_columndata` before
+/// any decompression work is done.
+pub(crate) fn check_output_type(
+    codec: &dyn Codec,
+    name: &str,
+    output_buf_columndata: &ColumnData,
+) -> Result<()> {
+    let typename = output_buf_columndata.typename();
+    if codec.supports_type(typename) {
+        Ok(())
+    } else {
+        Err(general_err!("{} decompress: unsupported output type {}", name, typename))
+    }
+}
 
-            let mut encoder = brotli::CompressorWriter::new(
-                output_buf,
-                BROTLI_DEFAULT_BUFFER_SIZE,
-                BROTLI_DEFAULT_COMPRESSION_QUALITY,
-                BROTLI_DEFAULT_LG_WINDOW_SIZE,
-            );
-            encoder.write_all(&input_buf)?;
-            encoder.flush().map_err(|e| e.into())
+/// Returns the size hint to decompress `count` values `width` bytes wide with, where
+/// `count` was read from an untrusted header and the values were compressed by `codec`
+/// into `input_len` bytes.
+///
+/// A count whose size overflows, or exceeds what `input_len` bytes can decompress to
+/// by [`Codec::max_expansion_ratio`], is an error rather than a panic or an oversized
+/// allocation. Codecs without such a bound stream their output and do not need the
+/// size, so they get no hint, and a corrupt count cannot make them reserve memory.
+pub(crate) fn checked_size_hint(
+    codec: &dyn Codec,
+    count: u64,
+    width: usize,
+    input_len: usize,
+) -> Result<Option<usize>> {
+    let size = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(width))
+        .filter(|&size| size <= isize::MAX as usize);
+    match (size, codec.max_expansion_ratio()) {
+        (Some(size), Some(ratio)) if size as f64 <= input_len as f64 * ratio => {
+            Ok(Some(size))
         }
+        (Some(_), None) => Ok(None),
+        _ => Err(general_err!(
+            "Header count {} of {}-byte values does not fit in {} compressed bytes",
+            count,
+            width,
+            input_len
+        )),
     }
 }
-#[cfg(any(feature = "brotli", test))]
-pub use brotli_codec::*;
-
-#[cfg(any(feature = "lz4", test))]
-mod lz4_codec {
-    use std::io::{Read, Write};
-
-    use crate::compression::Codec;
-    use crate::errors::Result;
 
-    // enum for columnar data
-    use crate::data_type::ColumnData;
+/// Implements [`Codec::compress_vectored`] for a codec with a streaming encoder by
+/// passing the chunks to [`Codec::compress_stream`] through a reader that converts
+/// them to bytes one at a time, so they are never copied into one buffer.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+pub(crate) fn compress_vectored_stream(
+    codec: &mut dyn Codec,
+    name: &str,
+    inputs: &[&ColumnData],
+    output_buf: &mut Vec<u8>,
+) -> Result<()> {
+    let first = match inputs.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    if let Some(other) = inputs.iter().find(|i| i.typename() != first.typename()) {
+        return Err(general_err!(
+            "Cannot extend a {} column with {} data",
+            first.typename(),
+            other.typename()
+        ));
+    }
 
-    const LZ4_BUFFER_SIZE: usize = 4096;
+    let mut reader = ChunkReader {
+        name,
+        chunks: inputs.iter(),
+        buf: scratch_bytes(),
+        pos: 0,
+        error: None,
+    };
+    // a buffer is not part of the stream whose state `save_state` returns
+    let state = codec.save_state().ok();
+    let result = codec.compress_stream(&mut reader, output_buf);
+    if let Some(state) = state {
+        codec.restore_state(&state)?;
+    }
+    match reader.error {
+        Some(e) => Err(e),
+        None => result.map(|_| ()),
+    }
+}
 
-    /// Codec for LZ4 compression algorithm.
-    pub struct LZ4Codec {}
+/// Reads the bytes of a sequence of columns, converting one column at a time.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+struct ChunkReader<'a, B: crate::data_type::ByteSink> {
+    /// Name of the codec reading, for conversion errors.
+    name: &'a str,
+    chunks: std::slice::Iter<'a, &'a ColumnData>,
+    /// Bytes of the current column.
+    buf: B,
+    /// Number of bytes of `buf` already read.
+    pos: usize,
+    /// Conversion error that ended the stream, with more context than the `io::Error`
+    /// returned in its place.
+    error: Option<ParquetError>,
+}
 
-    impl LZ4Codec {
-        /// Creates new LZ4 compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+impl<B: crate::data_type::ByteSink> Read for ChunkReader<'_, B> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.buf.len() {
+            let chunk = match self.chunks.next() {
+                Some(chunk) => chunk,
+                None => return Ok(0),
+            };
+            self.buf.resize(0, 0);
+            self.pos = 0;
+            if let Err(e) = chunk.convert_to_u8(&mut self.buf).conversion_context(
+                self.name,
+                "compress",
+                chunk.typename(),
+            ) {
+                let message = e.to_string();
+                self.error = Some(e);
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, message));
+            }
         }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf.as_mut_slice()[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
     }
+}
 
-    impl Codec for LZ4Codec {
+/// Returns the xxHash64 (seed 0) of `config`, for `Codec::config_fingerprint`.
+pub(crate) fn fingerprint<T: Hash + ?Sized>(config: &T) -> u64 {
+    let mut hasher = XxHash64::with_seed(0);
+    config.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Implements each listed [`Codec`] method for a wrapper codec by forwarding it unchanged
+/// to the wrapper's `inner` codec, so a wrapper only spells out the methods it changes.
+///
+/// A stream is untyped bytes, so wrappers that rewrite typed values have nothing to
+/// rewrite in `compress_stream` and `decompress_stream` and forward them, together with
+/// the stream state of `save_state` and `restore_state`. `config_fingerprint` and
+/// `clone_codec` cover the wrapper's own configuration and are always written out.
+macro_rules! forward_to_inner {
+    ($($method:ident),+ $(,)?) => {
+        $(forward_to_inner!(@$method);)+
+    };
+    (@decompress) => {
         fn decompress(
             &mut self,
             input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            _uncompress_size: Option<usize>,
+            output_buf_columndata: &mut crate::data_type::ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> crate::errors::Result<usize> {
+            self.inner
+                .decompress(input_buf, output_buf_columndata, uncompress_size)
+        }
+    };
+    (@decompress_file) => {
+        fn decompress_file(
+            &mut self,
+            path: &std::path::Path,
+            output_buf_columndata: &mut crate::data_type::ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> crate::errors::Result<usize> {
+            self.inner
+                .decompress_file(path, output_buf_columndata, uncompress_size)
+        }
+    };
+    (@decompress_with_bytes) => {
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut crate::data_type::ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> crate::errors::Result<Vec<u8>> {
+            self.inner
+                .decompress_with_bytes(input_buf, output_buf_columndata, uncompress_size)
+        }
+    };
+    (@compress) => {
+        fn compress(
+            &mut self,
+            input_buf_columndata: &crate::data_type::ColumnData,
+            output_buf: &mut Vec<u8>,
+        ) -> crate::errors::Result<()> {
+            self.inner.compress(input_buf_columndata, output_buf)
+        }
+    };
+    (@compress_within) => {
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &crate::data_type::ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: std::time::Duration,
+        ) -> crate::errors::Result<usize> {
+            self.inner
+                .compress_within(input_buf_columndata, output_buf, budget)
+        }
+    };
+    (@compress_stream) => {
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn std::io::Read,
+            output: &mut dyn std::io::Write,
+        ) -> crate::errors::Result<u64> {
+            self.inner.compress_stream(input, output)
+        }
+    };
+    (@decompress_stream) => {
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn std::io::Read,
+            output: &mut dyn std::io::Write,
+        ) -> crate::errors::Result<u64> {
+            self.inner.decompress_stream(input, output)
+        }
+    };
+    (@save_state) => {
+        fn save_state(&self) -> crate::errors::Result<Vec<u8>> {
+            self.inner.save_state()
+        }
+    };
+    (@restore_state) => {
+        fn restore_state(&mut self, bytes: &[u8]) -> crate::errors::Result<()> {
+            self.inner.restore_state(bytes)
+        }
+    };
+    (@supports_type) => {
+        fn supports_type(&self, typename: &str) -> bool {
+            self.inner.supports_type(typename)
+        }
+    };
+    (@framing_overhead) => {
+        fn framing_overhead(&self) -> usize {
+            self.inner.framing_overhead()
+        }
+    };
+    (@last_was_stored) => {
+        fn last_was_stored(&self) -> bool {
+            self.inner.last_was_stored()
+        }
+    };
+    (@max_input_len) => {
+        fn max_input_len(&self) -> usize {
+            self.inner.max_input_len()
+        }
+    };
+    (@last_was_truncated) => {
+        fn last_was_truncated(&self) -> bool {
+            self.inner.last_was_truncated()
+        }
+    };
+    (@last_skipped_frames) => {
+        fn last_skipped_frames(&self) -> &[usize] {
+            self.inner.last_skipped_frames()
+        }
+    };
+    (@max_expansion_ratio) => {
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            self.inner.max_expansion_ratio()
+        }
+    };
+    (@estimate_compress_duration) => {
+        fn estimate_compress_duration(&self, input_len_bytes: usize) -> std::time::Duration {
+            self.inner.estimate_compress_duration(input_len_bytes)
+        }
+    };
+    (@last_element_count) => {
+        fn last_element_count(&self) -> usize {
+            self.inner.last_element_count()
+        }
+    };
+    (@is_lossless) => {
+        fn is_lossless(&self) -> bool {
+            self.inner.is_lossless()
+        }
+    };
+    (@backend_version) => {
+        fn backend_version(&self) -> &'static str {
+            self.inner.backend_version()
+        }
+    };
+    (@name) => {
+        fn name(&self) -> &'static str {
+            self.inner.name()
+        }
+    };
+    (@concat_frames) => {
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> crate::errors::Result<Vec<u8>> {
+            self.inner.concat_frames(frames)
+        }
+    };
+    (@decompress_view) => {
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> crate::errors::Result<&[u8]> {
+            self.inner.decompress_view(input_buf, uncompress_size)
+        }
+    };
+    (@supports_seek) => {
+        fn supports_seek(&self) -> bool {
+            self.inner.supports_seek()
+        }
+    };
+    (@decompress_block) => {
+        fn decompress_block(
+            &mut self,
+            input_buf: &[u8],
+            block_index: usize,
+            output_buf_columndata: &mut crate::data_type::ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> crate::errors::Result<usize> {
+            self.inner.decompress_block(
+                input_buf,
+                block_index,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
+    };
+    (@reset) => {
+        fn reset(&mut self) {
+            self.inner.reset()
+        }
+    };
+}
+
+/// Format version of the state [`StreamProgress::save`] serializes.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+const STREAM_STATE_VERSION: u8 = 1;
+
+/// Length of the state [`StreamProgress::save`] serializes: the format version, then
+/// the codec's config fingerprint and the uncompressed and compressed byte counts,
+/// each as big-endian u64.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+const STREAM_STATE_LEN: usize = 25;
+
+/// How far a compression written through successive `compress_stream` calls has got,
+/// which codecs whose streams are sequences of self-contained frames save as their
+/// [`Codec::save_state`].
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct StreamProgress {
+    /// Uncompressed bytes read from the inputs.
+    consumed: u64,
+    /// Compressed bytes written to the outputs.
+    written: u64,
+}
+
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+impl StreamProgress {
+    /// Runs `compress`, which compresses one frame into the writer it is passed, on
+    /// `output`, and adds the bytes it read and wrote.
+    pub(crate) fn track(
+        &mut self,
+        output: &mut dyn Write,
+        compress: impl FnOnce(&mut dyn Write) -> Result<u64>,
+    ) -> Result<u64> {
+        let mut output = CountingWriter {
+            inner: output,
+            written: 0,
+        };
+        let n = compress(&mut output)?;
+        self.consumed += n;
+        self.written += output.written;
+        Ok(n)
+    }
+
+    /// Serializes this progress for a codec whose config fingerprint is `fingerprint`.
+    pub(crate) fn save(&self, fingerprint: u64) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STREAM_STATE_LEN);
+        state.push(STREAM_STATE_VERSION);
+        state.extend_from_slice(&fingerprint.to_be_bytes());
+        state.extend_from_slice(&self.consumed.to_be_bytes());
+        state.extend_from_slice(&self.written.to_be_bytes());
+        state
+    }
+
+    /// Reads the progress [`StreamProgress::save`] serialized, checking that it was
+    /// saved by a codec whose config fingerprint is `fingerprint`.
+    pub(crate) fn restore(state: &[u8], fingerprint: u64) -> Result<Self> {
+        if state.len() != STREAM_STATE_LEN || state[0] != STREAM_STATE_VERSION {
+            return Err(general_err!(
+                "Invalid stream state of {} bytes",
+                state.len()
+            ));
+        }
+        let read_u64 = |at: usize| {
+            let mut field = [0u8; 8];
+            field.copy_from_slice(&state[at..at + 8]);
+            u64::from_be_bytes(field)
+        };
+        if read_u64(1) != fingerprint {
+            return Err(general_err!(
+                "Stream state was saved by a codec with another configuration"
+            ));
+        }
+        Ok(Self {
+            consumed: read_u64(9),
+            written: read_u64(17),
+        })
+    }
+}
+
+/// Writer counting the bytes written through it to `inner`.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    written: u64,
+}
+
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "zstd",
+    test
+))]
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(any(feature = "lz4", test))]
+thread_local! {
+    /// Version of the linked LZ4 library, formatted once per thread.
+    static LZ4_VERSION: &'static str = {
+        let version = lz4::version();
+        let version = format!(
+            "{}.{}.{}",
+            version / 10000,
+            version / 100 % 100,
+            version % 100
+        );
+        Box::leak(version.into_boxed_str())
+    };
+}
+
+/// Returns the version of the linked LZ4 library, for `Codec::backend_version`.
+#[cfg(any(feature = "lz4", test))]
+pub(crate) fn lz4_version() -> &'static str {
+    LZ4_VERSION.with(|version| *version)
+}
+
+/// Guesses the codec that produced `input_buf` from its leading magic bytes.
+///
+/// Only GZIP, ZSTD, BZIP2, XZ and framed LZ4 output start with a magic number; `None`
+/// is returned for everything else, including SNAPPY, BROTLI and LZ4_RAW output.
+pub fn sniff_codec(input_buf: &[u8]) -> Option<CodecType> {
+    match input_buf {
+        [0x1f, 0x8b, ..] => Some(CodecType::GZIP),
+        [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(CodecType::BZIP2),
+        [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(CodecType::XZ),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(CodecType::ZSTD),
+        [0x04, 0x22, 0x4d, 0x18, ..] => Some(CodecType::LZ4),
+        _ => None,
+    }
+}
+
+/// Concatenates `frames` after checking that each starts with the magic number of
+/// `codec`, for codecs whose decoders read consecutive frames.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+pub(crate) fn concat_magic_frames(codec: CodecType, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+    for (i, frame) in frames.iter().enumerate() {
+        if sniff_codec(frame) != Some(codec) {
+            return Err(general_err!(
+                "{} concat_frames: frame {} is not a {} frame",
+                codec,
+                i,
+                codec
+            ));
+        }
+    }
+    Ok(frames.concat())
+}
+
+/// Replaces a failed decompression error with a hint when `input_buf` looks like the
+/// output of another codec, e.g. `ZSTD decompress failed; buffer appears to be GZIP`.
+pub(crate) trait DecompressHint<T> {
+    fn decompress_hint(self, codec: CodecType, input_buf: &[u8]) -> Result<T>;
+}
+
+impl<T, E: Into<ParquetError>> DecompressHint<T> for std::result::Result<T, E> {
+    fn decompress_hint(self, codec: CodecType, input_buf: &[u8]) -> Result<T> {
+        self.map_err(|e| match sniff_codec(input_buf) {
+            Some(sniffed) if sniffed != codec => general_err!(
+                "{} decompress failed; buffer appears to be {}: {}",
+                codec,
+                sniffed,
+                e.into()
+            ),
+            _ => e.into(),
+        })
+    }
+}
+
+/// Number of leading input bytes `pick_level_within` compresses to time a level.
+#[cfg(any(feature = "brotli", feature = "zstd", test))]
+const BUDGET_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Picks the slowest of `levels`, ordered from slowest to fastest, whose time on a
+/// sample of `input_buf` extrapolates to the whole input within what is left of
+/// `budget`. Falls back to the fastest level.
+///
+/// Time spent on the samples counts against the budget.
+#[cfg(any(feature = "brotli", feature = "zstd", test))]
+pub(crate) fn pick_level_within<L: Copy>(
+    levels: &[L],
+    input_buf: &[u8],
+    budget: Duration,
+    mut compress_sample: impl FnMut(L, &[u8]) -> Result<()>,
+) -> Result<L> {
+    let start = Instant::now();
+    let sample = &input_buf[..input_buf.len().min(BUDGET_SAMPLE_SIZE)];
+    let scale = input_buf.len() as f64 / sample.len().max(1) as f64;
+    let (fastest, slower) = match levels.split_last() {
+        Some(split) => split,
+        None => return Err(general_err!("No compression levels to choose from")),
+    };
+    for &level in slower {
+        let sample_start = Instant::now();
+        compress_sample(level, sample)?;
+        let projected = sample_start.elapsed().mul_f64(scale);
+        if start.elapsed() + projected <= budget {
+            return Ok(level);
+        }
+    }
+    Ok(*fastest)
+}
+
+/// Number of u32 values compressed to calibrate `estimate_compress_duration`.
+const CALIBRATION_VALUES: usize = 16 * 1024;
+
+/// Throughput, in bytes per second, assumed for a codec that fails to calibrate.
+const FALLBACK_THROUGHPUT: f64 = 10e6;
+
+thread_local! {
+    /// Compression throughput in bytes per second, by codec type name.
+    static THROUGHPUT: RefCell<HashMap<&'static str, f64>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the compression throughput, in bytes per second, of the codec type `key`,
+/// timing a codec from `new_codec` on a sample the first time it is asked for.
+pub(crate) fn calibrated_throughput(
+    key: &'static str,
+    new_codec: impl FnOnce() -> Box<dyn Codec>,
+) -> f64 {
+    if let Some(throughput) = THROUGHPUT.with(|t| t.borrow().get(key).copied()) {
+        return throughput;
+    }
+    let throughput = measure_throughput(new_codec()).unwrap_or(FALLBACK_THROUGHPUT);
+    THROUGHPUT.with(|t| t.borrow_mut().insert(key, throughput));
+    throughput
+}
+
+/// Times `codec` compressing a sample of mildly compressible u32 values.
+fn measure_throughput(mut codec: Box<dyn Codec>) -> Result<f64> {
+    let sample = ColumnData::VecU32(
+        (0..CALIBRATION_VALUES as u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) >> 20)
+            .collect(),
+    );
+    let mut output = Vec::new();
+    // the first run warms up the codec's tables and buffers
+    codec.compress(&sample, &mut output)?;
+    output.clear();
+    let start = Instant::now();
+    codec.compress(&sample, &mut output)?;
+    let elapsed = start.elapsed().max(Duration::from_micros(1));
+    Ok((CALIBRATION_VALUES * 4) as f64 / elapsed.as_secs_f64())
+}
+
+/// Decompresses `input_buf` with `codec` into `view`, the emptied buffer of its
+/// `decompress_view`, and returns the buffer for the codec to keep.
+pub(crate) fn decompress_into_view(
+    codec: &mut dyn Codec,
+    mut view: Vec<u8>,
+    input_buf: &[u8],
+    uncompress_size: Option<usize>,
+) -> Result<Vec<u8>> {
+    view.clear();
+    let mut bytes = ColumnData::VecU8(view);
+    codec.decompress(input_buf, &mut bytes, uncompress_size)?;
+    match bytes {
+        ColumnData::VecU8(x) => Ok(x),
+        _ => unreachable!(),
+    }
+}
+
+/// Implements `Codec::decompress_with_bytes` for codecs that decode typed values
+/// directly, by converting the decoded values to bytes.
+pub(crate) fn decompress_typed_with_bytes(
+    codec: &mut dyn Codec,
+    input_buf: &[u8],
+    output_buf_columndata: &mut ColumnData,
+    uncompress_size: Option<usize>,
+) -> Result<Vec<u8>> {
+    let mut decoded = output_buf_columndata.slice(0, 0);
+    codec.decompress(input_buf, &mut decoded, uncompress_size)?;
+    let mut bytes = Vec::new();
+    decoded.convert_to_u8(&mut bytes)?;
+    output_buf_columndata.extend_from(&decoded)?;
+    Ok(bytes)
+}
+
+/// Number of decompressed bytes `decompress_reader` converts at a time; a multiple of
+/// every element width.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Reads the decompressed byte stream `reader` to its end, converting it into
+/// `output_buf_columndata` one chunk at a time.
+///
+/// Returns the total number of bytes read.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "lz4",
+    feature = "zstd",
+    test
+))]
+pub(crate) fn decompress_reader<R: Read>(
+    mut reader: R,
+    output_buf_columndata: &mut ColumnData,
+    codec: &str,
+) -> Result<usize> {
+    let typename = output_buf_columndata.typename();
+    let mut chunk = Vec::with_capacity(STREAM_CHUNK_SIZE);
+    let mut total = 0;
+    loop {
+        chunk.clear();
+        let n = (&mut reader)
+            .take(STREAM_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        output_buf_columndata
+            .convert_from_u8(&chunk)
+            .conversion_context(codec, "decompress", typename)?;
+        total += n;
+        if n < STREAM_CHUNK_SIZE {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+/// Returns an empty buffer for a codec's temporary bytes, such as the serialized input
+/// of `compress`.
+#[cfg(not(feature = "allocator"))]
+pub(crate) fn scratch_bytes() -> Vec<u8> {
+    Vec::new()
+}
+
+/// Returns an empty buffer for a codec's temporary bytes, such as the serialized input
+/// of `compress`, taken from the allocator set in the codec's `CodecOptions`.
+#[cfg(feature = "allocator")]
+pub(crate) fn scratch_bytes() -> allocator_api2::vec::Vec<u8, ScratchAllocator> {
+    allocator_api2::vec::Vec::new_in(ScratchAllocator::current())
+}
+
+/// Reads `reader` to its end, appending the bytes to `output_buf`, and returns whether
+/// the stream was cut short.
+///
+/// With `allow_partial`, a read error such as a truncated stream keeps the bytes decoded
+/// before it, trimmed to whole elements of `width` bytes, instead of being returned.
+#[cfg(any(
+    feature = "flate2",
+    feature = "bzip2",
+    feature = "xz",
+    feature = "brotli",
+    feature = "zstd",
+    test
+))]
+pub(crate) fn read_to_end_partial<R: Read>(
+    reader: &mut R,
+    output_buf: &mut Vec<u8>,
+    width: usize,
+    allow_partial: bool,
+) -> Result<bool> {
+    match reader.read_to_end(output_buf) {
+        Ok(_) => Ok(false),
+        Err(_) if allow_partial => {
+            output_buf.truncate(output_buf.len() / width * width);
+            Ok(true)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// What is known about the order of the values in the columns being compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingHint {
+    /// Nothing is known about the order.
+    None,
+    /// Values are in ascending or descending order.
+    Sorted,
+    /// Values are mostly in order, with occasional exceptions.
+    MostlySorted,
+}
+
+impl EncodingHint {
+    /// Returns the delta encoding order that suits columns with this hint, if any.
+    fn delta_order(&self) -> Option<usize> {
+        match self {
+            EncodingHint::None => None,
+            EncodingHint::Sorted | EncodingHint::MostlySorted => Some(1),
+        }
+    }
+}
+
+/// Level ZSTD compresses at unless `CodecOptionsBuilder::set_zstd_level` says
+/// otherwise, chosen for compression speed.
+const DEFAULT_ZSTD_LEVEL: i32 = 1;
+
+/// Level GZIP compresses at unless `CodecOptionsBuilder::set_gzip_level` says
+/// otherwise, `flate2::Compression::default()`.
+const DEFAULT_GZIP_LEVEL: u32 = 6;
+
+/// Level BROTLI compresses at unless `CodecOptionsBuilder::set_brotli_level` says
+/// otherwise, chosen for compression speed.
+const DEFAULT_BROTLI_LEVEL: u32 = 1;
+
+/// Preset XZ compresses with unless `CodecOptionsBuilder::set_xz_preset` says
+/// otherwise, the default of the `xz` tool.
+const DEFAULT_XZ_PRESET: u32 = 6;
+
+/// Level QCOM compresses at unless `CodecOptionsBuilder::set_qcom_level` says
+/// otherwise, `q_compress::DEFAULT_COMPRESSION_LEVEL`.
+const DEFAULT_QCOM_LEVEL: usize = 8;
+
+/// Byte order values are written in before byte codecs compress them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+    /// Byte order of the platform running the codec, e.g. `Little` on x86-64.
+    ///
+    /// Only use this when compressed columns never leave the platform; they cannot
+    /// be read back on a platform of the other byte order.
+    Native,
+}
+
+impl Endianness {
+    /// Returns whether values are written least significant byte first, resolving
+    /// `Native` to the byte order of this platform.
+    pub fn is_little(self) -> bool {
+        match self {
+            Endianness::Big => false,
+            Endianness::Little => true,
+            Endianness::Native => cfg!(target_endian = "little"),
+        }
+    }
+}
+
+/// Returns the native byte order of the platform named by the target triple
+/// `target`, e.g. `x86_64-unknown-linux-gnu`, to configure `float_endianness` and
+/// `int_endianness` for consumers on that platform.
+///
+/// Only the architecture, the first component of the triple, is inspected. Unknown
+/// architectures map to `Endianness::Big`, the portable default.
+pub fn endianness_for_target(target: &str) -> Endianness {
+    let arch = target.split('-').next().unwrap_or(target);
+    // explicitly big-endian variants of little-endian families, e.g. aarch64_be
+    if arch.ends_with("_be") || arch.starts_with("armeb") || arch.starts_with("thumbeb") {
+        return Endianness::Big;
+    }
+    // explicitly little-endian variants of big-endian families, e.g. mipsel
+    if arch.ends_with("el") || arch.ends_with("le") {
+        return Endianness::Little;
+    }
+    const LITTLE_ENDIAN_ARCHS: [&str; 10] = [
+        "x86", "i386", "i586", "i686", "arm", "thumb", "aarch64", "riscv", "wasm",
+        "loongarch",
+    ];
+    if LITTLE_ENDIAN_ARCHS.iter().any(|prefix| arch.starts_with(prefix)) {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    }
+}
+
+/// Struct to hold `Codec` creation options.
+///
+/// Build it with [`CodecOptionsBuilder`], or derive a variant of existing options with
+/// the non-consuming `with_*` methods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodecOptions {
+    /// Whether or not to fallback to other LZ4 older implementations on error in LZ4_HADOOP.
+    backward_compatible_lz4: bool,
+    /// Whether the same input must always compress to byte-identical output.
+    deterministic: bool,
+    /// Whether to decompress every compressed buffer again and compare it to the input.
+    verify_roundtrip: bool,
+    /// What is known about the order of the values being compressed.
+    encoding_hint: EncodingHint,
+    /// Compression ratio below which columns are stored uncompressed.
+    min_compression_ratio: Option<f64>,
+    /// Whether streaming codecs return the values decoded before a truncation.
+    allow_partial_decompress: bool,
+    /// Whether multi-frame decompression skips corrupt frames instead of failing.
+    skip_corrupt_frames: bool,
+    /// Whether float columns are split into sign, exponent and mantissa streams.
+    float_component_split: bool,
+    /// Number of times a call failing with a transient error is retried.
+    retry_on_transient: u8,
+    /// Byte order of floating point values.
+    float_endianness: Endianness,
+    /// Byte order of integer values.
+    int_endianness: Endianness,
+    /// Whether block codecs split inputs larger than their block limit.
+    auto_block_split: bool,
+    /// Bytes compressed ahead of every input and stripped again on decompression.
+    context_prefix: Option<Vec<u8>>,
+    /// Whether repeated blocks of the value bytes are replaced by references.
+    intra_dedup: bool,
+    /// Dictionary ZSTD compresses and decompresses with.
+    zstd_dictionary: Option<Vec<u8>>,
+    /// Level ZSTD compresses at.
+    zstd_level: i32,
+    /// Level GZIP compresses at.
+    gzip_level: u32,
+    /// Level BROTLI compresses at.
+    brotli_level: u32,
+    /// Preset XZ compresses with.
+    xz_preset: u32,
+    /// Level QCOM compresses at.
+    qcom_level: usize,
+    /// Number of bits integer values are packed into.
+    bit_pack_width: Option<u8>,
+    /// Whether every float NaN is rewritten to the canonical NaN before compression.
+    canonicalize_nan: bool,
+    /// Whether to warn when byte inputs already look compressed.
+    warn_on_precompressed: bool,
+    /// Largest number of bytes a single `compress` call may produce.
+    max_output_size: Option<usize>,
+    /// Allocator codec scratch buffers are taken from, instead of the global one.
+    #[cfg(feature = "allocator")]
+    allocator: Option<ScratchAllocator>,
+}
+
+impl Default for CodecOptions {
+    fn default() -> Self {
+        CodecOptionsBuilder::default().build()
+    }
+}
+
+impl CodecOptions {
+    /// Builds options from `PARQUET_*` environment variables, using the default for
+    /// every variable that is unset:
+    ///
+    /// - `PARQUET_BACKWARD_COMPATIBLE_LZ4`, `PARQUET_DETERMINISTIC`,
+    ///   `PARQUET_VERIFY_ROUNDTRIP`, `PARQUET_ALLOW_PARTIAL_DECOMPRESS`,
+    ///   `PARQUET_SKIP_CORRUPT_FRAMES`, `PARQUET_FLOAT_COMPONENT_SPLIT`,
+    ///   `PARQUET_AUTO_BLOCK_SPLIT`, `PARQUET_INTRA_DEDUP`, `PARQUET_CANONICALIZE_NAN`
+    ///   and `PARQUET_WARN_ON_PRECOMPRESSED`: `true` or `false`
+    /// - `PARQUET_ENCODING_HINT`: `none`, `sorted` or `mostly_sorted`
+    /// - `PARQUET_MIN_COMPRESSION_RATIO`: a number
+    /// - `PARQUET_MAX_OUTPUT_SIZE`: a number of bytes
+    /// - `PARQUET_ZSTD_LEVEL`: a ZSTD level from 1 to 22
+    /// - `PARQUET_GZIP_LEVEL`: a GZIP level from 0 to 9
+    /// - `PARQUET_BROTLI_LEVEL`: a BROTLI level from 0 to 11
+    /// - `PARQUET_XZ_PRESET`: an XZ preset from 0 to 9
+    /// - `PARQUET_QCOM_LEVEL`: a QCOM level from 0 to 12
+    /// - `PARQUET_BIT_PACK_WIDTH`: a number of bits from 1 to 64
+    /// - `PARQUET_RETRY_ON_TRANSIENT`: a retry count from 0 to 255
+    /// - `PARQUET_FLOAT_ENDIANNESS` and `PARQUET_INT_ENDIANNESS`: `big`, `little` or
+    ///   `native`
+    /// - `PARQUET_CONTEXT_PREFIX`: text whose UTF-8 bytes are the prefix
+    ///
+    /// Returns an error naming the variable if a value cannot be parsed.
+    pub fn from_env() -> Result<CodecOptions> {
+        let mut builder = CodecOptionsBuilder::default();
+        if let Some(value) = env_option("PARQUET_BACKWARD_COMPATIBLE_LZ4", str::parse)? {
+            builder = builder.set_backward_compatible_lz4(value);
+        }
+        if let Some(value) = env_option("PARQUET_DETERMINISTIC", str::parse)? {
+            builder = builder.set_deterministic(value);
+        }
+        if let Some(value) = env_option("PARQUET_VERIFY_ROUNDTRIP", str::parse)? {
+            builder = builder.set_verify_roundtrip(value);
+        }
+        if let Some(value) = env_option("PARQUET_ENCODING_HINT", |value| match value {
+            "none" => Ok(EncodingHint::None),
+            "sorted" => Ok(EncodingHint::Sorted),
+            "mostly_sorted" => Ok(EncodingHint::MostlySorted),
+            _ => Err("expected none, sorted or mostly_sorted"),
+        })? {
+            builder = builder.set_encoding_hint(value);
+        }
+        if let Some(value) = env_option("PARQUET_MIN_COMPRESSION_RATIO", str::parse::<f64>)? {
+            builder = builder.set_min_compression_ratio(Some(value));
+        }
+        if let Some(value) = env_option("PARQUET_MAX_OUTPUT_SIZE", str::parse)? {
+            builder = builder.set_max_output_size(Some(value));
+        }
+        if let Some(value) = env_option("PARQUET_ZSTD_LEVEL", str::parse)? {
+            builder = builder.set_zstd_level(value);
+        }
+        if let Some(value) = env_option("PARQUET_GZIP_LEVEL", str::parse)? {
+            builder = builder.set_gzip_level(value);
+        }
+        if let Some(value) = env_option("PARQUET_BROTLI_LEVEL", str::parse)? {
+            builder = builder.set_brotli_level(value);
+        }
+        if let Some(value) = env_option("PARQUET_XZ_PRESET", str::parse)? {
+            builder = builder.set_xz_preset(value);
+        }
+        if let Some(value) = env_option("PARQUET_QCOM_LEVEL", str::parse)? {
+            builder = builder.set_qcom_level(value);
+        }
+        if let Some(value) = env_option("PARQUET_BIT_PACK_WIDTH", str::parse)? {
+            builder = builder.set_bit_pack_width(Some(value));
+        }
+        if let Some(value) = env_option("PARQUET_ALLOW_PARTIAL_DECOMPRESS", str::parse)? {
+            builder = builder.set_allow_partial_decompress(value);
+        }
+        if let Some(value) = env_option("PARQUET_SKIP_CORRUPT_FRAMES", str::parse)? {
+            builder = builder.set_skip_corrupt_frames(value);
+        }
+        if let Some(value) = env_option("PARQUET_FLOAT_COMPONENT_SPLIT", str::parse)? {
+            builder = builder.set_float_component_split(value);
+        }
+        if let Some(value) = env_option("PARQUET_AUTO_BLOCK_SPLIT", str::parse)? {
+            builder = builder.set_auto_block_split(value);
+        }
+        if let Some(value) = env_option("PARQUET_INTRA_DEDUP", str::parse)? {
+            builder = builder.set_intra_dedup(value);
+        }
+        if let Some(value) = env_option("PARQUET_CANONICALIZE_NAN", str::parse)? {
+            builder = builder.set_canonicalize_nan(value);
+        }
+        if let Some(value) = env_option("PARQUET_WARN_ON_PRECOMPRESSED", str::parse)? {
+            builder = builder.set_warn_on_precompressed(value);
+        }
+        if let Some(value) = env_option("PARQUET_CONTEXT_PREFIX", |value| {
+            Ok::<_, ParquetError>(value.as_bytes().to_vec())
+        })? {
+            builder = builder.set_context_prefix(Some(value));
+        }
+        if let Some(value) = env_option("PARQUET_RETRY_ON_TRANSIENT", str::parse)? {
+            builder = builder.set_retry_on_transient(value);
+        }
+        if let Some(value) = env_option("PARQUET_FLOAT_ENDIANNESS", parse_endianness)? {
+            builder = builder.set_float_endianness(value);
+        }
+        if let Some(value) = env_option("PARQUET_INT_ENDIANNESS", parse_endianness)? {
+            builder = builder.set_int_endianness(value);
+        }
+        Ok(builder.build())
+    }
+
+    /// Checks that the options do not contradict each other, so that no option is
+    /// silently ignored or corrupts data. `create_codec` calls this.
+    ///
+    /// Returns an error describing the first problem found:
+    ///
+    /// - the minimum compression ratio is not a positive finite number
+    /// - partial decompression is combined with a minimum compression ratio, since
+    ///   columns stored uncompressed cannot be partially decompressed
+    /// - little-endian floats are combined with float component splitting, which
+    ///   writes floats in its own layout
+    pub fn validate(&self) -> Result<()> {
+        if let Some(min_ratio) = self.min_compression_ratio {
+            if !(min_ratio.is_finite() && min_ratio > 0.0) {
+                return Err(general_err!(
+                    "Minimum compression ratio must be a positive number, got {}",
+                    min_ratio
+                ));
+            }
+            if self.allow_partial_decompress {
+                return Err(general_err!(
+                    "allow_partial_decompress cannot be combined with a minimum \
+                     compression ratio: stored columns are never partially decompressed"
+                ));
+            }
+        }
+        if !(1..=22).contains(&self.zstd_level) {
+            return Err(general_err!(
+                "ZSTD level must be between 1 and 22, got {}",
+                self.zstd_level
+            ));
+        }
+        if self.gzip_level > 9 {
+            return Err(general_err!(
+                "GZIP level must be between 0 and 9, got {}",
+                self.gzip_level
+            ));
+        }
+        if self.brotli_level > 11 {
+            return Err(general_err!(
+                "BROTLI level must be between 0 and 11, got {}",
+                self.brotli_level
+            ));
+        }
+        if self.xz_preset > 9 {
+            return Err(general_err!(
+                "XZ preset must be between 0 and 9, got {}",
+                self.xz_preset
+            ));
+        }
+        if self.qcom_level > 12 {
+            return Err(general_err!(
+                "QCOM level must be between 0 and 12, got {}",
+                self.qcom_level
+            ));
+        }
+        if let Some(width) = self.bit_pack_width {
+            if !(1..=64).contains(&width) {
+                return Err(general_err!(
+                    "Bit packing width must be between 1 and 64, got {}",
+                    width
+                ));
+            }
+            if self.int_endianness.is_little() {
+                return Err(general_err!(
+                    "int_endianness cannot be combined with bit_pack_width: packed \
+                     integers are written in their own layout"
+                ));
+            }
+            if self.encoding_hint.delta_order().is_some() {
+                return Err(general_err!(
+                    "A sorted encoding hint cannot be combined with bit_pack_width: \
+                     deltas may not fit the packing width"
+                ));
+            }
+        }
+        if self.float_component_split && self.float_endianness.is_little() {
+            return Err(general_err!(
+                "float_endianness cannot be combined with float_component_split: split \
+                 floats are written in their own layout"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of these options with `backward_compatible_lz4` set to `value`, see
+    /// [`CodecOptionsBuilder::set_backward_compatible_lz4`].
+    pub fn with_backward_compatible_lz4(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            backward_compatible_lz4: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `deterministic` set to `value`, see
+    /// [`CodecOptionsBuilder::set_deterministic`].
+    pub fn with_deterministic(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            deterministic: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `verify_roundtrip` set to `value`, see
+    /// [`CodecOptionsBuilder::set_verify_roundtrip`].
+    pub fn with_verify_roundtrip(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            verify_roundtrip: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `encoding_hint` set to `value`, see
+    /// [`CodecOptionsBuilder::set_encoding_hint`].
+    pub fn with_encoding_hint(&self, value: EncodingHint) -> CodecOptions {
+        CodecOptions {
+            encoding_hint: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `min_compression_ratio` set to `value`, see
+    /// [`CodecOptionsBuilder::set_min_compression_ratio`].
+    pub fn with_min_compression_ratio(&self, value: Option<f64>) -> CodecOptions {
+        CodecOptions {
+            min_compression_ratio: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `allow_partial_decompress` set to `value`, see
+    /// [`CodecOptionsBuilder::set_allow_partial_decompress`].
+    pub fn with_allow_partial_decompress(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            allow_partial_decompress: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `skip_corrupt_frames` set to `value`, see
+    /// [`CodecOptionsBuilder::set_skip_corrupt_frames`].
+    pub fn with_skip_corrupt_frames(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            skip_corrupt_frames: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `float_component_split` set to `value`, see
+    /// [`CodecOptionsBuilder::set_float_component_split`].
+    pub fn with_float_component_split(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            float_component_split: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `auto_block_split` set to `value`, see
+    /// [`CodecOptionsBuilder::set_auto_block_split`].
+    pub fn with_auto_block_split(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            auto_block_split: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `intra_dedup` set to `value`, see
+    /// [`CodecOptionsBuilder::set_intra_dedup`].
+    pub fn with_intra_dedup(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            intra_dedup: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `context_prefix` set to `value`, see
+    /// [`CodecOptionsBuilder::set_context_prefix`].
+    pub fn with_context_prefix(&self, value: Option<Vec<u8>>) -> CodecOptions {
+        CodecOptions {
+            context_prefix: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `zstd_dictionary` set to `value`, see
+    /// [`CodecOptionsBuilder::set_zstd_dictionary`].
+    pub fn with_zstd_dictionary(&self, value: Option<Vec<u8>>) -> CodecOptions {
+        CodecOptions {
+            zstd_dictionary: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `zstd_level` set to `value`, see
+    /// [`CodecOptionsBuilder::set_zstd_level`].
+    pub fn with_zstd_level(&self, value: i32) -> CodecOptions {
+        CodecOptions {
+            zstd_level: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `gzip_level` set to `value`, see
+    /// [`CodecOptionsBuilder::set_gzip_level`].
+    pub fn with_gzip_level(&self, value: u32) -> CodecOptions {
+        CodecOptions {
+            gzip_level: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `brotli_level` set to `value`, see
+    /// [`CodecOptionsBuilder::set_brotli_level`].
+    pub fn with_brotli_level(&self, value: u32) -> CodecOptions {
+        CodecOptions {
+            brotli_level: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `xz_preset` set to `value`, see
+    /// [`CodecOptionsBuilder::set_xz_preset`].
+    pub fn with_xz_preset(&self, value: u32) -> CodecOptions {
+        CodecOptions {
+            xz_preset: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `qcom_level` set to `value`, see
+    /// [`CodecOptionsBuilder::set_qcom_level`].
+    pub fn with_qcom_level(&self, value: usize) -> CodecOptions {
+        CodecOptions {
+            qcom_level: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `bit_pack_width` set to `value`, see
+    /// [`CodecOptionsBuilder::set_bit_pack_width`].
+    pub fn with_bit_pack_width(&self, value: Option<u8>) -> CodecOptions {
+        CodecOptions {
+            bit_pack_width: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `canonicalize_nan` set to `value`, see
+    /// [`CodecOptionsBuilder::set_canonicalize_nan`].
+    pub fn with_canonicalize_nan(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            canonicalize_nan: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `warn_on_precompressed` set to `value`,
+    /// see [`CodecOptionsBuilder::set_warn_on_precompressed`].
+    pub fn with_warn_on_precompressed(&self, value: bool) -> CodecOptions {
+        CodecOptions {
+            warn_on_precompressed: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `max_output_size` set to `value`, see
+    /// [`CodecOptionsBuilder::set_max_output_size`].
+    pub fn with_max_output_size(&self, value: Option<usize>) -> CodecOptions {
+        CodecOptions {
+            max_output_size: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `retry_on_transient` set to `value`, see
+    /// [`CodecOptionsBuilder::set_retry_on_transient`].
+    pub fn with_retry_on_transient(&self, value: u8) -> CodecOptions {
+        CodecOptions {
+            retry_on_transient: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `float_endianness` set to `value`, see
+    /// [`CodecOptionsBuilder::set_float_endianness`].
+    pub fn with_float_endianness(&self, value: Endianness) -> CodecOptions {
+        CodecOptions {
+            float_endianness: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `int_endianness` set to `value`, see
+    /// [`CodecOptionsBuilder::set_int_endianness`].
+    pub fn with_int_endianness(&self, value: Endianness) -> CodecOptions {
+        CodecOptions {
+            int_endianness: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with both `int_endianness` and
+    /// `float_endianness` set to `value`, see [`CodecOptionsBuilder::set_endianness`].
+    pub fn with_endianness(&self, value: Endianness) -> CodecOptions {
+        CodecOptions {
+            int_endianness: value,
+            float_endianness: value,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy of these options with `allocator` set to `value`, see
+    /// [`CodecOptionsBuilder::set_allocator`].
+    #[cfg(feature = "allocator")]
+    pub fn with_allocator(&self, value: ScratchAllocator) -> CodecOptions {
+        CodecOptions {
+            allocator: Some(value),
+            ..self.clone()
+        }
+    }
+}
+
+/// Parses the byte order names accepted by `CodecOptions::from_env`.
+fn parse_endianness(value: &str) -> std::result::Result<Endianness, &'static str> {
+    match value {
+        "big" => Ok(Endianness::Big),
+        "little" => Ok(Endianness::Little),
+        "native" => Ok(Endianness::Native),
+        _ => Err("expected big, little or native"),
+    }
+}
+
+/// Reads the environment variable `name` and parses it with `parse`, returning `None`
+/// if it is unset.
+fn env_option<T, E: std::fmt::Display>(
+    name: &str,
+    parse: impl FnOnce(&str) -> std::result::Result<T, E>,
+) -> Result<Option<T>> {
+    match std::env::var(name) {
+        Ok(value) => parse(value.trim())
+            .map(Some)
+            .map_err(|e| general_err!("Invalid {} value {:?}: {}", name, value, e)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(general_err!("Invalid {} value: {}", name, e)),
+    }
+}
+
+pub struct CodecOptionsBuilder {
+    /// Whether or not to fallback to other LZ4 older implementations on error in LZ4_HADOOP.
+    backward_compatible_lz4: bool,
+    /// Whether the same input must always compress to byte-identical output.
+    deterministic: bool,
+    /// Whether to decompress every compressed buffer again and compare it to the input.
+    verify_roundtrip: bool,
+    /// What is known about the order of the values being compressed.
+    encoding_hint: EncodingHint,
+    /// Compression ratio below which columns are stored uncompressed.
+    min_compression_ratio: Option<f64>,
+    /// Whether streaming codecs return the values decoded before a truncation.
+    allow_partial_decompress: bool,
+    /// Whether multi-frame decompression skips corrupt frames instead of failing.
+    skip_corrupt_frames: bool,
+    /// Whether float columns are split into sign, exponent and mantissa streams.
+    float_component_split: bool,
+    /// Number of times a call failing with a transient error is retried.
+    retry_on_transient: u8,
+    /// Byte order of floating point values.
+    float_endianness: Endianness,
+    /// Byte order of integer values.
+    int_endianness: Endianness,
+    /// Whether block codecs split inputs larger than their block limit.
+    auto_block_split: bool,
+    /// Bytes compressed ahead of every input and stripped again on decompression.
+    context_prefix: Option<Vec<u8>>,
+    /// Whether repeated blocks of the value bytes are replaced by references.
+    intra_dedup: bool,
+    /// Dictionary ZSTD compresses and decompresses with.
+    zstd_dictionary: Option<Vec<u8>>,
+    /// Level ZSTD compresses at.
+    zstd_level: i32,
+    /// Level GZIP compresses at.
+    gzip_level: u32,
+    /// Level BROTLI compresses at.
+    brotli_level: u32,
+    /// Preset XZ compresses with.
+    xz_preset: u32,
+    /// Level QCOM compresses at.
+    qcom_level: usize,
+    /// Number of bits integer values are packed into.
+    bit_pack_width: Option<u8>,
+    /// Whether every float NaN is rewritten to the canonical NaN before compression.
+    canonicalize_nan: bool,
+    /// Whether to warn when byte inputs already look compressed.
+    warn_on_precompressed: bool,
+    /// Largest number of bytes a single `compress` call may produce.
+    max_output_size: Option<usize>,
+    /// Allocator codec scratch buffers are taken from, instead of the global one.
+    #[cfg(feature = "allocator")]
+    allocator: Option<ScratchAllocator>,
+}
+
+impl Default for CodecOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            backward_compatible_lz4: true,
+            deterministic: false,
+            verify_roundtrip: false,
+            encoding_hint: EncodingHint::None,
+            min_compression_ratio: None,
+            allow_partial_decompress: false,
+            skip_corrupt_frames: false,
+            float_component_split: false,
+            retry_on_transient: 0,
+            float_endianness: Endianness::Big,
+            int_endianness: Endianness::Big,
+            auto_block_split: true,
+            context_prefix: None,
+            intra_dedup: false,
+            zstd_dictionary: None,
+            zstd_level: DEFAULT_ZSTD_LEVEL,
+            gzip_level: DEFAULT_GZIP_LEVEL,
+            brotli_level: DEFAULT_BROTLI_LEVEL,
+            xz_preset: DEFAULT_XZ_PRESET,
+            qcom_level: DEFAULT_QCOM_LEVEL,
+            bit_pack_width: None,
+            canonicalize_nan: false,
+            warn_on_precompressed: false,
+            max_output_size: None,
+            #[cfg(feature = "allocator")]
+            allocator: None,
+        }
+    }
+}
+
+impl CodecOptionsBuilder {
+    /// Enable/disable backward compatible LZ4.
+    ///
+    /// If backward compatible LZ4 is enable, on LZ4_HADOOP error it will fallback
+    /// to the older versions LZ4 algorithms. That is LZ4_FRAME, for backward compatibility
+    /// with files generated by older versions of this library, and LZ4_RAW, for backward
+    /// compatibility with files generated by older versions of parquet-cpp.
+    ///
+    /// If backward compatible LZ4 is disabled, on LZ4_HADOOP error it will return the error.
+    pub fn set_backward_compatible_lz4(mut self, value: bool) -> CodecOptionsBuilder {
+        self.backward_compatible_lz4 = value;
+        self
+    }
+
+    /// Require byte-identical output for identical input.
+    ///
+    /// All codecs currently compress single-threaded with fixed parameters and are
+    /// deterministic. The one exception is `Codec::compress_within`, whose levels
+    /// depend on timing: with this flag set it returns an error instead.
+    pub fn set_deterministic(mut self, value: bool) -> CodecOptionsBuilder {
+        self.deterministic = value;
+        self
+    }
+
+    /// Enable/disable round-trip verification.
+    ///
+    /// If enabled, every `compress` call decompresses its output again and fails with
+    /// "roundtrip verification failed" unless the result matches the input, catching
+    /// codec bugs before the data is persisted. This roughly doubles the CPU cost of
+    /// compression, so it is disabled by default.
+    pub fn set_verify_roundtrip(mut self, value: bool) -> CodecOptionsBuilder {
+        self.verify_roundtrip = value;
+        self
+    }
+
+    /// Sets what is known about the order of the values being compressed.
+    ///
+    /// With a `Sorted` or `MostlySorted` hint, QCOM uses first-order delta encoding and
+    /// the byte codecs delta-encode integer columns before compressing them. Both hints
+    /// currently behave the same. The hint only affects the compression ratio: data
+    /// that does not match it still round-trips exactly. Byte codecs must decompress
+    /// with the same hint they compressed with.
+    pub fn set_encoding_hint(mut self, value: EncodingHint) -> CodecOptionsBuilder {
+        self.encoding_hint = value;
+        self
+    }
+
+    /// Sets the compression ratio, uncompressed size over compressed size, a column
+    /// must reach to be stored compressed.
+    ///
+    /// Columns that compress worse are stored uncompressed behind a one-byte marker,
+    /// and `Codec::last_was_stored` reports it. Columns must be decompressed with the
+    /// same setting they were compressed with. Disabled by default.
+    pub fn set_min_compression_ratio(mut self, value: Option<f64>) -> CodecOptionsBuilder {
+        self.min_compression_ratio = value;
+        self
+    }
+
+    /// Enable/disable best-effort decompression of truncated streams.
+    ///
+    /// If enabled, the streaming codecs GZIP, BROTLI and ZSTD return the whole values
+    /// decoded before a truncated or corrupt stream ends, instead of an error, and
+    /// `Codec::last_was_truncated` reports it. This helps recover data from partially
+    /// written files. Disabled by default.
+    pub fn set_allow_partial_decompress(mut self, value: bool) -> CodecOptionsBuilder {
+        self.allow_partial_decompress = value;
+        self
+    }
+
+    /// Enable/disable skipping corrupt frames of multi-frame LZ4_HADOOP buffers.
+    ///
+    /// If enabled, a frame that fails to decompress is skipped with a logged warning
+    /// instead of failing the whole buffer, the values of the other frames are still
+    /// returned, and `Codec::last_skipped_frames` reports which frames were skipped.
+    /// This helps recover data from damaged files. Frames whose size prefix is
+    /// damaged cannot be located and still fail the buffer. Disabled by default.
+    pub fn set_skip_corrupt_frames(mut self, value: bool) -> CodecOptionsBuilder {
+        self.skip_corrupt_frames = value;
+        self
+    }
+
+    /// Enable/disable splitting float columns into sign, exponent and mantissa streams.
+    ///
+    /// If enabled, the byte codecs compress the sign bits, the exponents and the
+    /// mantissas of `f32` and `f64` columns as three separate streams, which often
+    /// compress better than whole floats. Values are recombined bit-exactly. QCOM and
+    /// other column types are unaffected. Columns must be decompressed with the same
+    /// setting they were compressed with. Disabled by default.
+    pub fn set_float_component_split(mut self, value: bool) -> CodecOptionsBuilder {
+        self.float_component_split = value;
+        self
+    }
+
+    /// Enable/disable splitting inputs larger than a block codec's limit.
+    ///
+    /// If enabled, SNAPPY, LZ4 and LZ4_RAW compress inputs larger than
+    /// `Codec::max_input_len` as several length-prefixed blocks, which `decompress`
+    /// reassembles when given the uncompressed size. Smaller inputs are compressed as
+    /// a single block as usual. Enabled by default.
+    pub fn set_auto_block_split(mut self, value: bool) -> CodecOptionsBuilder {
+        self.auto_block_split = value;
+        self
+    }
+
+    /// Enable/disable replacing repeated blocks of a column's bytes by references.
+    ///
+    /// If enabled, the byte codecs cut the value bytes into 1 KiB blocks and store
+    /// each block repeating an earlier one as a reference to it, in a table ahead of
+    /// the compressed remaining bytes. This shrinks columns with large exact repeats
+    /// further apart than the codec's window. Ignored by QCOM. Columns must be
+    /// decompressed with the same setting they were compressed with. Disabled by
+    /// default.
+    pub fn set_intra_dedup(mut self, value: bool) -> CodecOptionsBuilder {
+        self.intra_dedup = value;
+        self
+    }
+
+    /// Sets bytes compressed ahead of every input, such as the column name, and
+    /// checked and stripped again by `decompress`. Defaults to `None`.
+    ///
+    /// The prefix gives the compressor some context shared by similar columns and marks
+    /// the output for debugging. It only improves the ratio for a codec using a
+    /// dictionary it can match against; otherwise it just adds its compressed size.
+    /// Ignored by QCOM, which compresses typed values rather than bytes.
+    pub fn set_context_prefix(mut self, value: Option<Vec<u8>>) -> CodecOptionsBuilder {
+        self.context_prefix = value;
+        self
+    }
+
+    /// Sets the dictionary ZSTD compresses and decompresses with: either a dictionary
+    /// trained by zstd or, without its magic number, raw content to match against.
+    /// Defaults to `None`.
+    ///
+    /// Buffers compressed with a dictionary can only be decompressed with the same
+    /// dictionary. `create_codec` returns an error if zstd cannot load it.
+    pub fn set_zstd_dictionary(mut self, value: Option<Vec<u8>>) -> CodecOptionsBuilder {
+        self.zstd_dictionary = value;
+        self
+    }
+
+    /// Sets the level ZSTD compresses at, from 1, the fastest, to 22, the strongest.
+    /// Defaults to 1.
+    ///
+    /// `try_build` and `create_codec` return an error for levels outside that range.
+    /// The level does not affect decompression, nor `compress_within`, which picks its
+    /// own levels.
+    pub fn set_zstd_level(mut self, value: i32) -> CodecOptionsBuilder {
+        self.zstd_level = value;
+        self
+    }
+
+    /// Sets the level GZIP compresses at, from 0, which stores the data without
+    /// compressing it, to 9, the strongest. Defaults to 6, the zlib default.
+    ///
+    /// `try_build` and `create_codec` return an error for levels outside that range.
+    /// The level does not affect decompression.
+    pub fn set_gzip_level(mut self, value: u32) -> CodecOptionsBuilder {
+        self.gzip_level = value;
+        self
+    }
+
+    /// Sets the level BROTLI compresses at, from 0, the fastest, to 11, the strongest.
+    /// Defaults to 1.
+    ///
+    /// `try_build` and `create_codec` return an error for levels outside that range.
+    /// The level does not affect decompression, nor `compress_within`, which picks its
+    /// own levels.
+    pub fn set_brotli_level(mut self, value: u32) -> CodecOptionsBuilder {
+        self.brotli_level = value;
+        self
+    }
+
+    /// Sets the preset XZ compresses with, from 0, the fastest, to 9, the strongest
+    /// and slowest. Defaults to 6, the default of the `xz` tool.
+    ///
+    /// `try_build` and `create_codec` return an error for presets outside that range.
+    /// Higher presets also need more memory to decompress.
+    pub fn set_xz_preset(mut self, value: u32) -> CodecOptionsBuilder {
+        self.xz_preset = value;
+        self
+    }
+
+    /// Sets the level QCOM compresses at, from 0, the fastest, to 12, the strongest.
+    /// Defaults to 8, the q_compress default.
+    ///
+    /// `try_build` and `create_codec` return an error for levels outside that range.
+    /// The level does not affect decompression.
+    pub fn set_qcom_level(mut self, value: usize) -> CodecOptionsBuilder {
+        self.qcom_level = value;
+        self
+    }
+
+    /// Sets the number of bits, from 1 to 64, integer values are packed into before
+    /// byte codecs compress them, for columns whose values are known to be small.
+    /// Defaults to `None`, no packing.
+    ///
+    /// Signed values are packed in two's complement. `compress` returns an error if a
+    /// value does not fit in the width. The width is recorded with the packed values,
+    /// but columns must still be decompressed with a width set.
+    pub fn set_bit_pack_width(mut self, value: Option<u8>) -> CodecOptionsBuilder {
+        self.bit_pack_width = value;
+        self
+    }
+
+    /// Enable/disable rewriting every NaN in float columns to the canonical quiet NaN
+    /// before compression. Defaults to `false`.
+    ///
+    /// NaNs with different payloads or signs compare as unordered either way, but their
+    /// differing bits compress differently and break content hashing. Canonicalizing
+    /// keeps every value's meaning but not its exact bits, so codecs report
+    /// `is_lossless() == false` with this enabled.
+    pub fn set_canonicalize_nan(mut self, value: bool) -> CodecOptionsBuilder {
+        self.canonicalize_nan = value;
+        self
+    }
+
+    /// Enable/disable logging a warning when a byte column about to be compressed
+    /// starts with the magic number of a known codec, see [`sniff_codec`]. Defaults
+    /// to `false`.
+    ///
+    /// Compressing already compressed data barely shrinks it and usually means a
+    /// pipeline compresses the same buffer twice. The data is still compressed.
+    pub fn set_warn_on_precompressed(mut self, value: bool) -> CodecOptionsBuilder {
+        self.warn_on_precompressed = value;
+        self
+    }
+
+    /// Sets the largest number of bytes a single `compress` call may append to its
+    /// output, e.g. the capacity of a fixed-size storage slot. Defaults to `None`, no
+    /// limit.
+    ///
+    /// A call whose output would exceed the limit returns an error and leaves the
+    /// output buffer as it was, so callers can fall back to another strategy.
+    pub fn set_max_output_size(mut self, value: Option<usize>) -> CodecOptionsBuilder {
+        self.max_output_size = value;
+        self
+    }
+
+    /// Sets how many times `compress` and `decompress` retry a call that fails with a
+    /// transient error, such as an interrupted call or a temporary allocation failure.
+    ///
+    /// Each retry starts over from the original input and output. Other errors fail
+    /// immediately; see `is_transient`. Defaults to 0, no retries.
+    pub fn set_retry_on_transient(mut self, value: u8) -> CodecOptionsBuilder {
+        self.retry_on_transient = value;
+        self
+    }
+
+    /// Sets the byte order the byte codecs write `f32` and `f64` values in.
+    ///
+    /// Defaults to `Endianness::Big`, the same as integers. Some interoperating
+    /// readers expect floats in a different byte order than integers. Columns must be
+    /// decompressed with the same setting they were compressed with.
+    pub fn set_float_endianness(mut self, value: Endianness) -> CodecOptionsBuilder {
+        self.float_endianness = value;
+        self
+    }
+
+    /// Sets the byte order the byte codecs write integer values in.
+    ///
+    /// Defaults to `Endianness::Big`. Columns must be decompressed with the same
+    /// setting they were compressed with.
+    pub fn set_int_endianness(mut self, value: Endianness) -> CodecOptionsBuilder {
+        self.int_endianness = value;
+        self
+    }
+
+    /// Sets the byte order the byte codecs write both integer and float values in,
+    /// e.g. `Endianness::Little` to match the layout parquet-cpp reads. Defaults to
+    /// `Endianness::Big`.
+    ///
+    /// Shorthand for [`Self::set_int_endianness`] and [`Self::set_float_endianness`].
+    pub fn set_endianness(self, value: Endianness) -> CodecOptionsBuilder {
+        self.set_int_endianness(value).set_float_endianness(value)
+    }
+
+    /// Sets the allocator codecs take their scratch buffers from, such as the
+    /// serialized input of `compress`, e.g. an arena.
+    ///
+    /// Buffers the compression libraries allocate internally, and the output buffers,
+    /// still use the global allocator.
+    #[cfg(feature = "allocator")]
+    pub fn set_allocator(mut self, value: ScratchAllocator) -> CodecOptionsBuilder {
+        self.allocator = Some(value);
+        self
+    }
+
+    /// Builds the options without checking them; `create_codec` rejects invalid
+    /// combinations later. Use [`Self::try_build`] to catch them here instead.
+    pub fn build(self) -> CodecOptions {
+        CodecOptions {
+            backward_compatible_lz4: self.backward_compatible_lz4,
+            deterministic: self.deterministic,
+            verify_roundtrip: self.verify_roundtrip,
+            encoding_hint: self.encoding_hint,
+            min_compression_ratio: self.min_compression_ratio,
+            allow_partial_decompress: self.allow_partial_decompress,
+            skip_corrupt_frames: self.skip_corrupt_frames,
+            float_component_split: self.float_component_split,
+            retry_on_transient: self.retry_on_transient,
+            float_endianness: self.float_endianness,
+            int_endianness: self.int_endianness,
+            auto_block_split: self.auto_block_split,
+            context_prefix: self.context_prefix,
+            intra_dedup: self.intra_dedup,
+            zstd_dictionary: self.zstd_dictionary,
+            zstd_level: self.zstd_level,
+            gzip_level: self.gzip_level,
+            brotli_level: self.brotli_level,
+            xz_preset: self.xz_preset,
+            qcom_level: self.qcom_level,
+            bit_pack_width: self.bit_pack_width,
+            canonicalize_nan: self.canonicalize_nan,
+            warn_on_precompressed: self.warn_on_precompressed,
+            max_output_size: self.max_output_size,
+            #[cfg(feature = "allocator")]
+            allocator: self.allocator,
+        }
+    }
+
+    /// Builds the options, returning the error of [`CodecOptions::validate`] if they
+    /// are invalid, e.g. a compression level out of range.
+    pub fn try_build(self) -> Result<CodecOptions> {
+        let options = self.build();
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+/// Given the compression type `codec_type`, returns a codec used to compress and
+/// decompress bytes for the compression type.
+/// This returns `None` if the codec type is `UNCOMPRESSED`.
+pub fn create_codec(
+    codec_type: CodecType,
+    options: &CodecOptions,
+) -> Result<Option<Box<dyn Codec>>> {
+    let codec = match create_codec_enum(codec_type, options)? {
+        Some(kind) => kind.into_codec(),
+        None => return Ok(None),
+    };
+    let block_codec = matches!(
+        codec_type,
+        CodecType::SNAPPY | CodecType::LZ4 | CodecType::LZ4_RAW
+    );
+    let codec: Box<dyn Codec> = match options.auto_block_split {
+        true if block_codec => {
+            let block_len = codec.max_input_len();
+            Box::new(BlockSplitCodec::new(codec, block_len))
+        }
+        _ => codec,
+    };
+    // QCOM has no byte blocks to deduplicate, it compresses the typed values
+    let codec: Box<dyn Codec> = match options.intra_dedup {
+        true if codec_type != CodecType::QCOM => Box::new(DedupCodec::new(codec)),
+        _ => codec,
+    };
+    let codec: Box<dyn Codec> = match options.retry_on_transient {
+        0 => codec,
+        retries => Box::new(RetryCodec::new(codec, retries)),
+    };
+    // QCOM has no byte stream to compress a prefix ahead of
+    let codec: Box<dyn Codec> = match &options.context_prefix {
+        Some(prefix) if codec_type != CodecType::QCOM => {
+            Box::new(ContextPrefixCodec::new(codec, prefix.clone()))
+        }
+        _ => codec,
+    };
+    // QCOM never writes the values' bytes, so their byte order does not apply
+    let swap_ints = options.int_endianness.is_little();
+    let swap_floats = options.float_endianness.is_little();
+    let codec: Box<dyn Codec> = match (swap_ints, swap_floats) {
+        (false, false) => codec,
+        _ if codec_type == CodecType::QCOM => codec,
+        _ => Box::new(EndianCodec::new(codec, swap_ints, swap_floats)),
+    };
+    // QCOM applies the delta order itself
+    let codec: Box<dyn Codec> = match options.encoding_hint.delta_order() {
+        Some(_) if codec_type != CodecType::QCOM => Box::new(DeltaCodec::new(codec)),
+        _ => codec,
+    };
+    // QCOM compresses typed floats, not byte streams
+    let codec: Box<dyn Codec> = match options.float_component_split {
+        true if codec_type != CodecType::QCOM => Box::new(FloatSplitCodec::new(codec)),
+        _ => codec,
+    };
+    // QCOM compresses typed integers, not byte streams
+    let codec: Box<dyn Codec> = match options.bit_pack_width {
+        Some(width) if codec_type != CodecType::QCOM => {
+            Box::new(BitPackCodec::new(codec, width))
+        }
+        _ => codec,
+    };
+    let codec: Box<dyn Codec> = match options.min_compression_ratio {
+        Some(min_ratio) => Box::new(StoredFallbackCodec::new(codec, min_ratio)),
+        None => codec,
+    };
+    let codec: Box<dyn Codec> = if options.verify_roundtrip {
+        Box::new(VerifyRoundtripCodec::new(codec))
+    } else {
+        codec
+    };
+    // outside round-trip verification, which compares the canonicalized values
+    let codec: Box<dyn Codec> = if options.canonicalize_nan {
+        Box::new(CanonicalNanCodec::new(codec))
+    } else {
+        codec
+    };
+    let codec: Box<dyn Codec> = if options.warn_on_precompressed {
+        Box::new(PrecompressedCheckCodec::new(codec, codec_type))
+    } else {
+        codec
+    };
+    let codec: Box<dyn Codec> = match options.max_output_size {
+        Some(max_size) => Box::new(OutputCapCodec::new(codec, max_size)),
+        None => codec,
+    };
+    #[cfg(feature = "allocator")]
+    let codec: Box<dyn Codec> = match &options.allocator {
+        Some(allocator) => Box::new(ScratchAllocatorCodec::new(codec, allocator.clone())),
+        None => codec,
+    };
+    Ok(Some(codec))
+}
+
+/// Creates the codec named `name`, e.g. `"snappy"`, `"zstd"`, `"lz4_raw"` or `"qcom"`,
+/// as read from a configuration file or the command line.
+///
+/// Names are the `CodecType` variant names, compared case-insensitively. This returns
+/// `None` for `"uncompressed"`, and an error for unknown names and codec types
+/// `create_codec` does not support.
+pub fn create_codec_by_name(
+    name: &str,
+    options: &CodecOptions,
+) -> Result<Option<Box<dyn Codec>>> {
+    let codec_type = match name.to_ascii_uppercase().as_str() {
+        "UNCOMPRESSED" => CodecType::UNCOMPRESSED,
+        "SNAPPY" => CodecType::SNAPPY,
+        "GZIP" => CodecType::GZIP,
+        "LZO" => CodecType::LZO,
+        "BROTLI" => CodecType::BROTLI,
+        "LZ4" => CodecType::LZ4,
+        "ZSTD" => CodecType::ZSTD,
+        "LZ4_RAW" => CodecType::LZ4_RAW,
+        "QCOM" => CodecType::QCOM,
+        "BZIP2" => CodecType::BZIP2,
+        "XZ" => CodecType::XZ,
+        _ => {
+            return Err(general_err!(
+                "Unknown codec name {}: expected one of uncompressed, snappy, gzip, lzo, \
+                 brotli, lz4, zstd, lz4_raw, qcom, bzip2 or xz",
+                name
+            ))
+        }
+    };
+    create_codec(codec_type, options)
+}
+
+/// Returns the compression levels the library behind `codec_type` accepts, from the
+/// fastest to the strongest, or `None` for codecs without levels.
+pub fn level_range(codec_type: CodecType) -> Option<std::ops::RangeInclusive<i32>> {
+    match codec_type {
+        CodecType::GZIP => Some(0..=9),
+        CodecType::BROTLI => Some(0..=11),
+        CodecType::ZSTD => Some(1..=22),
+        CodecType::QCOM => Some(0..=12),
+        CodecType::XZ => Some(0..=9),
+        _ => None,
+    }
+}
+
+/// A codec of one of the supported algorithms, created by [`create_codec_enum`].
+///
+/// Unlike the `Box<dyn Codec>` returned by [`create_codec`], `compress` and
+/// `decompress` call the codec directly, without a virtual call or a heap allocation
+/// per codec, for hot loops.
+// boxing the larger codecs would bring back the allocation this avoids
+#[allow(clippy::large_enum_variant)]
+pub enum CodecKind {
+    #[cfg(any(feature = "brotli", test))]
+    Brotli(BrotliCodec),
+    #[cfg(any(feature = "flate2", test))]
+    Gzip(GZipCodec),
+    #[cfg(any(feature = "snap", test))]
+    Snappy(SnappyCodec),
+    /// LZ4 in the Hadoop framing, written for `CodecType::LZ4`.
+    #[cfg(any(feature = "lz4", test))]
+    Lz4(LZ4HadoopCodec),
+    #[cfg(any(feature = "zstd", test))]
+    Zstd(ZSTDCodec),
+    #[cfg(any(feature = "lz4", test))]
+    Lz4Raw(LZ4RawCodec),
+    #[cfg(any(feature = "q_compress", test))]
+    QCom(QComCodec),
+    #[cfg(any(feature = "bzip2", test))]
+    Bzip2(Bzip2Codec),
+    #[cfg(any(feature = "xz", test))]
+    Xz(XzCodec),
+}
+
+impl CodecKind {
+    /// Compresses `input_buf_columndata` and appends the compressed bytes to
+    /// `output_buf`, see [`Codec::compress`].
+    pub fn compress(
+        &mut self,
+        input_buf_columndata: &ColumnData,
+        output_buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        match *self {
+            #[cfg(any(feature = "brotli", test))]
+            CodecKind::Brotli(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "flate2", test))]
+            CodecKind::Gzip(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "snap", test))]
+            CodecKind::Snappy(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "zstd", test))]
+            CodecKind::Zstd(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4Raw(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "q_compress", test))]
+            CodecKind::QCom(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "bzip2", test))]
+            CodecKind::Bzip2(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+            #[cfg(any(feature = "xz", test))]
+            CodecKind::Xz(ref mut codec) => {
+                codec.compress(input_buf_columndata, output_buf)
+            }
+        }
+    }
+
+    /// Decompresses `input_buf` and appends the values to `output_buf_columndata`,
+    /// see [`Codec::decompress`].
+    pub fn decompress(
+        &mut self,
+        input_buf: &[u8],
+        output_buf_columndata: &mut ColumnData,
+        uncompress_size: Option<usize>,
+    ) -> Result<usize> {
+        match *self {
+            #[cfg(any(feature = "brotli", test))]
+            CodecKind::Brotli(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "flate2", test))]
+            CodecKind::Gzip(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "snap", test))]
+            CodecKind::Snappy(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "zstd", test))]
+            CodecKind::Zstd(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4Raw(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "q_compress", test))]
+            CodecKind::QCom(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "bzip2", test))]
+            CodecKind::Bzip2(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+            #[cfg(any(feature = "xz", test))]
+            CodecKind::Xz(ref mut codec) => {
+                codec.decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+        }
+    }
+
+    /// Returns the codec as a `Codec` trait object, for the rest of the `Codec` API.
+    pub fn as_codec(&mut self) -> &mut dyn Codec {
+        match *self {
+            #[cfg(any(feature = "brotli", test))]
+            CodecKind::Brotli(ref mut codec) => codec,
+            #[cfg(any(feature = "flate2", test))]
+            CodecKind::Gzip(ref mut codec) => codec,
+            #[cfg(any(feature = "snap", test))]
+            CodecKind::Snappy(ref mut codec) => codec,
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4(ref mut codec) => codec,
+            #[cfg(any(feature = "zstd", test))]
+            CodecKind::Zstd(ref mut codec) => codec,
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4Raw(ref mut codec) => codec,
+            #[cfg(any(feature = "q_compress", test))]
+            CodecKind::QCom(ref mut codec) => codec,
+            #[cfg(any(feature = "bzip2", test))]
+            CodecKind::Bzip2(ref mut codec) => codec,
+            #[cfg(any(feature = "xz", test))]
+            CodecKind::Xz(ref mut codec) => codec,
+        }
+    }
+
+    /// Boxes the codec, as `create_codec` returns it.
+    pub fn into_codec(self) -> Box<dyn Codec> {
+        match self {
+            #[cfg(any(feature = "brotli", test))]
+            CodecKind::Brotli(codec) => Box::new(codec),
+            #[cfg(any(feature = "flate2", test))]
+            CodecKind::Gzip(codec) => Box::new(codec),
+            #[cfg(any(feature = "snap", test))]
+            CodecKind::Snappy(codec) => Box::new(codec),
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4(codec) => Box::new(codec),
+            #[cfg(any(feature = "zstd", test))]
+            CodecKind::Zstd(codec) => Box::new(codec),
+            #[cfg(any(feature = "lz4", test))]
+            CodecKind::Lz4Raw(codec) => Box::new(codec),
+            #[cfg(any(feature = "q_compress", test))]
+            CodecKind::QCom(codec) => Box::new(codec),
+            #[cfg(any(feature = "bzip2", test))]
+            CodecKind::Bzip2(codec) => Box::new(codec),
+            #[cfg(any(feature = "xz", test))]
+            CodecKind::Xz(codec) => Box::new(codec),
+        }
+    }
+}
+
+/// Given the compression type `codec_type`, returns the codec for it as a
+/// [`CodecKind`], which dispatches statically. This returns `None` if the codec type
+/// is `UNCOMPRESSED`.
+///
+/// Only the options of the codec itself, such as its level, apply. The options that
+/// `create_codec` implements by wrapping the codec, such as round-trip verification,
+/// byte order or block splitting, are not applied, so inputs must fit in
+/// `Codec::max_input_len`.
+pub fn create_codec_enum(
+    codec_type: CodecType,
+    options: &CodecOptions,
+) -> Result<Option<CodecKind>> {
+    options.validate()?;
+    let kind = match codec_type {
+        #[cfg(any(feature = "brotli", test))]
+        CodecType::BROTLI => CodecKind::Brotli(BrotliCodec::new(
+            options.brotli_level,
+            options.deterministic,
+            options.allow_partial_decompress,
+        )),
+        #[cfg(any(feature = "flate2", test))]
+        CodecType::GZIP => CodecKind::Gzip(GZipCodec::new(
+            options.gzip_level,
+            options.allow_partial_decompress,
+        )),
+        #[cfg(any(feature = "snap", test))]
+        CodecType::SNAPPY => CodecKind::Snappy(SnappyCodec::new()),
+        #[cfg(any(feature = "lz4", test))]
+        CodecType::LZ4 => CodecKind::Lz4(LZ4HadoopCodec::new(
+            options.backward_compatible_lz4,
+            options.skip_corrupt_frames,
+        )),
+        #[cfg(any(feature = "zstd", test))]
+        CodecType::ZSTD => CodecKind::Zstd(match &options.zstd_dictionary {
+            Some(dictionary) => ZSTDCodec::with_dictionary(
+                options.zstd_level,
+                options.deterministic,
+                options.allow_partial_decompress,
+                dictionary.clone(),
+            )?,
+            None => ZSTDCodec::new(
+                options.zstd_level,
+                options.deterministic,
+                options.allow_partial_decompress,
+            ),
+        }),
+        #[cfg(any(feature = "lz4", test))]
+        CodecType::LZ4_RAW => CodecKind::Lz4Raw(LZ4RawCodec::new()),
+        #[cfg(any(feature = "q_compress", test))]
+        CodecType::QCOM => CodecKind::QCom(QComCodec::new(
+            options.encoding_hint.delta_order(),
+            options.qcom_level,
+        )),
+        #[cfg(any(feature = "bzip2", test))]
+        CodecType::BZIP2 => {
+            CodecKind::Bzip2(Bzip2Codec::new(options.allow_partial_decompress))
+        }
+        #[cfg(any(feature = "xz", test))]
+        CodecType::XZ => CodecKind::Xz(XzCodec::new(
+            options.xz_preset,
+            options.allow_partial_decompress,
+        )),
+        CodecType::UNCOMPRESSED => return Ok(None),
+        _ => {
+            return Err(nyi_err!("The codec type {} is not supported yet", codec_type))
+        }
+    };
+    Ok(Some(kind))
+}
+
+/// Returns the codec types compiled into this build, excluding `UNCOMPRESSED`, in the
+/// order of `CodecType`'s variants. These are the types `create_codec` accepts.
+pub fn available_codecs() -> Vec<CodecType> {
+    [
+        (cfg!(any(feature = "snap", test)), CodecType::SNAPPY),
+        (cfg!(any(feature = "flate2", test)), CodecType::GZIP),
+        (cfg!(any(feature = "brotli", test)), CodecType::BROTLI),
+        (cfg!(any(feature = "lz4", test)), CodecType::LZ4),
+        (cfg!(any(feature = "zstd", test)), CodecType::ZSTD),
+        (cfg!(any(feature = "lz4", test)), CodecType::LZ4_RAW),
+        (cfg!(any(feature = "q_compress", test)), CodecType::QCOM),
+        (cfg!(any(feature = "bzip2", test)), CodecType::BZIP2),
+        (cfg!(any(feature = "xz", test)), CodecType::XZ),
+    ]
+    .into_iter()
+    .filter_map(|(compiled_in, codec_type)| compiled_in.then_some(codec_type))
+    .collect()
+}
+
+/// Returns whether `codec_type` is compiled into this build. `UNCOMPRESSED` always is.
+pub fn is_compiled_in(codec_type: CodecType) -> bool {
+    codec_type == CodecType::UNCOMPRESSED || available_codecs().contains(&codec_type)
+}
+
+/// Number of leading elements `recommend_codec` compresses with each candidate.
+const RECOMMEND_SAMPLE_SIZE: usize = 1024;
+
+/// Recommends a codec for `data` by compressing a small sample with every available
+/// codec that supports its element type.
+///
+/// Each candidate is scored by the compression ratio it achieves on the first
+/// `RECOMMEND_SAMPLE_SIZE` elements, halved for every order of magnitude it is slower
+/// than the fastest candidate. Returns `UNCOMPRESSED` if no codec can compress the data.
+pub fn recommend_codec(data: &ColumnData, options: &CodecOptions) -> CodecType {
+    let sample = data.slice(0, data.len().min(RECOMMEND_SAMPLE_SIZE));
+    let mut sample_bytes = Vec::new();
+    if sample.convert_to_u8(&mut sample_bytes).is_err() || sample_bytes.is_empty() {
+        return CodecType::UNCOMPRESSED;
+    }
+
+    // (codec, compression ratio, elapsed seconds)
+    let mut trials = Vec::new();
+    for codec_type in available_codecs() {
+        let mut codec = match create_codec(codec_type, options) {
+            Ok(Some(codec)) if codec.supports_type(sample.typename()) => codec,
+            _ => continue,
+        };
+        let mut compressed = Vec::new();
+        let start = std::time::Instant::now();
+        if codec.compress(&sample, &mut compressed).is_err() || compressed.is_empty() {
+            continue;
+        }
+        let elapsed = start.elapsed().as_secs_f64().max(f64::EPSILON);
+        let ratio = sample_bytes.len() as f64 / compressed.len() as f64;
+        trials.push((codec_type, ratio, elapsed));
+    }
+
+    let fastest = trials
+        .iter()
+        .map(|(_, _, elapsed)| *elapsed)
+        .fold(f64::INFINITY, f64::min);
+    trials
+        .into_iter()
+        .map(|(codec_type, ratio, elapsed)| {
+            let slowdown = (elapsed / fastest).log10();
+            (codec_type, ratio / 2f64.powf(slowdown))
+        })
+        .fold(None, |best: Option<(CodecType, f64)>, (codec_type, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((codec_type, score)),
+        })
+        .map(|(codec_type, _)| codec_type)
+        .unwrap_or(CodecType::UNCOMPRESSED)
+}
+
+/// Caches the codec [`recommend_codec`] picks for each column of a schema, so that
+/// repeated writes of the same schema sample every column only once.
+///
+/// Entries are keyed by column name and element typename and never expire; create a
+/// new cache when the data of a column changes character. The cache can be shared
+/// between threads.
+pub struct SchemaCodecCache {
+    options: CodecOptions,
+    recommendations: Mutex<HashMap<(String, String), CodecType>>,
+    /// Number of recommendations that sampled their column.
+    samples: AtomicUsize,
+}
+
+impl SchemaCodecCache {
+    /// Creates an empty cache recommending codecs created with `options`.
+    pub fn new(options: CodecOptions) -> Self {
+        Self {
+            options,
+            recommendations: Mutex::new(HashMap::new()),
+            samples: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the codec recommended for `column` of type `typename`, running
+    /// [`recommend_codec`] on `sample` only the first time the pair is seen.
+    pub fn recommend_cached(
+        &self,
+        column: &str,
+        typename: &str,
+        sample: &ColumnData,
+    ) -> CodecType {
+        let key = (column.to_string(), typename.to_string());
+        if let Some(codec_type) = self.lock().get(&key) {
+            return *codec_type;
+        }
+        // sample without holding the lock, so other columns are not held up
+        let codec_type = recommend_codec(sample, &self.options);
+        self.samples.fetch_add(1, Ordering::Relaxed);
+        *self.lock().entry(key).or_insert(codec_type)
+    }
+
+    /// Returns the number of recommendations that sampled their column rather than
+    /// being served from the cache.
+    pub fn sample_count(&self) -> usize {
+        self.samples.load(Ordering::Relaxed)
+    }
+
+    /// Forgets every cached recommendation.
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String), CodecType>> {
+        // a panic while holding the lock cannot leave the map half-updated
+        self.recommendations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether [`select_codec`] favours compression speed or compressed size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preference {
+    /// Compress and decompress as fast as possible.
+    Fastest,
+    /// Trade some speed for a good compression ratio.
+    Balanced,
+    /// Compress as small as possible, however slowly.
+    Smallest,
+}
+
+impl Preference {
+    /// Returns the codecs meeting this preference, best first.
+    fn candidates(self) -> &'static [CodecType] {
+        match self {
+            Preference::Fastest => &[CodecType::LZ4_RAW, CodecType::SNAPPY, CodecType::ZSTD],
+            Preference::Balanced => &[CodecType::ZSTD, CodecType::GZIP, CodecType::SNAPPY],
+            Preference::Smallest => &[
+                CodecType::QCOM,
+                CodecType::BROTLI,
+                CodecType::ZSTD,
+                CodecType::GZIP,
+            ],
+        }
+    }
+}
+
+/// Returns a codec meeting `preference` for columns of `data_typename`, e.g. `"i64"`,
+/// without looking at any data; see [`recommend_codec`] to pick one by trial.
+///
+/// Only codecs compiled into this build whose `supports_type` accepts the type are
+/// returned, e.g. QCOM for `Smallest` numeric columns but not for `u8`. Returns
+/// `UNCOMPRESSED` for types no column can hold, or if no suitable codec is available.
+pub fn select_codec(preference: Preference, data_typename: &str) -> CodecType {
+    if !supported_typenames().contains(&data_typename) {
+        return CodecType::UNCOMPRESSED;
+    }
+    let options = CodecOptions::default();
+    preference
+        .candidates()
+        .iter()
+        .copied()
+        .find(|&codec_type| match create_codec(codec_type, &options) {
+            Ok(Some(codec)) => codec.supports_type(data_typename),
+            _ => false,
+        })
+        .unwrap_or(CodecType::UNCOMPRESSED)
+}
+
+/// Compresses every named column of a batch, using QCOM for the columns whose element
+/// type it supports and ZSTD for the rest.
+///
+/// Returns the column name, the codec used and the compressed bytes for each column,
+/// in input order.
+pub fn compress_batch_auto(
+    columns: &[(&str, &ColumnData)],
+    options: &CodecOptions,
+) -> Result<Vec<(String, CodecType, Vec<u8>)>> {
+    // QCOM is optional; without it every column falls back to ZSTD
+    let mut qcom = create_codec(CodecType::QCOM, options).ok().flatten();
+    let mut zstd = None;
+
+    let mut compressed_columns = Vec::with_capacity(columns.len());
+    for (name, data) in columns {
+        let mut compressed = Vec::new();
+        let codec_type = match qcom.as_mut() {
+            Some(codec) if codec.supports_type(data.typename()) => {
+                codec.compress(data, &mut compressed)?;
+                CodecType::QCOM
+            }
+            _ => {
+                if zstd.is_none() {
+                    zstd = create_codec(CodecType::ZSTD, options)?;
+                }
+                let codec = zstd
+                    .as_mut()
+                    .ok_or_else(|| general_err!("ZSTD codec is not available"))?;
+                codec.compress(data, &mut compressed)?;
+                CodecType::ZSTD
+            }
+        };
+        compressed_columns.push((name.to_string(), codec_type, compressed));
+    }
+    Ok(compressed_columns)
+}
+
+/// Compresses `input` with `codec` after checking that every element lies within
+/// `valid_range`, appending the result to `output_buf`.
+///
+/// Returns the number of compressed bytes appended. Nothing is written if an element
+/// is out of range; the error names the first offending value and its index.
+pub fn compress_validated<T: DataTypeConstraint>(
+    codec: &mut dyn Codec,
+    input: &ColumnData,
+    output_buf: &mut Vec<u8>,
+    valid_range: (T, T),
+) -> Result<usize> {
+    input.check_range(valid_range.0, valid_range.1)?;
+    let offset = output_buf.len();
+    codec.compress(input, output_buf)?;
+    Ok(output_buf.len() - offset)
+}
+
+#[cfg(any(feature = "snap", test))]
+mod snappy_codec {
+    use snap::raw::{decompress_len, max_compress_len, Decoder, Encoder};
+
+    use crate::compression::{
+        check_output_type, decompress_into_view, fingerprint, scratch_bytes, Codec,
+        CodecType, ConversionContext, DecompressHint,
+    };
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec for Snappy compression format.
+    pub struct SnappyCodec {
+        decoder: Decoder,
+        encoder: Encoder,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl SnappyCodec {
+        /// Creates new Snappy compression codec.
+        pub(crate) fn new() -> Self {
+            Self {
+                decoder: Decoder::new(),
+                encoder: Encoder::new(),
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for SnappyCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "SNAPPY", output_buf_columndata)?;
+
+            let len = match uncompress_size {
+                Some(size) => size,
+                None => decompress_len(input_buf)
+                    .decompress_hint(CodecType::SNAPPY, input_buf)?,
+            };
+            let mut output_buf = vec![0; len];
+            let n = self
+                .decoder
+                .decompress(input_buf, &mut output_buf)
+                .decompress_hint(CodecType::SNAPPY, input_buf)?;
+
+            // `n` is the exact decompressed length, so size the typed output once for
+            // it and leave out any excess of an overstated `uncompress_size`
+            output_buf.truncate(n);
+            output_buf_columndata.reserve_exact(n / output_buf_columndata.type_width());
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("SNAPPY", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("SNAPPY", "compress", input_buf_columndata.typename())?;
+
+            let output_buf_len = output_buf.len();
+            let required_len = max_compress_len(input_buf.len());
+            output_buf.resize(output_buf_len + required_len, 0);
+            let n = self
+                .encoder
+                .compress(&input_buf, &mut output_buf[output_buf_len..])?;
+            output_buf.truncate(output_buf_len + n);
+            Ok(())
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // the uncompressed length as a varint, at least one byte
+            1
+        }
+
+        fn max_input_len(&self) -> usize {
+            // the largest input whose worst case, 32 + n + n / 6, fits snap's u32 limit
+            (u32::MAX as usize - 32) / 7 * 6
+        }
+
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            // a three byte copy tag expands to at most 64 bytes
+            Some(64.0 / 3.0)
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.decoder = Decoder::new();
+            self.encoder = Encoder::new();
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "SNAPPY"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the snap crate version this build requires
+            "1.0"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&"SNAPPY")
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new())
+        }
+    }
+}
+#[cfg(any(feature = "snap", test))]
+pub use snappy_codec::*;
+
+#[cfg(any(feature = "flate2", test))]
+mod gzip_codec {
+
+    use std::fs::File;
+    use std::io::{self, BufReader, Read, Write};
+    use std::path::Path;
+
+    use flate2::{read, write, Compression};
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, concat_magic_frames,
+        decompress_into_view, decompress_reader, fingerprint, read_to_end_partial,
+        scratch_bytes, Codec, CodecType, ConversionContext, DecompressHint, StreamProgress,
+    };
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec for GZIP compression algorithm.
+    pub struct GZipCodec {
+        /// Level `compress` compresses at.
+        level: u32,
+        /// Whether a truncated stream yields the values decoded before it.
+        allow_partial: bool,
+        /// Whether the previous `decompress` hit a truncated stream.
+        truncated: bool,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// How far the compression written through `compress_stream` has got.
+        progress: StreamProgress,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl GZipCodec {
+        /// Creates new GZIP compression codec compressing at `level`.
+        pub(crate) fn new(level: u32, allow_partial: bool) -> Self {
+            Self {
+                level,
+                allow_partial,
+                truncated: false,
+                last_element_count: 0,
+                progress: StreamProgress::default(),
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for GZipCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "GZIP", output_buf_columndata)?;
+
+            let len = uncompress_size.unwrap_or(0);
+            let mut output_buf: Vec<u8> = Vec::with_capacity(len);
+            output_buf_columndata.reserve(len / output_buf_columndata.type_width());
+
+            let mut decoder = read::MultiGzDecoder::new(input_buf);
+            let width = output_buf_columndata.type_width();
+            self.truncated =
+                read_to_end_partial(&mut decoder, &mut output_buf, width, self.allow_partial)
+                    .decompress_hint(CodecType::GZIP, input_buf)?;
+            let n = output_buf.len();
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("GZIP", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("GZIP", "compress", input_buf_columndata.typename())?;
+
+            let mut encoder = write::GzEncoder::new(output_buf, Compression::new(self.level));
+            encoder.write_all(&input_buf)?;
+            encoder.try_finish().map_err(|e| e.into())
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "GZIP", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let level = Compression::new(self.level);
+            self.progress.track(output, |output| {
+                let mut encoder = write::GzEncoder::new(output, level);
+                let n = io::copy(input, &mut encoder)?;
+                encoder.try_finish()?;
+                Ok(n)
+            })
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut decoder = read::MultiGzDecoder::new(input);
+            Ok(io::copy(&mut decoder, output)?)
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>> {
+            Ok(self.progress.save(self.config_fingerprint()))
+        }
+
+        fn restore_state(&mut self, bytes: &[u8]) -> Result<()> {
+            self.progress = StreamProgress::restore(bytes, self.config_fingerprint())?;
+            Ok(())
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "GZIP", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            decompress_reader(read::MultiGzDecoder::new(file), output_buf_columndata, "GZIP")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // 10-byte header and 8-byte CRC32/size trailer
+            18
+        }
+
+        fn last_was_truncated(&self) -> bool {
+            self.truncated
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            concat_magic_frames(CodecType::GZIP, frames)
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.truncated = false;
+            self.last_element_count = 0;
+            self.progress = StreamProgress::default();
+        }
+
+        fn name(&self) -> &'static str {
+            "GZIP"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the flate2 crate version this build requires
+            "1.0"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("GZIP", self.level, self.allow_partial))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.level, self.allow_partial))
+        }
+    }
+}
+#[cfg(any(feature = "flate2", test))]
+pub use gzip_codec::*;
+
+#[cfg(any(feature = "bzip2", test))]
+mod bzip2_codec {
+
+    use std::fs::File;
+    use std::io::{self, BufReader, Read, Write};
+    use std::path::Path;
+
+    use bzip2::{read, write, Compression};
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, concat_magic_frames,
+        decompress_into_view, decompress_reader, fingerprint, read_to_end_partial,
+        scratch_bytes, Codec, CodecType, ConversionContext, DecompressHint, StreamProgress,
+    };
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec for BZIP2 compression algorithm.
+    pub struct Bzip2Codec {
+        /// Whether a truncated stream yields the values decoded before it.
+        allow_partial: bool,
+        /// Whether the previous `decompress` hit a truncated stream.
+        truncated: bool,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// How far the compression written through `compress_stream` has got.
+        progress: StreamProgress,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl Bzip2Codec {
+        /// Creates new BZIP2 compression codec.
+        pub(crate) fn new(allow_partial: bool) -> Self {
+            Self {
+                allow_partial,
+                truncated: false,
+                last_element_count: 0,
+                progress: StreamProgress::default(),
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for Bzip2Codec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "BZIP2", output_buf_columndata)?;
+
+            let len = uncompress_size.unwrap_or(0);
+            let mut output_buf: Vec<u8> = Vec::with_capacity(len);
+            output_buf_columndata.reserve(len / output_buf_columndata.type_width());
+
+            let mut decoder = read::MultiBzDecoder::new(input_buf);
+            let width = output_buf_columndata.type_width();
+            self.truncated =
+                read_to_end_partial(&mut decoder, &mut output_buf, width, self.allow_partial)
+                    .decompress_hint(CodecType::BZIP2, input_buf)?;
+            let n = output_buf.len();
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("BZIP2", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("BZIP2", "compress", input_buf_columndata.typename())?;
+
+            let mut encoder = write::BzEncoder::new(output_buf, Compression::default());
+            encoder.write_all(&input_buf)?;
+            encoder.try_finish().map_err(|e| e.into())
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "BZIP2", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            self.progress.track(output, |output| {
+                let mut encoder = write::BzEncoder::new(output, Compression::default());
+                let n = io::copy(input, &mut encoder)?;
+                encoder.try_finish()?;
+                Ok(n)
+            })
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut decoder = read::MultiBzDecoder::new(input);
+            Ok(io::copy(&mut decoder, output)?)
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>> {
+            Ok(self.progress.save(self.config_fingerprint()))
+        }
+
+        fn restore_state(&mut self, bytes: &[u8]) -> Result<()> {
+            self.progress = StreamProgress::restore(bytes, self.config_fingerprint())?;
+            Ok(())
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "BZIP2", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            decompress_reader(read::MultiBzDecoder::new(file), output_buf_columndata, "BZIP2")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // 4-byte stream header and 10-byte end-of-stream marker with CRC32
+            14
+        }
+
+        fn last_was_truncated(&self) -> bool {
+            self.truncated
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            concat_magic_frames(CodecType::BZIP2, frames)
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.truncated = false;
+            self.last_element_count = 0;
+            self.progress = StreamProgress::default();
+        }
+
+        fn name(&self) -> &'static str {
+            "BZIP2"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the bzip2 crate version this build requires
+            "0.4"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("BZIP2", self.allow_partial))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.allow_partial))
+        }
+    }
+}
+#[cfg(any(feature = "bzip2", test))]
+pub use bzip2_codec::*;
+
+#[cfg(any(feature = "xz", test))]
+mod xz_codec {
+
+    use std::fs::File;
+    use std::io::{self, BufReader, Read, Write};
+    use std::path::Path;
+
+    use xz2::{read, write};
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, concat_magic_frames,
+        decompress_into_view, decompress_reader, fingerprint, read_to_end_partial,
+        scratch_bytes, Codec, CodecType, ConversionContext, DecompressHint, StreamProgress,
+    };
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec for XZ compression algorithm, LZMA2 in the `.xz` container.
+    pub struct XzCodec {
+        /// Preset `compress` compresses with.
+        preset: u32,
+        /// Whether a truncated stream yields the values decoded before it.
+        allow_partial: bool,
+        /// Whether the previous `decompress` hit a truncated stream.
+        truncated: bool,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// How far the compression written through `compress_stream` has got.
+        progress: StreamProgress,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl XzCodec {
+        /// Creates new XZ compression codec compressing with `preset`.
+        pub(crate) fn new(preset: u32, allow_partial: bool) -> Self {
+            Self {
+                preset,
+                allow_partial,
+                truncated: false,
+                last_element_count: 0,
+                progress: StreamProgress::default(),
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for XzCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "XZ", output_buf_columndata)?;
+
+            let len = uncompress_size.unwrap_or(0);
+            let mut output_buf: Vec<u8> = Vec::with_capacity(len);
+            output_buf_columndata.reserve(len / output_buf_columndata.type_width());
+
+            let mut decoder = read::XzDecoder::new_multi_decoder(input_buf);
+            let width = output_buf_columndata.type_width();
+            self.truncated =
+                read_to_end_partial(&mut decoder, &mut output_buf, width, self.allow_partial)
+                    .decompress_hint(CodecType::XZ, input_buf)?;
+            let n = output_buf.len();
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("XZ", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("XZ", "compress", input_buf_columndata.typename())?;
+
+            let mut encoder = write::XzEncoder::new(output_buf, self.preset);
+            encoder.write_all(&input_buf)?;
+            encoder.try_finish().map_err(|e| e.into())
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "XZ", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let preset = self.preset;
+            self.progress.track(output, |output| {
+                let mut encoder = write::XzEncoder::new(output, preset);
+                let n = io::copy(input, &mut encoder)?;
+                encoder.try_finish()?;
+                Ok(n)
+            })
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut decoder = read::XzDecoder::new_multi_decoder(input);
+            Ok(io::copy(&mut decoder, output)?)
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>> {
+            Ok(self.progress.save(self.config_fingerprint()))
+        }
+
+        fn restore_state(&mut self, bytes: &[u8]) -> Result<()> {
+            self.progress = StreamProgress::restore(bytes, self.config_fingerprint())?;
+            Ok(())
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "XZ", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            let decoder = read::XzDecoder::new_multi_decoder(file);
+            decompress_reader(decoder, output_buf_columndata, "XZ")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // 12-byte stream header and footer, and an index of at least 8 bytes
+            32
+        }
+
+        fn last_was_truncated(&self) -> bool {
+            self.truncated
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            concat_magic_frames(CodecType::XZ, frames)
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.truncated = false;
+            self.last_element_count = 0;
+            self.progress = StreamProgress::default();
+        }
+
+        fn name(&self) -> &'static str {
+            "XZ"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the xz2 crate version this build requires
+            "0.1"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("XZ", self.preset, self.allow_partial))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.preset, self.allow_partial))
+        }
+    }
+}
+#[cfg(any(feature = "xz", test))]
+pub use xz_codec::*;
+
+#[cfg(any(feature = "brotli", test))]
+mod brotli_codec {
+
+    use std::fs::File;
+    use std::io::{self, BufReader, Read, Write};
+    use std::path::Path;
+
+    use std::time::Duration;
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, decompress_into_view,
+        decompress_reader, fingerprint, pick_level_within, read_to_end_partial,
+        scratch_bytes, Codec, CodecType, ConversionContext, DecompressHint,
+    };
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    const BROTLI_DEFAULT_BUFFER_SIZE: usize = 4096;
+    const BROTLI_DEFAULT_LG_WINDOW_SIZE: u32 = 22; // recommended between 20-22
+    /// Qualities `compress_within` tries, from slowest to fastest.
+    const BROTLI_BUDGET_QUALITIES: [u32; 3] = [9, 5, 1];
+
+    /// Codec for Brotli compression algorithm.
+    pub struct BrotliCodec {
+        /// Level `compress` compresses at.
+        level: u32,
+        /// Whether the output must not depend on timing, which rules out
+        /// `compress_within`.
+        deterministic: bool,
+        /// Whether a truncated stream yields the values decoded before it.
+        allow_partial: bool,
+        /// Whether the previous `decompress` hit a truncated stream.
+        truncated: bool,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl BrotliCodec {
+        /// Creates new Brotli compression codec compressing at `level`.
+        pub(crate) fn new(level: u32, deterministic: bool, allow_partial: bool) -> Self {
+            Self {
+                level,
+                deterministic,
+                allow_partial,
+                truncated: false,
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+    }
+
+    fn compress_quality(input_buf: &[u8], quality: u32, output_buf: &mut Vec<u8>) -> Result<()> {
+        let mut encoder = brotli::CompressorWriter::new(
+            output_buf,
+            BROTLI_DEFAULT_BUFFER_SIZE,
+            quality,
+            BROTLI_DEFAULT_LG_WINDOW_SIZE,
+        );
+        encoder.write_all(input_buf)?;
+        encoder.flush().map_err(|e| e.into())
+    }
+
+    impl Codec for BrotliCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "BROTLI", output_buf_columndata)?;
+
+            let mut output_buf: Vec<u8> = Vec::with_capacity(uncompress_size.unwrap_or(0));
+            output_buf_columndata
+                .reserve(uncompress_size.unwrap_or(0) / output_buf_columndata.type_width());
+
+            let buffer_size = uncompress_size.unwrap_or(BROTLI_DEFAULT_BUFFER_SIZE);
+            let mut decoder = brotli::Decompressor::new(input_buf, buffer_size);
+            let width = output_buf_columndata.type_width();
+            self.truncated =
+                read_to_end_partial(&mut decoder, &mut output_buf, width, self.allow_partial)
+                    .decompress_hint(CodecType::BROTLI, input_buf)?;
+            let n = output_buf.len();
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("BROTLI", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("BROTLI", "compress", input_buf_columndata.typename())?;
+
+            compress_quality(&input_buf, self.level, output_buf)
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "BROTLI", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut encoder = brotli::CompressorWriter::new(
+                output,
+                BROTLI_DEFAULT_BUFFER_SIZE,
+                self.level,
+                BROTLI_DEFAULT_LG_WINDOW_SIZE,
+            );
+            let n = io::copy(input, &mut encoder)?;
+            // the stream is finished when the encoder is dropped
+            encoder.flush()?;
+            Ok(n)
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut decoder = brotli::Decompressor::new(input, BROTLI_DEFAULT_BUFFER_SIZE);
+            Ok(io::copy(&mut decoder, output)?)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            self.last_element_count = input_buf_columndata.len();
+            if self.deterministic {
+                return Err(general_err!(
+                    "BROTLI compress_within: adaptive levels conflict with deterministic output"
+                ));
+            }
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("BROTLI", "compress", input_buf_columndata.typename())?;
+
+            let mut sample_buf = Vec::new();
+            let quality = pick_level_within(
+                &BROTLI_BUDGET_QUALITIES,
+                &input_buf,
+                budget,
+                |quality, sample| {
+                    sample_buf.clear();
+                    compress_quality(sample, quality, &mut sample_buf)
+                },
+            )?;
+
+            let offset = output_buf.len();
+            compress_quality(&input_buf, quality, output_buf)?;
+            Ok(output_buf.len() - offset)
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "BROTLI", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            let decoder = brotli::Decompressor::new(file, BROTLI_DEFAULT_BUFFER_SIZE);
+            decompress_reader(decoder, output_buf_columndata, "BROTLI")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // stream header and final empty meta-block, at least one byte
+            1
+        }
+
+        fn last_was_truncated(&self) -> bool {
+            self.truncated
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.truncated = false;
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "BROTLI"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the brotli crate version this build requires
+            "3.3"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("BROTLI", self.level, self.deterministic, self.allow_partial))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.level, self.deterministic, self.allow_partial))
+        }
+    }
+}
+#[cfg(any(feature = "brotli", test))]
+pub use brotli_codec::*;
+
+#[cfg(any(feature = "lz4", test))]
+mod lz4_codec {
+    use std::fs::File;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::path::Path;
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, concat_magic_frames,
+        decompress_into_view, decompress_reader, fingerprint, lz4_version, scratch_bytes,
+        Codec, CodecType, ConversionContext, DecompressHint,
+    };
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    const LZ4_BUFFER_SIZE: usize = 4096;
+
+    /// Codec for LZ4 compression algorithm.
+    pub struct LZ4Codec {
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl LZ4Codec {
+        /// Creates new LZ4 compression codec.
+        pub(crate) fn new() -> Self {
+            Self {
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for LZ4Codec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "LZ4", output_buf_columndata)?;
+
+            let len = uncompress_size.unwrap_or(0);
+            let mut output_buf = Vec::with_capacity(len);
+            output_buf_columndata.reserve(len / output_buf_columndata.type_width());
+
+            let mut buffer: [u8; LZ4_BUFFER_SIZE] = [0; LZ4_BUFFER_SIZE];
+            let mut total_len = 0;
+            // the decoder stops at the end of a frame, and `concat_frames` output holds
+            // several of them
+            let mut input = input_buf;
+            while !input.is_empty() {
+                let mut decoder =
+                    lz4::Decoder::new(input).decompress_hint(CodecType::LZ4, input_buf)?;
+                loop {
+                    let len = decoder
+                        .read(&mut buffer)
+                        .decompress_hint(CodecType::LZ4, input_buf)?;
+                    if len == 0 {
+                        break;
+                    }
+                    total_len += len;
+                    output_buf.write_all(&buffer[0..len])?;
+                }
+                let (rest, result) = decoder.finish();
+                result?;
+                input = rest;
+            }
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("LZ4", "decompress", output_buf_columndata.typename())?;
+
+            Ok(total_len)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("LZ4", "compress", input_buf_columndata.typename())?;
+
+            let mut encoder = lz4::EncoderBuilder::new().build(output_buf)?;
+            let mut from = 0;
+            loop {
+                let to = std::cmp::min(from + LZ4_BUFFER_SIZE, input_buf.len());
+                encoder.write_all(&input_buf[from..to])?;
+                from += LZ4_BUFFER_SIZE;
+                if from >= input_buf.len() {
+                    break;
+                }
+            }
+            encoder.finish().1.map_err(|e| e.into())
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "LZ4", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut encoder = lz4::EncoderBuilder::new().build(output)?;
+            let n = io::copy(input, &mut encoder)?;
+            encoder.finish().1?;
+            Ok(n)
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            // the decoder stops at the end of a frame, so keep decoding while input
+            // remains, as `decompress` does for `concat_frames` output
+            let mut input = BufReader::new(input);
+            let mut total = 0;
+            while !input.fill_buf()?.is_empty() {
+                let mut decoder = lz4::Decoder::new(input)?;
+                total += io::copy(&mut decoder, output)?;
+                let (rest, result) = decoder.finish();
+                result?;
+                input = rest;
+            }
+            Ok(total)
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "LZ4", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            decompress_reader(lz4::Decoder::new(file)?, output_buf_columndata, "LZ4")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // magic, frame descriptor, end mark and content checksum
+            15
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            concat_magic_frames(CodecType::LZ4, frames)
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "LZ4"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            lz4_version()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&"LZ4_FRAME")
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new())
+        }
+    }
+}
+#[cfg(any(feature = "lz4", test))]
+pub use lz4_codec::*;
+
+#[cfg(any(feature = "zstd", test))]
+mod zstd_codec {
+    use std::fs::File;
+    use std::io::{self, BufReader, Read, Write};
+    use std::path::Path;
+
+    use std::time::Duration;
+
+    use crate::compression::{
+        check_output_type, compress_vectored_stream, concat_magic_frames,
+        decompress_into_view, decompress_reader, fingerprint, pick_level_within,
+        read_to_end_partial, scratch_bytes, Codec, CodecType, ConversionContext,
+        DecompressHint, StreamProgress,
+    };
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec for Zstandard compression algorithm.
+    pub struct ZSTDCodec {
+        /// Level `compress` compresses at.
+        level: i32,
+        /// Whether the output must not depend on timing, which rules out
+        /// `compress_within`.
+        deterministic: bool,
+        /// Whether a truncated stream yields the values decoded before it.
+        allow_partial: bool,
+        /// Whether the previous `decompress` hit a truncated stream.
+        truncated: bool,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// How far the compression written through `compress_stream` has got.
+        progress: StreamProgress,
+        /// Dictionary compression and decompression start from; empty for none.
+        dictionary: Vec<u8>,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl ZSTDCodec {
+        /// Creates new Zstandard compression codec compressing at `level`.
+        pub(crate) fn new(level: i32, deterministic: bool, allow_partial: bool) -> Self {
+            Self {
+                level,
+                deterministic,
+                allow_partial,
+                truncated: false,
+                last_element_count: 0,
+                progress: StreamProgress::default(),
+                dictionary: Vec::new(),
+                view: Vec::new(),
+            }
+        }
+
+        /// Creates new Zstandard compression codec using `dictionary`.
+        ///
+        /// The dictionary is loaded once here, so that bytes zstd cannot load, such as
+        /// a truncated trained dictionary, are reported now rather than by the first
+        /// `decompress`. Bytes without the zstd dictionary magic number are used as raw
+        /// content.
+        pub(crate) fn with_dictionary(
+            level: i32,
+            deterministic: bool,
+            allow_partial: bool,
+            dictionary: Vec<u8>,
+        ) -> Result<Self> {
+            zstd::stream::raw::Decoder::with_dictionary(&dictionary)
+                .map_err(|e| general_err!("Invalid ZSTD dictionary: {}", e))?;
+            Ok(Self {
+                dictionary,
+                ..Self::new(level, deterministic, allow_partial)
+            })
+        }
+    }
+
+    /// Levels `compress_within` tries, from slowest to fastest.
+    const ZSTD_BUDGET_LEVELS: [i32; 4] = [19, 9, 3, 1];
+
+    fn compress_level(
+        input_buf: &[u8],
+        level: i32,
+        dictionary: &[u8],
+        output_buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let mut encoder = zstd::Encoder::with_dictionary(output_buf, level, dictionary)?;
+        encoder.write_all(input_buf)?;
+        match encoder.finish() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    impl Codec for ZSTDCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "ZSTD", output_buf_columndata)?;
+
+            let len = uncompress_size.unwrap_or(0);
+            let mut output_buf = Vec::with_capacity(len);
+            output_buf_columndata.reserve(len / output_buf_columndata.type_width());
+
+            let mut decoder = zstd::Decoder::with_dictionary(input_buf, &self.dictionary)
+                .decompress_hint(CodecType::ZSTD, input_buf)?;
+            let width = output_buf_columndata.type_width();
+            self.truncated =
+                read_to_end_partial(&mut decoder, &mut output_buf, width, self.allow_partial)
+                    .decompress_hint(CodecType::ZSTD, input_buf)?;
+            let n = output_buf.len();
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("ZSTD", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("ZSTD", "compress", input_buf_columndata.typename())?;
+
+            compress_level(&input_buf, self.level, &self.dictionary, output_buf)
+        }
+
+        fn compress_vectored(
+            &mut self,
+            inputs: &[&ColumnData],
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = inputs.iter().map(|input| input.len()).sum();
+            compress_vectored_stream(self, "ZSTD", inputs, output_buf)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let (level, dictionary) = (self.level, &self.dictionary);
+            self.progress.track(output, |output| {
+                let mut encoder = zstd::Encoder::with_dictionary(output, level, dictionary)?;
+                let n = io::copy(input, &mut encoder)?;
+                encoder.finish()?;
+                Ok(n)
+            })
+        }
+
+        fn decompress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut decoder =
+                zstd::Decoder::with_dictionary(BufReader::new(input), &self.dictionary)?;
+            Ok(io::copy(&mut decoder, output)?)
+        }
+
+        fn save_state(&self) -> Result<Vec<u8>> {
+            Ok(self.progress.save(self.config_fingerprint()))
+        }
+
+        fn restore_state(&mut self, bytes: &[u8]) -> Result<()> {
+            self.progress = StreamProgress::restore(bytes, self.config_fingerprint())?;
+            Ok(())
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            self.last_element_count = input_buf_columndata.len();
+            if self.deterministic {
+                return Err(general_err!(
+                    "ZSTD compress_within: adaptive levels conflict with deterministic output"
+                ));
+            }
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("ZSTD", "compress", input_buf_columndata.typename())?;
+
+            let mut sample_buf = Vec::new();
+            let level =
+                pick_level_within(&ZSTD_BUDGET_LEVELS, &input_buf, budget, |level, sample| {
+                    sample_buf.clear();
+                    compress_level(sample, level, &self.dictionary, &mut sample_buf)
+                })?;
+
+            let offset = output_buf.len();
+            compress_level(&input_buf, level, &self.dictionary, output_buf)?;
+            Ok(output_buf.len() - offset)
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "ZSTD", output_buf_columndata)?;
+
+            let file = BufReader::new(File::open(path)?);
+            let decoder = zstd::Decoder::with_dictionary(file, &self.dictionary)?;
+            decompress_reader(decoder, output_buf_columndata, "ZSTD")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // magic, minimal frame header and the last block header
+            9
+        }
+
+        fn last_was_truncated(&self) -> bool {
+            self.truncated
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            concat_magic_frames(CodecType::ZSTD, frames)
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.truncated = false;
+            self.last_element_count = 0;
+            self.progress = StreamProgress::default();
+        }
+
+        fn name(&self) -> &'static str {
+            "ZSTD"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            zstd::zstd_safe::version_string()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&(
+                "ZSTD",
+                self.level,
+                self.deterministic,
+                self.allow_partial,
+                &self.dictionary,
+            ))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self {
+                dictionary: self.dictionary.clone(),
+                ..Self::new(self.level, self.deterministic, self.allow_partial)
+            })
+        }
+    }
+}
+#[cfg(any(feature = "zstd", test))]
+pub use zstd_codec::*;
+
+#[cfg(any(feature = "lz4", test))]
+mod lz4_raw_codec {
+    use crate::compression::{
+        check_output_type, decompress_into_view, fingerprint, lz4_version, scratch_bytes,
+        Codec, CodecType, ConversionContext, DecompressHint,
+    };
+    use crate::errors::ParquetError;
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Largest input LZ4 compresses into a single block, `LZ4_MAX_INPUT_SIZE` in lz4.h.
+    const LZ4_MAX_INPUT_SIZE: usize = 0x7E00_0000;
+
+    /// Largest ratio between decompressed and compressed length of an LZ4 block.
+    const LZ4_MAX_EXPANSION: usize = 255;
+
+    /// Decompresses an LZ4 block whose decompressed length is not known, starting
+    /// from 4x the compressed length and doubling the buffer until the block fits.
+    fn decompress_unknown_size(input_buf: &[u8]) -> Result<Vec<u8>> {
+        let limit = input_buf
+            .len()
+            .saturating_mul(LZ4_MAX_EXPANSION)
+            .min(LZ4_MAX_INPUT_SIZE);
+        let mut capacity = input_buf.len().saturating_mul(4).clamp(64, limit.max(64));
+        loop {
+            let mut output_buf = vec![0; capacity];
+            match lz4::block::decompress_to_buffer(
+                input_buf,
+                Some(capacity as i32),
+                &mut output_buf,
+            ) {
+                Ok(n) => {
+                    output_buf.truncate(n);
+                    return Ok(output_buf);
+                }
+                Err(_) if capacity < limit => {
+                    capacity = capacity.saturating_mul(2).min(limit);
+                }
+                Err(e) => return Err(e).decompress_hint(CodecType::LZ4_RAW, input_buf),
+            }
+        }
+    }
+
+    /// Codec for LZ4 Raw compression algorithm.
+    pub struct LZ4RawCodec {
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl LZ4RawCodec {
+        /// Creates new LZ4 Raw compression codec.
+        pub(crate) fn new() -> Self {
+            Self {
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+    }
+
+    impl Codec for LZ4RawCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "LZ4_RAW", output_buf_columndata)?;
+
+            let required_len = match uncompress_size {
+                Some(uncompress_size) => uncompress_size,
+                None => {
+                    let output_buf = decompress_unknown_size(input_buf)?;
+                    output_buf_columndata
+                        .convert_from_u8(&output_buf)
+                        .conversion_context(
+                            "LZ4_RAW",
+                            "decompress",
+                            output_buf_columndata.typename(),
+                        )?;
+                    return Ok(output_buf.len());
+                }
+            };
+            let mut output_buf = Vec::with_capacity(required_len);
+            output_buf_columndata.reserve(required_len / output_buf_columndata.type_width());
+
+            let offset = output_buf.len();
+            output_buf.resize(offset + required_len, 0);
+            let n = lz4::block::decompress_to_buffer(
+                input_buf,
+                Some(required_len.try_into().unwrap()),
+                &mut output_buf[offset..],
+            )
+            .decompress_hint(CodecType::LZ4_RAW, input_buf)?;
+            if n != required_len {
+                return Err(ParquetError::General(
+                    "LZ4RawCodec uncompress_size is not the expected one".into(),
+                ));
+            }
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("LZ4_RAW", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("LZ4_RAW", "compress", input_buf_columndata.typename())?;
+
+            let offset = output_buf.len();
+            let required_len = lz4::block::compress_bound(input_buf.len())?;
+            output_buf.resize(offset + required_len, 0);
+            match lz4::block::compress_to_buffer(
+                &input_buf,
+                None,
+                false,
+                &mut output_buf[offset..],
+            ) {
+                Ok(n) => {
+                    output_buf.truncate(offset + n);
+                    Ok(())
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // the token byte of the single sequence
+            1
+        }
+
+        fn max_input_len(&self) -> usize {
+            LZ4_MAX_INPUT_SIZE
+        }
+
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            // every 255 byte match length extension costs at least one input byte
+            Some(255.0)
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "LZ4_RAW"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            lz4_version()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&"LZ4_RAW")
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new())
+        }
+    }
+}
+#[cfg(any(feature = "lz4", test))]
+pub use lz4_raw_codec::*;
+
+#[cfg(any(feature = "lz4", test))]
+mod lz4_hadoop_codec {
+    use crate::compression::lz4_codec::LZ4Codec;
+    use crate::compression::lz4_raw_codec::LZ4RawCodec;
+    use crate::compression::{
+        check_output_type, decompress_into_view, fingerprint, lz4_version, scratch_bytes,
+        Codec, ConversionContext,
+    };
+    use crate::errors::{ParquetError, Result};
+    use log::warn;
+    use std::io;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Size of u32 type.
+    const SIZE_U32: usize = std::mem::size_of::<u32>();
+
+    /// Length of the LZ4_HADOOP prefix.
+    const PREFIX_LEN: usize = SIZE_U32 * 2;
+
+    /// Codec for LZ4 Hadoop compression algorithm.
+    pub struct LZ4HadoopCodec {
+        /// Whether or not to fallback to other LZ4 implementations on error.
+        /// Fallback is done to be backward compatible with older versions of this
+        /// library and older versions parquet-cpp.
+        backward_compatible_lz4: bool,
+        /// Whether frames that fail to decompress are skipped instead of failing.
+        skip_corrupt_frames: bool,
+        /// Indexes of the frames the last `decompress` call skipped.
+        skipped_frames: Vec<usize>,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl LZ4HadoopCodec {
+        /// Creates new LZ4 Hadoop compression codec.
+        pub(crate) fn new(backward_compatible_lz4: bool, skip_corrupt_frames: bool) -> Self {
+            Self {
+                backward_compatible_lz4,
+                skip_corrupt_frames,
+                skipped_frames: Vec::new(),
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+    }
+
+    /// Decompresses the compressed data of a single Hadoop frame into `output`, which
+    /// must hold at least `expected_decompressed_size` bytes.
+    fn decompress_hadoop_frame(
+        frame: &[u8],
+        expected_decompressed_size: usize,
+        output: &mut [u8],
+    ) -> io::Result<()> {
+        if output.len() < expected_decompressed_size {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Not enough bytes to hold advertised output",
+            ));
+        }
+        let decompressed_size =
+            lz4::block::decompress_to_buffer(frame, Some(output.len() as i32), output)?;
+        if decompressed_size != expected_decompressed_size {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unexpected decompressed size",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Try to decompress the buffer as if it was compressed with the Hadoop Lz4Codec.
+    /// Adapted from pola-rs [compression.rs:try_decompress_hadoop](https://pola-rs.github.io/polars/src/parquet2/compression.rs.html#225)
+    /// Translated from the apache arrow c++ function [TryDecompressHadoop](https://github.com/apache/arrow/blob/bf18e6e4b5bb6180706b1ba0d597a65a4ce5ca48/cpp/src/arrow/util/compression_lz4.cc#L474).
+    /// Returns error if decompression failed.
+    ///
+    /// If `skip_corrupt` is set, frames that fail to decompress are left out of the
+    /// output and their indexes pushed to `skipped`, as long as at least one frame
+    /// decompresses.
+    fn try_decompress_hadoop(
+        input_buf: &[u8],
+        output_buf: &mut [u8],
+        skip_corrupt: bool,
+        skipped: &mut Vec<usize>,
+    ) -> io::Result<usize> {
+        // Parquet files written with the Hadoop Lz4Codec use their own framing.
+        // The input buffer can contain an arbitrary number of "frames", each
+        // with the following structure:
+        // - bytes 0..3: big-endian uint32_t representing the frame decompressed size
+        // - bytes 4..7: big-endian uint32_t representing the frame compressed size
+        // - bytes 8...: frame compressed data
+        //
+        // The Hadoop Lz4Codec source code can be found here:
+        // https://github.com/apache/hadoop/blob/trunk/hadoop-mapreduce-project/hadoop-mapreduce-client/hadoop-mapreduce-client-nativetask/src/main/native/src/codec/Lz4Codec.cc
+        let mut input_len = input_buf.len();
+        let mut input = input_buf;
+        let mut read_bytes = 0;
+        let mut output: &mut [u8] = output_buf;
+        let mut frame_index = 0;
+        while input_len >= PREFIX_LEN {
+            let mut bytes = [0; SIZE_U32];
+            bytes.copy_from_slice(&input[0..4]);
+            let expected_decompressed_size = u32::from_be_bytes(bytes);
+            let mut bytes = [0; SIZE_U32];
+            bytes.copy_from_slice(&input[4..8]);
+            let expected_compressed_size = u32::from_be_bytes(bytes);
+            input = &input[PREFIX_LEN..];
+            input_len -= PREFIX_LEN;
+
+            if input_len < expected_compressed_size as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "Not enough bytes for Hadoop frame",
+                ));
+            }
+
+            let written = match decompress_hadoop_frame(
+                &input[..expected_compressed_size as usize],
+                expected_decompressed_size as usize,
+                output,
+            ) {
+                Ok(()) => expected_decompressed_size as usize,
+                Err(e) if skip_corrupt => {
+                    warn!(
+                        "LZ4_HADOOP decompress: skipping corrupt frame {}: {}",
+                        frame_index, e
+                    );
+                    skipped.push(frame_index);
+                    0
+                }
+                Err(e) => return Err(e),
+            };
+            frame_index += 1;
+            input_len -= expected_compressed_size as usize;
+            read_bytes += written;
+            if input_len > 0 {
+                input = &input[expected_compressed_size as usize..];
+                output = &mut output[written..];
+            } else {
+                break;
+            }
+        }
+        if read_bytes == 0 && !skipped.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "No Hadoop frame could be decompressed",
+            ))
+        } else if input_len == 0 {
+            Ok(read_bytes)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Not all input are consumed",
+            ))
+        }
+    }
+
+    impl Codec for LZ4HadoopCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "LZ4_HADOOP", output_buf_columndata)?;
+
+            let required_len = match uncompress_size {
+                Some(n) => n,
+                None => {
+                    return Err(ParquetError::General(
+                        "LZ4HadoopCodec unsupported without uncompress_size".into(),
+                    ))
+                }
+            };
+            let mut output_buf = Vec::with_capacity(required_len);
+            output_buf_columndata.reserve(required_len / output_buf_columndata.type_width());
+
+            let output_len = output_buf.len();
+            output_buf.resize(output_len + required_len, 0);
+            self.skipped_frames.clear();
+            let n = match try_decompress_hadoop(
+                input_buf,
+                &mut output_buf[output_len..],
+                self.skip_corrupt_frames,
+                &mut self.skipped_frames,
+            ) {
+                Ok(n) if !self.skipped_frames.is_empty() => {
+                    // the skipped frames' values are missing from the output
+                    output_buf.truncate(output_len + n);
+                    n
+                }
+                Ok(n) => {
+                    if n != required_len {
+                        return Err(ParquetError::General(
+                            "LZ4HadoopCodec uncompress_size is not the expected one"
+                                .into(),
+                        ));
+                    }
+                    n
+                }
+                Err(e) if !self.backward_compatible_lz4 => return Err(e.into()),
+                // Fallback done to be backward compatible with older versions of this
+                // libray and older versions of parquet-cpp. The fallback codecs write
+                // straight into `output_buf_columndata`, and only on success.
+                Err(_) => {
+                    self.skipped_frames.clear();
+                    return match LZ4Codec::new().decompress(
+                        input_buf,
+                        output_buf_columndata,
+                        uncompress_size,
+                    ) {
+                        Ok(n) => Ok(n),
+                        Err(_) => LZ4RawCodec::new().decompress(
+                            input_buf,
+                            output_buf_columndata,
+                            uncompress_size,
+                        ),
+                    }
+                }
+            };
+
+            output_buf_columndata
+                .convert_from_u8(&output_buf)
+                .conversion_context("LZ4_HADOOP", "decompress", output_buf_columndata.typename())?;
+
+            Ok(n)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = scratch_bytes();
+            input_buf_columndata
+                .convert_to_u8(&mut input_buf)
+                .conversion_context("LZ4_HADOOP", "compress", input_buf_columndata.typename())?;
+
+            // Allocate memory to store the LZ4_HADOOP prefix.
+            let offset = output_buf.len();
+            output_buf.resize(offset + PREFIX_LEN, 0);
+
+            // Append LZ4_RAW compressed bytes after prefix.
+            LZ4RawCodec::new().compress(input_buf_columndata, output_buf)?;
+
+            // Prepend decompressed size and compressed size in big endian to be compatible
+            // with LZ4_HADOOP.
+            let output_buf = &mut output_buf[offset..];
+            let compressed_size = output_buf.len() - PREFIX_LEN;
+            let compressed_size = compressed_size as u32;
+            let uncompressed_size = input_buf.len() as u32;
+            output_buf[..SIZE_U32].copy_from_slice(&uncompressed_size.to_be_bytes());
+            output_buf[SIZE_U32..PREFIX_LEN].copy_from_slice(&compressed_size.to_be_bytes());
+
+            Ok(())
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // compressed and uncompressed size prefix
+            PREFIX_LEN
+        }
+
+        fn max_input_len(&self) -> usize {
+            // one LZ4 block, whose sizes must also fit the u32 prefix
+            LZ4RawCodec::new().max_input_len().min(u32::MAX as usize)
+        }
+
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            // LZ4 blocks behind a size prefix, or LZ4 frames for the legacy format
+            LZ4RawCodec::new().max_expansion_ratio()
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        fn last_skipped_frames(&self) -> &[usize] {
+            &self.skipped_frames
+        }
+
+        fn reset(&mut self) {
+            self.skipped_frames.clear();
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "LZ4_HADOOP"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            lz4_version()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&(
+                "LZ4_HADOOP",
+                self.backward_compatible_lz4,
+                self.skip_corrupt_frames,
+            ))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.backward_compatible_lz4, self.skip_corrupt_frames))
+        }
+    }
+}
+#[cfg(any(feature = "lz4", test))]
+pub use lz4_hadoop_codec::*;
+
+#[cfg(any(feature = "q_compress", test))]
+mod qcom_codec {
+    use crate::compression::{
+        check_output_type, decompress_typed_with_bytes, fingerprint, Codec,
+    };
+    use crate::errors::{ParquetError, Result};
+
+    use q_compress::{
+        auto_compress, auto_compressor_config, auto_decompress, data_types::NumberLike,
+        simple_compress,
+    };
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the big-endian u32 prefix holding the size of each q_compress frame.
+    const FRAME_LEN_PREFIX: usize = 4;
+
+    /// Magic number every q_compress frame starts with, "qco!".
+    const QCOM_MAGIC: [u8; 4] = *b"qco!";
+
+    /// Codec for q-compress numerical compression algorithm.
+    ///
+    /// Every `compress` call appends one q_compress frame prefixed with its length, so
+    /// several frames written into the same buffer stay separable. `decompress` reads
+    /// all frames in the buffer and appends their values in order. Buffers written
+    /// before frames were prefixed hold a single bare frame, which `decompress` still
+    /// reads.
+    ///
+    /// q_compress picks its compressor configuration from the data alone, without any
+    /// randomness, so the same input always compresses to the same bytes.
+    pub struct QComCodec {
+        /// Delta encoding order to compress with; chosen from the data when `None`.
+        delta_order: Option<usize>,
+        /// Level `compress` compresses at.
+        level: usize,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+    }
+
+    impl QComCodec {
+        /// Creates new q-compress compression codec compressing at `level`.
+        pub(crate) fn new(delta_order: Option<usize>, level: usize) -> Self {
+            Self {
+                delta_order,
+                level,
+                last_element_count: 0,
+            }
+        }
+
+        /// Returns the number of `typename` values that decompressing `input_buf` yields,
+        /// so callers can preallocate.
+        ///
+        /// q_compress keeps element counts in per-chunk metadata rather than in its
+        /// header, and the simple API offers no way to read them without decoding. This
+        /// therefore decodes each frame and discards its values; it saves memory, not
+        /// time, compared to `decompress`.
+        pub fn peek_count(input_buf: &[u8], typename: &str) -> Result<usize> {
+            match typename {
+                "u16" => count_frames::<u16>(input_buf, typename),
+                "u32" => count_frames::<u32>(input_buf, typename),
+                "u64" => count_frames::<u64>(input_buf, typename),
+                "i16" => count_frames::<i16>(input_buf, typename),
+                "i32" => count_frames::<i32>(input_buf, typename),
+                "i64" => count_frames::<i64>(input_buf, typename),
+                "f32" => count_frames::<f32>(input_buf, typename),
+                "f64" => count_frames::<f64>(input_buf, typename),
+                "i24" => count_frames::<i32>(input_buf, typename),
+                "u24" => count_frames::<u32>(input_buf, typename),
+                _ => Err(general_err!("QCOM peek_count: unsupported type {}", typename)),
+            }
+        }
+    }
+
+    /// Wraps a q_compress decoding failure with the column type being decoded.
+    fn decompress_err(typename: &str, e: impl std::fmt::Display) -> ParquetError {
+        general_err!("QCOM decompress: failed to decode {} values: {}", typename, e)
+    }
+
+    /// Compresses `values` at `level` into a single length-prefixed frame appended to
+    /// `output_buf`, with a fixed delta encoding order if one is given.
+    fn compress_frame<T: NumberLike>(
+        values: &[T],
+        delta_order: Option<usize>,
+        level: usize,
+        output_buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let frame = match delta_order {
+            Some(order) => simple_compress(
+                auto_compressor_config(values, level).with_delta_encoding_order(order),
+                values,
+            ),
+            None => auto_compress::<T>(values, level),
+        };
+        push_frame(&frame, output_buf)
+    }
+
+    /// Appends `frame` to `output_buf`, prefixed with its length.
+    fn push_frame(frame: &[u8], output_buf: &mut Vec<u8>) -> Result<()> {
+        let frame_len = u32::try_from(frame.len())
+            .map_err(|_| general_err!("QCOM compress: frame too large: {} bytes", frame.len()))?;
+        output_buf.extend_from_slice(&frame_len.to_be_bytes());
+        output_buf.extend_from_slice(frame);
+        Ok(())
+    }
+
+    /// Splits `input_buf` into its length-prefixed q_compress frames.
+    ///
+    /// A buffer starting with the q_compress magic rather than a length is a single
+    /// bare frame, as written before frames were prefixed. A prefixed buffer can only
+    /// start with the magic for a frame of about 1.9 GB, and then has the magic again
+    /// right after it.
+    fn split_frames(input_buf: &[u8]) -> Result<Vec<&[u8]>> {
+        if input_buf.starts_with(&QCOM_MAGIC)
+            && !input_buf[FRAME_LEN_PREFIX..].starts_with(&QCOM_MAGIC)
+        {
+            return Ok(vec![input_buf]);
+        }
+
+        let mut input = input_buf;
+        let mut frames = Vec::new();
+        while !input.is_empty() {
+            if input.len() < FRAME_LEN_PREFIX {
+                return Err(eof_err!("QCOM decompress: truncated frame length"));
+            }
+            let mut bytes = [0; FRAME_LEN_PREFIX];
+            bytes.copy_from_slice(&input[..FRAME_LEN_PREFIX]);
+            let frame_len = u32::from_be_bytes(bytes) as usize;
+            input = &input[FRAME_LEN_PREFIX..];
+            if input.len() < frame_len {
+                return Err(eof_err!(
+                    "QCOM decompress: frame of {} bytes truncated to {}",
+                    frame_len,
+                    input.len()
+                ));
+            }
+            frames.push(&input[..frame_len]);
+            input = &input[frame_len..];
+        }
+        Ok(frames)
+    }
+
+    /// Decompresses every length-prefixed frame in `input_buf`, appending the values to
+    /// `output`. Returns the number of values decoded.
+    fn decompress_frames<T: NumberLike>(
+        input_buf: &[u8],
+        typename: &str,
+        output: &mut Vec<T>,
+    ) -> Result<usize> {
+        let mut decoded = 0;
+        for frame in split_frames(input_buf)? {
+            let mut values =
+                auto_decompress::<T>(frame).map_err(|e| decompress_err(typename, e))?;
+            decoded += values.len();
+            output.append(&mut values);
+        }
+        Ok(decoded)
+    }
+
+    /// Counts the values in every frame of `input_buf`, one frame at a time.
+    fn count_frames<T: NumberLike>(input_buf: &[u8], typename: &str) -> Result<usize> {
+        let mut count = 0;
+        for frame in split_frames(input_buf)? {
+            count += auto_decompress::<T>(frame)
+                .map_err(|e| decompress_err(typename, e))?
+                .len();
+        }
+        Ok(count)
+    }
+
+    impl Codec for QComCodec {
+        /// Returns the number of values decoded, rather than a byte count.
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            check_output_type(self, "QCOM", output_buf_columndata)?;
+
+            match output_buf_columndata {
+                ColumnData::VecU8(_) | ColumnData::VecI8(_) => {
+                    unreachable!("QCOM output type is checked by check_output_type")
+                },
+                ColumnData::VecU16(x) => decompress_frames(input_buf, "u16", x),
+                ColumnData::VecU32(x) => decompress_frames(input_buf, "u32", x),
+                ColumnData::VecU64(x) => decompress_frames(input_buf, "u64", x),
+                ColumnData::VecI16(x) => decompress_frames(input_buf, "i16", x),
+                ColumnData::VecI32(x) => decompress_frames(input_buf, "i32", x),
+                ColumnData::VecI64(x) => decompress_frames(input_buf, "i64", x),
+                ColumnData::VecF32(x) => decompress_frames(input_buf, "f32", x),
+                ColumnData::VecF64(x) => decompress_frames(input_buf, "f64", x),
+                ColumnData::VecI24(x) => decompress_frames(input_buf, "i24", x),
+                ColumnData::VecU24(x) => decompress_frames(input_buf, "u24", x),
+            }
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
+
+        fn compress(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+        ) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+
+            let (order, level) = (self.delta_order, self.level);
+            match input_buf_columndata {
+                // q_compress has no 8-bit number type
+                ColumnData::VecU8(_) | ColumnData::VecI8(_) => Err(general_err!(
+                    "QCOM compress: unsupported input type {}",
+                    input_buf_columndata.typename()
+                )),
+                ColumnData::VecU16(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecU32(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecU64(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecI16(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecI32(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecI64(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecF32(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecF64(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecI24(x) => compress_frame(x, order, level, output_buf),
+                ColumnData::VecU24(x) => compress_frame(x, order, level, output_buf),
+            }
+        }
+
+        fn supports_type(&self, typename: &str) -> bool {
+            !matches!(typename, "u8" | "i8")
+        }
+
+        fn framing_overhead(&self) -> usize {
+            // frame length prefix, q_compress magic header and termination byte
+            FRAME_LEN_PREFIX + 5
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            // `decompress` reads every length-prefixed frame in its input, so bare
+            // frames are prefixed to stay separable
+            let mut output = Vec::new();
+            for frame in frames {
+                for frame in split_frames(frame)? {
+                    push_frame(frame, &mut output)?;
+                }
+            }
+            Ok(output)
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+        }
+
+        fn name(&self) -> &'static str {
+            "QCOM"
+        }
+
+        fn backend_version(&self) -> &'static str {
+            // the q_compress crate version this build requires
+            "0.11.4"
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("QCOM", self.delta_order, self.level))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.delta_order, self.level))
+        }
+    }
+}
+#[cfg(any(feature = "q_compress", test))]
+pub use qcom_codec::*;
+
+#[cfg(feature = "encryption")]
+mod encrypt_codec {
+    use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use aes_gcm::{Aes256Gcm, Nonce};
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length in bytes of the AES-GCM nonce prepended to every encrypted buffer.
+    const NONCE_LEN: usize = 12;
+
+    /// Length in bytes of the AES-GCM authentication tag appended to the ciphertext.
+    const TAG_LEN: usize = 16;
+
+    /// Codec wrapper that encrypts the output of another codec with AES-256-GCM.
+    ///
+    /// `compress` runs the inner codec and then encrypts its output under a fresh
+    /// random nonce, which is written in front of the ciphertext. `decompress` checks
+    /// the authentication tag before handing the plaintext to the inner codec, so any
+    /// tampering with the nonce or ciphertext is reported as an error. Because the
+    /// nonce is random, the output is never byte-for-byte reproducible.
+    pub struct EncryptCodec {
+        inner: Box<dyn Codec>,
+        cipher: Aes256Gcm,
+        /// Fingerprint of the key, as the cipher does not expose it.
+        key_fingerprint: u64,
+    }
+
+    impl EncryptCodec {
+        /// Creates a new codec compressing with `inner` and encrypting with the
+        /// 256-bit `key`.
+        pub fn new(inner: Box<dyn Codec>, key: &[u8; 32]) -> Self {
+            Self {
+                inner,
+                cipher: Aes256Gcm::new(key.into()),
+                key_fingerprint: fingerprint(key),
+            }
+        }
+
+        /// Authenticates and decrypts `input_buf`, a nonce followed by ciphertext.
+        fn decrypt(&self, input_buf: &[u8]) -> Result<Vec<u8>> {
+            if input_buf.len() < NONCE_LEN {
+                return Err(eof_err!(
+                    "EncryptCodec decompress: {} bytes is shorter than the nonce",
+                    input_buf.len()
+                ));
+            }
+            let (nonce, ciphertext) = input_buf.split_at(NONCE_LEN);
+            self.cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| general_err!("EncryptCodec decompress: authentication failed"))
+        }
+    }
+
+    impl Codec for EncryptCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let plaintext = self.decrypt(input_buf)?;
+            self.inner
+                .decompress(&plaintext, output_buf_columndata, uncompress_size)
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            let plaintext = self.decrypt(input_buf)?;
+            self.inner
+                .decompress_with_bytes(&plaintext, output_buf_columndata, uncompress_size)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let mut compressed = Vec::new();
+            self.inner.compress(input_buf_columndata, &mut compressed)?;
+
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = self
+                .cipher
+                .encrypt(&nonce, compressed.as_slice())
+                .map_err(|_| general_err!("EncryptCodec compress: encryption failed"))?;
+
+            output_buf.extend_from_slice(&nonce);
+            output_buf.extend_from_slice(&ciphertext);
+            Ok(())
+        }
+
+        forward_to_inner!(
+            supports_type,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            reset,
+        );
+
+        fn framing_overhead(&self) -> usize {
+            NONCE_LEN + TAG_LEN + self.inner.framing_overhead()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&(
+                "Encrypt",
+                self.key_fingerprint,
+                self.inner.config_fingerprint(),
+            ))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self {
+                inner: self.inner.clone_codec(),
+                cipher: self.cipher.clone(),
+                key_fingerprint: self.key_fingerprint,
+            })
+        }
+    }
+}
+#[cfg(feature = "encryption")]
+pub use encrypt_codec::*;
+
+mod verify_codec {
+    use std::hash::Hasher;
+    use std::io::{self, Read, Write};
+    use std::time::Duration;
+
+    use twox_hash::XxHash64;
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that checks every compressed buffer decompresses back to its input.
+    ///
+    /// Values are compared by their byte representation, so NaN payloads must survive
+    /// the round trip too. Created by `create_codec` when round-trip verification is
+    /// enabled in `CodecOptions`.
+    pub struct VerifyRoundtripCodec {
+        inner: Box<dyn Codec>,
+    }
+
+    impl VerifyRoundtripCodec {
+        /// Creates a new codec verifying the output of `inner`.
+        pub fn new(inner: Box<dyn Codec>) -> Self {
+            Self { inner }
+        }
+
+        /// Checks that `output_buf[offset..]` decompresses back to `input`, truncating
+        /// `output_buf` to `offset` if it does not.
+        fn verify(
+            &mut self,
+            input: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            offset: usize,
+        ) -> Result<()> {
+            let mut expected = Vec::new();
+            input.convert_to_u8(&mut expected)?;
+            let mut decoded = input.slice(0, 0);
+            let mut actual = Vec::new();
+            let verified = self
+                .inner
+                .decompress(&output_buf[offset..], &mut decoded, Some(expected.len()))
+                .and_then(|_| decoded.convert_to_u8(&mut actual))
+                .is_ok()
+                && actual == expected;
+
+            if !verified {
+                output_buf.truncate(offset);
+                return Err(general_err!("roundtrip verification failed"));
+            }
+            Ok(())
+        }
+    }
+
+    /// Hashes the bytes passing through it, to verify a stream without holding its
+    /// uncompressed bytes in memory.
+    struct Hashing<T> {
+        inner: T,
+        hasher: XxHash64,
+        len: u64,
+    }
+
+    impl<T> Hashing<T> {
+        fn new(inner: T) -> Self {
+            Self {
+                inner,
+                hasher: XxHash64::with_seed(0),
+                len: 0,
+            }
+        }
+    }
+
+    impl<T: Read> Read for Hashing<T> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.hasher.write(&buf[..n]);
+            self.len += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl<T: Write> Write for Hashing<T> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = self.inner.write(buf)?;
+            self.hasher.write(&buf[..n]);
+            self.len += n as u64;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Codec for VerifyRoundtripCodec {
+        forward_to_inner!(
+            decompress,
+            decompress_file,
+            decompress_with_bytes,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            decompress_block,
+            reset,
+        );
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let offset = output_buf.len();
+            self.inner.compress(input_buf_columndata, output_buf)?;
+            self.verify(input_buf_columndata, output_buf, offset)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            let offset = output_buf.len();
+            let n = self
+                .inner
+                .compress_within(input_buf_columndata, output_buf, budget)?;
+            self.verify(input_buf_columndata, output_buf, offset)?;
+            Ok(n)
+        }
+
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            // the compressed stream is held back until it is verified, the input is
+            // only hashed
+            let state = self.inner.save_state().ok();
+            let mut input = Hashing::new(input);
+            let mut compressed = Vec::new();
+            self.inner.compress_stream(&mut input, &mut compressed)?;
+
+            let mut decoded = Hashing::new(io::sink());
+            let verified = self
+                .inner
+                .decompress_stream(&mut compressed.as_slice(), &mut decoded)
+                .is_ok()
+                && decoded.len == input.len
+                && decoded.hasher.finish() == input.hasher.finish();
+
+            if !verified {
+                // nothing was written, so the stream has not moved on
+                if let Some(state) = state {
+                    self.inner.restore_state(&state)?;
+                }
+                return Err(general_err!("roundtrip verification failed"));
+            }
+            output.write_all(&compressed)?;
+            Ok(input.len)
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("VerifyRoundtrip", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec()))
+        }
+    }
+}
+pub use verify_codec::*;
+
+mod canonical_nan_codec {
+    use std::time::Duration;
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that rewrites every NaN of a float column to the canonical quiet
+    /// NaN, `f32::NAN` or `f64::NAN`, before compressing it with another codec.
+    ///
+    /// Created by `create_codec` when NaN canonicalization is enabled in
+    /// `CodecOptions`.
+    pub struct CanonicalNanCodec {
+        inner: Box<dyn Codec>,
+    }
+
+    impl CanonicalNanCodec {
+        /// Creates a new codec canonicalizing the NaNs of the input of `inner`.
+        pub fn new(inner: Box<dyn Codec>) -> Self {
+            Self { inner }
+        }
+    }
+
+    /// Returns a copy of `values` with canonical NaNs, or `None` if it holds no
+    /// non-canonical NaN and can be compressed as is.
+    fn canonicalize(values: &ColumnData) -> Option<ColumnData> {
+        match values {
+            ColumnData::VecF32(x) => {
+                let canonical = f32::NAN.to_bits();
+                if !x.iter().any(|v| v.is_nan() && v.to_bits() != canonical) {
+                    return None;
+                }
+                let values = x.iter().map(|&v| if v.is_nan() { f32::NAN } else { v });
+                Some(ColumnData::VecF32(values.collect()))
+            }
+            ColumnData::VecF64(x) => {
+                let canonical = f64::NAN.to_bits();
+                if !x.iter().any(|v| v.is_nan() && v.to_bits() != canonical) {
+                    return None;
+                }
+                let values = x.iter().map(|&v| if v.is_nan() { f64::NAN } else { v });
+                Some(ColumnData::VecF64(values.collect()))
+            }
+            _ => None,
+        }
+    }
+
+    impl Codec for CanonicalNanCodec {
+        forward_to_inner!(
+            decompress,
+            decompress_file,
+            decompress_with_bytes,
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            decompress_block,
+            reset,
+        );
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            match canonicalize(input_buf_columndata) {
+                Some(canonical) => self.inner.compress(&canonical, output_buf),
+                None => self.inner.compress(input_buf_columndata, output_buf),
+            }
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            match canonicalize(input_buf_columndata) {
+                Some(canonical) => self.inner.compress_within(&canonical, output_buf, budget),
+                None => self
+                    .inner
+                    .compress_within(input_buf_columndata, output_buf, budget),
+            }
+        }
+
+        fn is_lossless(&self) -> bool {
+            false
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("CanonicalNan", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec()))
+        }
+    }
+}
+pub use canonical_nan_codec::*;
+
+mod precompressed_codec {
+    use std::time::Duration;
+
+    use log::warn;
+
+    use crate::compression::{fingerprint, sniff_codec, Codec, CodecType};
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that logs a warning when a byte column it compresses already
+    /// starts with the magic number of a known codec, then compresses it anyway.
+    ///
+    /// Created by `create_codec` when `warn_on_precompressed` is enabled in
+    /// `CodecOptions`.
+    pub struct PrecompressedCheckCodec {
+        inner: Box<dyn Codec>,
+        codec_type: CodecType,
+    }
+
+    impl PrecompressedCheckCodec {
+        /// Creates a new codec checking the input of `inner`, a `codec_type` codec.
+        pub fn new(inner: Box<dyn Codec>, codec_type: CodecType) -> Self {
+            Self { inner, codec_type }
+        }
+
+        /// Logs a warning if `input` is a byte column that looks compressed already.
+        fn check(&self, input: &ColumnData) {
+            if let ColumnData::VecU8(bytes) = input {
+                if let Some(found) = sniff_codec(bytes) {
+                    warn!(
+                        "{} compress: input already looks {} compressed",
+                        self.codec_type, found
+                    );
+                }
+            }
+        }
+    }
+
+    impl Codec for PrecompressedCheckCodec {
+        forward_to_inner!(
+            decompress,
+            decompress_file,
+            decompress_with_bytes,
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            decompress_block,
+            reset,
+        );
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.check(input_buf_columndata);
+            self.inner.compress(input_buf_columndata, output_buf)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            self.check(input_buf_columndata);
+            self.inner
+                .compress_within(input_buf_columndata, output_buf, budget)
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("PrecompressedCheck", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.codec_type))
+        }
+    }
+}
+pub use precompressed_codec::*;
+
+mod output_cap_codec {
+    use std::io::{self, Read, Write};
+    use std::time::Duration;
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that fails `compress` calls whose output is larger than a fixed
+    /// number of bytes, restoring the output buffer to its previous length.
+    ///
+    /// Created by `create_codec` when `max_output_size` is set in `CodecOptions`.
+    pub struct OutputCapCodec {
+        inner: Box<dyn Codec>,
+        max_size: usize,
+    }
+
+    impl OutputCapCodec {
+        /// Creates a new codec limiting the output of `inner` to `max_size` bytes.
+        pub fn new(inner: Box<dyn Codec>, max_size: usize) -> Self {
+            Self { inner, max_size }
+        }
+
+        /// Fails if more than `max_size` bytes were appended to `output_buf` after
+        /// `offset`, truncating it back to `offset`.
+        fn check(&self, output_buf: &mut Vec<u8>, offset: usize) -> Result<()> {
+            if output_buf.len() - offset > self.max_size {
+                output_buf.truncate(offset);
+                return Err(general_err!("compressed output exceeds max_output_size"));
+            }
+            Ok(())
+        }
+    }
+
+    /// Fails writes once more than `remaining` bytes would have been written.
+    struct CappedWriter<'a> {
+        inner: &'a mut dyn Write,
+        remaining: usize,
+        exceeded: bool,
+    }
+
+    impl Write for CappedWriter<'_> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.remaining {
+                self.exceeded = true;
+                return Err(io::Error::new(io::ErrorKind::Other, "max_output_size exceeded"));
+            }
+            let n = self.inner.write(buf)?;
+            self.remaining -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl Codec for OutputCapCodec {
+        forward_to_inner!(
+            decompress,
+            decompress_file,
+            decompress_with_bytes,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            decompress_block,
+            reset,
+        );
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let offset = output_buf.len();
+            self.inner.compress(input_buf_columndata, output_buf)?;
+            self.check(output_buf, offset)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            let offset = output_buf.len();
+            let n = self
+                .inner
+                .compress_within(input_buf_columndata, output_buf, budget)?;
+            self.check(output_buf, offset)?;
+            Ok(n)
+        }
+
+        /// Fails once the compressed stream exceeds `max_size` bytes. Unlike `compress`,
+        /// the bytes already written to `output` cannot be taken back.
+        fn compress_stream(
+            &mut self,
+            input: &mut dyn Read,
+            output: &mut dyn Write,
+        ) -> Result<u64> {
+            let mut output = CappedWriter {
+                inner: output,
+                remaining: self.max_size,
+                exceeded: false,
+            };
+            match self.inner.compress_stream(input, &mut output) {
+                Err(_) if output.exceeded => {
+                    Err(general_err!("compressed output exceeds max_output_size"))
+                }
+                result => result,
+            }
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("OutputCap", self.max_size, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.max_size))
+        }
+    }
+}
+pub use output_cap_codec::*;
+
+mod delta_codec {
+    use std::time::Duration;
+
+    use crate::compression::{decompress_typed_with_bytes, fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Splits the residual width byte written ahead of delta encoded values off
+    /// `input_buf`.
+    pub(crate) fn split_residual_width(input_buf: &[u8]) -> Result<(u8, &[u8])> {
+        match input_buf.split_first() {
+            Some((&width, rest)) => Ok((width, rest)),
+            None => Err(eof_err!("Missing delta residual width")),
+        }
+    }
+
+    /// Returns an empty column to decompress delta residuals of `width` bytes into,
+    /// for values of the type of `output`: of that type, or of the wider type
+    /// [`ColumnData::delta_encode_promoted`] promotes it to.
+    ///
+    /// Also returns `uncompress_size`, the expected size of the values, scaled to the
+    /// size of the residuals.
+    pub(crate) fn delta_residuals(
+        width: u8,
+        output: &ColumnData,
+        uncompress_size: Option<usize>,
+    ) -> Result<(ColumnData, Option<usize>)> {
+        let residuals = output.slice(0, 0);
+        let narrow = residuals.type_width();
+        if narrow == width as usize {
+            return Ok((residuals, uncompress_size));
+        }
+        match residuals.delta_widened() {
+            Some(wide) if wide.type_width() == width as usize => {
+                Ok((wide, uncompress_size.map(|n| n / narrow * width as usize)))
+            }
+            _ => Err(general_err!(
+                "Delta residuals of {} bytes cannot decode into {} values",
+                width,
+                output.typename()
+            )),
+        }
+    }
+
+    /// Codec wrapper that delta-encodes integer columns before compressing them with
+    /// another codec, and decodes them again after decompressing.
+    ///
+    /// Float columns pass through unchanged. When a difference overflows the element
+    /// type, the differences are kept in a wider type instead, see
+    /// [`ColumnData::delta_encode_promoted`]. Every buffer starts with one byte holding
+    /// the width in bytes of the differences that follow. Created by `create_codec` for
+    /// byte codecs when `CodecOptions` carries a sorted encoding hint.
+    pub struct DeltaCodec {
+        inner: Box<dyn Codec>,
+    }
+
+    impl DeltaCodec {
+        /// Creates a new codec delta-encoding the input of `inner`.
+        pub fn new(inner: Box<dyn Codec>) -> Self {
+            Self { inner }
+        }
+    }
+
+    impl Codec for DeltaCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let (width, input_buf) = split_residual_width(input_buf)?;
+            let (mut residuals, size) =
+                delta_residuals(width, output_buf_columndata, uncompress_size)?;
+            let n = self.inner.decompress(input_buf, &mut residuals, size)?;
+            residuals.delta_decode();
+            output_buf_columndata.extend_narrowed(&residuals)?;
+            Ok(n)
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let mut encoded = input_buf_columndata.clone();
+            encoded.delta_encode_promoted();
+            output_buf.push(encoded.type_width() as u8);
+            self.inner.compress(&encoded, output_buf)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            let mut encoded = input_buf_columndata.clone();
+            encoded.delta_encode_promoted();
+            output_buf.push(encoded.type_width() as u8);
+            Ok(1 + self.inner.compress_within(&encoded, output_buf, budget)?)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            reset,
+        );
+
+        fn framing_overhead(&self) -> usize {
+            1 + self.inner.framing_overhead()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("Delta", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec()))
+        }
+    }
+}
+pub use delta_codec::*;
+
+mod block_split_codec {
+    use std::path::Path;
+
+    use crate::compression::{
+        decompress_into_view, decompress_typed_with_bytes, fingerprint, Codec,
+    };
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the big-endian u32 prefix holding the size of each compressed block.
+    const BLOCK_LEN_PREFIX: usize = 4;
+
+    /// Codec wrapper that compresses inputs larger than `block_len` bytes as several
+    /// blocks with another codec, each prefixed with its compressed length.
+    ///
+    /// Inputs of at most `block_len` bytes are compressed by the inner codec as usual,
+    /// so its output format is unchanged for them. `decompress` tells the two apart by
+    /// the uncompressed size, which split inputs therefore need. Created by
+    /// `create_codec` for block codecs when `CodecOptions` enables block splitting.
+    pub struct BlockSplitCodec {
+        inner: Box<dyn Codec>,
+        block_len: usize,
+        last_element_count: usize,
+        /// Buffer `decompress_view` decompresses into.
+        view: Vec<u8>,
+    }
+
+    impl BlockSplitCodec {
+        /// Creates a new codec splitting the input of `inner` into blocks of at most
+        /// `block_len` uncompressed bytes.
+        pub fn new(inner: Box<dyn Codec>, block_len: usize) -> Self {
+            Self {
+                inner,
+                block_len,
+                last_element_count: 0,
+                view: Vec::new(),
+            }
+        }
+
+        /// Returns whether a column of `uncompressed_len` bytes is split into blocks.
+        fn is_split(&self, uncompressed_len: Option<usize>) -> bool {
+            matches!(uncompressed_len, Some(len) if len > self.block_len)
+        }
+
+        /// Decompresses the length-prefixed blocks in `input_buf`, appending their
+        /// values to `output_buf_columndata`. Returns the number of bytes decompressed.
+        fn decompress_blocks(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: usize,
+        ) -> Result<usize> {
+            let block_size = self.block_size(output_buf_columndata);
+            let mut input = input_buf;
+            let mut decompressed = 0;
+            while !input.is_empty() {
+                let (block, rest) = split_block(input)?;
+                let size = block_size.min(uncompress_size.saturating_sub(decompressed));
+                decompressed += self
+                    .inner
+                    .decompress(block, output_buf_columndata, Some(size))?;
+                input = rest;
+            }
+            Ok(decompressed)
+        }
+
+        /// Returns the number of uncompressed bytes in every block but the last, for
+        /// columns of the element type of `output_buf_columndata`.
+        fn block_size(&self, output_buf_columndata: &ColumnData) -> usize {
+            let width = output_buf_columndata.type_width();
+            (self.block_len / width).max(1) * width
+        }
+    }
+
+    /// Splits the first length-prefixed block off `input`, returning the block without
+    /// its prefix and the rest of `input`.
+    fn split_block(input: &[u8]) -> Result<(&[u8], &[u8])> {
+        if input.len() < BLOCK_LEN_PREFIX {
+            return Err(eof_err!("Block split decompress: truncated block length"));
+        }
+        let mut len = [0; BLOCK_LEN_PREFIX];
+        len.copy_from_slice(&input[..BLOCK_LEN_PREFIX]);
+        let len = u32::from_be_bytes(len) as usize;
+        let input = &input[BLOCK_LEN_PREFIX..];
+        if input.len() < len {
+            return Err(eof_err!(
+                "Block split decompress: block of {} bytes truncated to {}",
+                len,
+                input.len()
+            ));
+        }
+        Ok(input.split_at(len))
+    }
+
+    impl Codec for BlockSplitCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            match uncompress_size {
+                Some(size) if self.is_split(uncompress_size) => {
+                    self.decompress_blocks(input_buf, output_buf_columndata, size)
+                }
+                _ => self
+                    .inner
+                    .decompress(input_buf, output_buf_columndata, uncompress_size),
+            }
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            match uncompress_size {
+                Some(size) if self.is_split(uncompress_size) => {
+                    let input_buf = std::fs::read(path)?;
+                    self.decompress_blocks(&input_buf, output_buf_columndata, size)
+                }
+                _ => self
+                    .inner
+                    .decompress_file(path, output_buf_columndata, uncompress_size),
+            }
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            if self.is_split(uncompress_size) {
+                return decompress_typed_with_bytes(
+                    self,
+                    input_buf,
+                    output_buf_columndata,
+                    uncompress_size,
+                );
+            }
+            self.inner
+                .decompress_with_bytes(input_buf, output_buf_columndata, uncompress_size)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let width = input_buf_columndata.type_width();
+            let len = input_buf_columndata.len();
+            self.last_element_count = len;
+            if !self.is_split(Some(len * width)) {
+                return self.inner.compress(input_buf_columndata, output_buf);
+            }
+            let block_elements = (self.block_len / width).max(1);
+            for start in (0..len).step_by(block_elements) {
+                let block = input_buf_columndata.slice(start, block_elements.min(len - start));
+                let offset = output_buf.len();
+                output_buf.extend_from_slice(&[0; BLOCK_LEN_PREFIX]);
+                self.inner.compress(&block, output_buf)?;
+                let block_len = output_buf.len() - offset - BLOCK_LEN_PREFIX;
+                let block_len = u32::try_from(block_len).map_err(|_| {
+                    general_err!("Block split compress: block too large: {} bytes", block_len)
+                })?;
+                output_buf[offset..offset + BLOCK_LEN_PREFIX]
+                    .copy_from_slice(&block_len.to_be_bytes());
+            }
+            Ok(())
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            let view = std::mem::take(&mut self.view);
+            self.view = decompress_into_view(self, view, input_buf, uncompress_size)?;
+            Ok(&self.view)
+        }
+
+        // The length prefixes locate a block without decompressing the ones before it.
+        fn supports_seek(&self) -> bool {
+            true
+        }
+
+        fn decompress_block(
+            &mut self,
+            input_buf: &[u8],
+            block_index: usize,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let size = match uncompress_size {
+                Some(size) if self.is_split(uncompress_size) => size,
+                // a column of one block is stored as the inner codec wrote it
+                _ if block_index == 0 => {
+                    return self.inner.decompress(
+                        input_buf,
+                        output_buf_columndata,
+                        uncompress_size,
+                    )
+                }
+                _ => {
+                    return Err(general_err!(
+                        "Block split decompress: block {} of a single-block column",
+                        block_index
+                    ))
+                }
+            };
+            let block_size = self.block_size(output_buf_columndata);
+            let start = block_index.saturating_mul(block_size);
+            if start >= size {
+                return Err(general_err!(
+                    "Block split decompress: block {} out of range for {} bytes",
+                    block_index,
+                    size
+                ));
+            }
+            let mut input = input_buf;
+            for _ in 0..block_index {
+                input = split_block(input)?.1;
+            }
+            let (block, _) = split_block(input)?;
+            let size = block_size.min(size - start);
+            self.inner
+                .decompress(block, output_buf_columndata, Some(size))
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("BlockSplit", self.block_len, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.block_len))
+        }
+    }
+}
+pub use block_split_codec::*;
+
+mod endian_codec {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that writes integer or float values little-endian instead of
+    /// big-endian, passing their bytes to another codec, and reads them back the same
+    /// way after decompressing.
+    ///
+    /// On little-endian hosts with the `bytemuck` feature, the values are converted
+    /// to and from bytes in bulk. Created by `create_codec` for byte codecs when
+    /// `CodecOptions` sets a little-endian byte order.
+    pub struct EndianCodec {
+        inner: Box<dyn Codec>,
+        swap_ints: bool,
+        swap_floats: bool,
+        last_element_count: usize,
+    }
+
+    impl EndianCodec {
+        /// Creates a new codec writing the integer values, the float values, or both,
+        /// of the input of `inner` little-endian.
+        pub fn new(inner: Box<dyn Codec>, swap_ints: bool, swap_floats: bool) -> Self {
+            Self {
+                inner,
+                swap_ints,
+                swap_floats,
+                last_element_count: 0,
+            }
+        }
+
+        /// Returns whether the values of `column` are written little-endian.
+        fn is_little(&self, column: &ColumnData) -> bool {
+            match column {
+                ColumnData::VecF32(_) | ColumnData::VecF64(_) => self.swap_floats,
+                _ => self.swap_ints,
+            }
+        }
+
+        /// Returns the bytes of `column` in its configured byte order, as the byte
+        /// column the inner codec compresses.
+        fn encode(&mut self, column: &ColumnData) -> Result<ColumnData> {
+            let mut bytes = Vec::with_capacity(column.len() * column.type_width());
+            column.convert_to_u8_ordered(&mut bytes, self.is_little(column))?;
+            self.last_element_count = column.len();
+            Ok(ColumnData::VecU8(bytes))
+        }
+
+        /// Decodes the decompressed `bytes` in the configured byte order of `output`
+        /// and appends the values to it, dropping the partial value a truncated stream
+        /// may end with.
+        fn decode(&self, bytes: &[u8], output: &mut ColumnData) -> Result<()> {
+            let bytes = match self.inner.last_was_truncated() {
+                true => &bytes[..bytes.len() / output.type_width() * output.type_width()],
+                false => bytes,
+            };
+            output.convert_from_u8_ordered(bytes, self.is_little(output))
+        }
+    }
+
+    /// Returns the bytes of `column`, a byte column decompressed by an inner codec.
+    fn byte_values(column: &ColumnData) -> &[u8] {
+        match column {
+            ColumnData::VecU8(x) => x,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Reverses the byte order of the integer values, the float values, or both, of
+    /// `column`.
+    pub(crate) fn swap_column(column: &mut ColumnData, swap_ints: bool, swap_floats: bool) {
+        match column {
+            ColumnData::VecU16(x) if swap_ints => swap_all(x, u16::swap_bytes),
+            ColumnData::VecU32(x) if swap_ints => swap_all(x, u32::swap_bytes),
+            ColumnData::VecU64(x) if swap_ints => swap_all(x, u64::swap_bytes),
+            ColumnData::VecI16(x) if swap_ints => swap_all(x, i16::swap_bytes),
+            ColumnData::VecI32(x) if swap_ints => swap_all(x, i32::swap_bytes),
+            ColumnData::VecI64(x) if swap_ints => swap_all(x, i64::swap_bytes),
+            // the three low bytes, sign extended again for i24
+            ColumnData::VecI24(x) if swap_ints => swap_all(x, |v| v.swap_bytes() >> 8),
+            ColumnData::VecU24(x) if swap_ints => swap_all(x, |v| v.swap_bytes() >> 8),
+            ColumnData::VecF32(x) if swap_floats => {
+                swap_all(x, |v| f32::from_bits(v.to_bits().swap_bytes()))
+            }
+            ColumnData::VecF64(x) if swap_floats => {
+                swap_all(x, |v| f64::from_bits(v.to_bits().swap_bytes()))
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces every value in `values` with `swap` of it.
+    fn swap_all<T: Copy>(values: &mut [T], swap: impl Fn(T) -> T) {
+        values.iter_mut().for_each(|v| *v = swap(*v));
+    }
+
+    impl Codec for EndianCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let mut bytes = ColumnData::VecU8(Vec::new());
+            let n = self
+                .inner
+                .decompress(input_buf, &mut bytes, uncompress_size)?;
+            self.decode(byte_values(&bytes), output_buf_columndata)?;
+            Ok(n)
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let mut bytes = ColumnData::VecU8(Vec::new());
+            let n = self
+                .inner
+                .decompress_file(path, &mut bytes, uncompress_size)?;
+            self.decode(byte_values(&bytes), output_buf_columndata)?;
+            Ok(n)
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            let mut values = ColumnData::VecU8(Vec::new());
+            let bytes = self.inner.decompress_with_bytes(
+                input_buf,
+                &mut values,
+                uncompress_size,
+            )?;
+            self.decode(&bytes, output_buf_columndata)?;
+            Ok(bytes)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let bytes = self.encode(input_buf_columndata)?;
+            self.inner.compress(&bytes, output_buf)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            let bytes = self.encode(input_buf_columndata)?;
+            self.inner.compress_within(&bytes, output_buf, budget)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+        );
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset()
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&(
+                "Endian",
+                self.swap_ints,
+                self.swap_floats,
+                self.inner.config_fingerprint(),
+            ))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(
+                self.inner.clone_codec(),
+                self.swap_ints,
+                self.swap_floats,
+            ))
+        }
+    }
+}
+pub use endian_codec::*;
+
+mod float_split_codec {
+    use std::path::Path;
+
+    use crate::compression::{decompress_typed_with_bytes, fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the split header: layout byte, then value count as big-endian u64.
+    const SPLIT_HEADER_LEN: usize = 9;
+
+    /// Layout byte of split `f32` values.
+    const F32_LAYOUT: u8 = 4;
+
+    /// Layout byte of split `f64` values.
+    const F64_LAYOUT: u8 = 8;
+
+    /// Bit layout of an IEEE-754 float type and how its components are stored.
+    struct FloatLayout {
+        /// Layout byte recorded in the header.
+        tag: u8,
+        /// Number of mantissa bits.
+        mantissa_bits: u32,
+        /// Number of exponent bits.
+        exponent_bits: u32,
+        /// Bytes each exponent takes in the exponent stream.
+        exponent_bytes: usize,
+        /// Bytes each mantissa takes in the mantissa stream.
+        mantissa_bytes: usize,
+    }
+
+    const F32: FloatLayout = FloatLayout {
+        tag: F32_LAYOUT,
+        mantissa_bits: 23,
+        exponent_bits: 8,
+        exponent_bytes: 1,
+        mantissa_bytes: 3,
+    };
+
+    const F64: FloatLayout = FloatLayout {
+        tag: F64_LAYOUT,
+        mantissa_bits: 52,
+        exponent_bits: 11,
+        exponent_bytes: 2,
+        mantissa_bytes: 7,
+    };
+
+    /// Codec wrapper that splits float columns into a packed sign bit stream, an
+    /// exponent stream and a mantissa stream before compressing them with another
+    /// codec as bytes, and recombines them after decompressing.
+    ///
+    /// Other columns pass through unchanged. Created by `create_codec` for byte codecs
+    /// when `CodecOptions` enables float component splitting.
+    pub struct FloatSplitCodec {
+        inner: Box<dyn Codec>,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+    }
+
+    impl FloatSplitCodec {
+        /// Creates a new codec splitting the float input of `inner`.
+        pub fn new(inner: Box<dyn Codec>) -> Self {
+            Self {
+                inner,
+                last_element_count: 0,
+            }
+        }
+    }
+
+    impl FloatLayout {
+        /// Returns the number of bytes the header and streams of `count` values take.
+        fn split_len(&self, count: usize) -> usize {
+            SPLIT_HEADER_LEN
+                + (count + 7) / 8
+                + count * (self.exponent_bytes + self.mantissa_bytes)
+        }
+    }
+
+    /// Writes the header and the three component streams of `bits`, the raw bits of
+    /// floats laid out as `layout`.
+    fn split(
+        bits: impl ExactSizeIterator<Item = u64> + Clone,
+        layout: &FloatLayout,
+    ) -> Vec<u8> {
+        let count = bits.len();
+        let mut output = Vec::with_capacity(layout.split_len(count));
+        output.push(layout.tag);
+        output.extend_from_slice(&(count as u64).to_be_bytes());
+
+        let sign_shift = layout.mantissa_bits + layout.exponent_bits;
+        let mut signs = vec![0u8; (count + 7) / 8];
+        for (i, b) in bits.clone().enumerate() {
+            signs[i / 8] |= ((b >> sign_shift) as u8 & 1) << (i % 8);
+        }
+        output.extend_from_slice(&signs);
+        let exponent_mask = (1 << layout.exponent_bits) - 1;
+        for b in bits.clone() {
+            let exponent = (b >> layout.mantissa_bits & exponent_mask).to_be_bytes();
+            output.extend_from_slice(&exponent[8 - layout.exponent_bytes..]);
+        }
+        for b in bits {
+            let mantissa = (b & ((1 << layout.mantissa_bits) - 1)).to_be_bytes();
+            output.extend_from_slice(&mantissa[8 - layout.mantissa_bytes..]);
+        }
+        output
+    }
+
+    /// Reads the three component streams written by `split` back into the raw bits of
+    /// floats laid out as `layout`.
+    fn join(input: &[u8], layout: &FloatLayout) -> Result<Vec<u64>> {
+        if input.len() < SPLIT_HEADER_LEN {
+            return Err(eof_err!(
+                "float split header needs {} bytes, got {}",
+                SPLIT_HEADER_LEN,
+                input.len()
+            ));
+        }
+        if input[0] != layout.tag {
+            return Err(general_err!(
+                "Float split layout {} does not match {}-byte floats",
+                input[0],
+                layout.tag
+            ));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&input[1..SPLIT_HEADER_LEN]);
+        let count = u64::from_be_bytes(count) as usize;
+        let streams = &input[SPLIT_HEADER_LEN..];
+        if count > input.len() || input.len() != layout.split_len(count) {
+            return Err(general_err!(
+                "Float split streams of {} values have {} bytes",
+                count,
+                streams.len()
+            ));
+        }
+        let (signs, rest) = streams.split_at((count + 7) / 8);
+        let (exponents, mantissas) = rest.split_at(count * layout.exponent_bytes);
+
+        let be_uint = |bytes: &[u8]| bytes.iter().fold(0u64, |v, &b| v << 8 | b as u64);
+        let sign_shift = layout.mantissa_bits + layout.exponent_bits;
+        Ok(exponents
+            .chunks(layout.exponent_bytes)
+            .zip(mantissas.chunks(layout.mantissa_bytes))
+            .enumerate()
+            .map(|(i, (exponent, mantissa))| {
+                let sign = (signs[i / 8] >> (i % 8) & 1) as u64;
+                let exponent = be_uint(exponent) << layout.mantissa_bits;
+                sign << sign_shift | exponent | be_uint(mantissa)
+            })
+            .collect())
+    }
+
+    impl FloatSplitCodec {
+        /// Decompresses the split streams in `input_buf` and appends the recombined
+        /// floats to `output`.
+        ///
+        /// `uncompress_size` is the size of the floats, which is converted to the size
+        /// of their streams for the inner codec.
+        fn decompress_split(
+            &mut self,
+            input_buf: &[u8],
+            output: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let layout = match output {
+                ColumnData::VecF32(_) => &F32,
+                _ => &F64,
+            };
+            let streams_size =
+                uncompress_size.map(|size| layout.split_len(size / output.type_width()));
+            let mut streams = ColumnData::VecU8(Vec::new());
+            let n = self.inner.decompress(input_buf, &mut streams, streams_size)?;
+            let bits = match streams {
+                ColumnData::VecU8(x) => join(&x, layout)?,
+                _ => unreachable!(),
+            };
+            match output {
+                ColumnData::VecF32(x) => {
+                    x.extend(bits.into_iter().map(|b| f32::from_bits(b as u32)))
+                }
+                ColumnData::VecF64(x) => x.extend(bits.into_iter().map(f64::from_bits)),
+                _ => unreachable!(),
+            }
+            Ok(n)
+        }
+    }
+
+    impl Codec for FloatSplitCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            match output_buf_columndata {
+                ColumnData::VecF32(_) | ColumnData::VecF64(_) => {
+                    self.decompress_split(input_buf, output_buf_columndata, uncompress_size)
+                }
+                _ => self
+                    .inner
+                    .decompress(input_buf, output_buf_columndata, uncompress_size),
+            }
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            match output_buf_columndata {
+                ColumnData::VecF32(_) | ColumnData::VecF64(_) => {
+                    let input_buf = std::fs::read(path)?;
+                    self.decompress_split(&input_buf, output_buf_columndata, uncompress_size)
+                }
+                _ => self
+                    .inner
+                    .decompress_file(path, output_buf_columndata, uncompress_size),
+            }
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let streams = match input_buf_columndata {
+                ColumnData::VecF32(x) => split(x.iter().map(|v| v.to_bits() as u64), &F32),
+                ColumnData::VecF64(x) => split(x.iter().map(|v| v.to_bits()), &F64),
+                _ => return self.inner.compress(input_buf_columndata, output_buf),
+            };
+            self.inner.compress(&ColumnData::VecU8(streams), output_buf)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("FloatSplit", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec()))
+        }
+    }
+}
+pub use float_split_codec::*;
+
+mod bit_pack_codec {
+    use std::path::Path;
+
+    use crate::compression::{decompress_typed_with_bytes, fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the packing header: bit width, then value count as big-endian u64.
+    const PACK_HEADER_LEN: usize = 9;
+
+    /// Codec wrapper that packs integer columns into a fixed number of bits per value
+    /// before compressing them with another codec as bytes, and unpacks them after
+    /// decompressing.
+    ///
+    /// Values are packed least significant bit first after a header recording the bit
+    /// width and the value count; signed values are packed in two's complement. Float
+    /// columns pass through unchanged. Created by `create_codec` for byte codecs when
+    /// `CodecOptions` sets a bit packing width.
+    pub struct BitPackCodec {
+        inner: Box<dyn Codec>,
+        width: u8,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+    }
+
+    impl BitPackCodec {
+        /// Creates a new codec packing the integer input of `inner` into `width` bits
+        /// per value, from 1 to 64.
+        pub fn new(inner: Box<dyn Codec>, width: u8) -> Self {
+            Self {
+                inner,
+                width,
+                last_element_count: 0,
+            }
+        }
+    }
+
+    /// Returns the number of bytes the header and `count` values of `width` bits take.
+    fn packed_len(count: usize, width: u8) -> usize {
+        PACK_HEADER_LEN + (count * width as usize + 7) / 8
+    }
+
+    /// Writes the header and the low `width` bits of each of `values`.
+    fn pack(values: impl ExactSizeIterator<Item = u64>, width: u8) -> Vec<u8> {
+        let count = values.len();
+        let mut output = Vec::with_capacity(packed_len(count, width));
+        output.push(width);
+        output.extend_from_slice(&(count as u64).to_be_bytes());
+
+        let mut acc = 0u128;
+        let mut acc_bits = 0;
+        for v in values {
+            acc |= (v as u128) << acc_bits;
+            acc_bits += width as u32;
+            while acc_bits >= 8 {
+                output.push(acc as u8);
+                acc >>= 8;
+                acc_bits -= 8;
+            }
+        }
+        if acc_bits > 0 {
+            output.push(acc as u8);
+        }
+        output
+    }
+
+    /// Reads the values written by `pack` for a column of `max_width`-bit integers,
+    /// returning their bit width and their low bits.
+    fn unpack(input: &[u8], max_width: u8) -> Result<(u8, Vec<u64>)> {
+        if input.len() < PACK_HEADER_LEN {
+            return Err(eof_err!(
+                "bit packing header needs {} bytes, got {}",
+                PACK_HEADER_LEN,
+                input.len()
+            ));
+        }
+        let width = input[0];
+        if width == 0 || width > max_width {
+            return Err(general_err!(
+                "Bit packing width {} does not fit {}-bit integers",
+                width,
+                max_width
+            ));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&input[1..PACK_HEADER_LEN]);
+        let count = u64::from_be_bytes(count) as usize;
+        if count > input.len() * 8 || input.len() != packed_len(count, width) {
+            return Err(general_err!(
+                "Bit packed {} values of {} bits have {} bytes",
+                count,
+                width,
+                input.len() - PACK_HEADER_LEN
+            ));
+        }
+
+        let mask = u64::MAX >> (64 - width as u32);
+        let mut values = Vec::with_capacity(count);
+        let mut bytes = input[PACK_HEADER_LEN..].iter();
+        let mut acc = 0u128;
+        let mut acc_bits = 0;
+        for _ in 0..count {
+            while acc_bits < width as u32 {
+                acc |= (*bytes.next().unwrap() as u128) << acc_bits;
+                acc_bits += 8;
+            }
+            values.push(acc as u64 & mask);
+            acc >>= width;
+            acc_bits -= width as u32;
+        }
+        Ok((width, values))
+    }
+
+    /// Returns the low `width` bits of `values`, or an error naming the first value
+    /// that does not fit in them.
+    fn unsigned_bits(
+        values: impl ExactSizeIterator<Item = u64> + Clone,
+        width: u8,
+    ) -> Result<impl ExactSizeIterator<Item = u64>> {
+        let max = u64::MAX >> (64 - width as u32);
+        match values.clone().find(|&v| v > max) {
+            Some(v) => Err(general_err!(
+                "BitPack compress: value {} does not fit in {} bits",
+                v,
+                width
+            )),
+            None => Ok(values),
+        }
+    }
+
+    /// Returns the two's complement low `width` bits of `values`, or an error naming
+    /// the first value that does not fit in them.
+    fn signed_bits(
+        values: impl ExactSizeIterator<Item = i64> + Clone,
+        width: u8,
+    ) -> Result<impl ExactSizeIterator<Item = u64>> {
+        let max = i64::MAX >> (64 - width as u32);
+        match values.clone().find(|&v| v > max || v < -max - 1) {
+            Some(v) => Err(general_err!(
+                "BitPack compress: value {} does not fit in {} bits",
+                v,
+                width
+            )),
+            None => {
+                let mask = u64::MAX >> (64 - width as u32);
+                Ok(values.map(move |v| v as u64 & mask))
+            }
+        }
+    }
+
+    /// Sign-extends the `width`-bit two's complement value `bits`.
+    fn sign_extend(bits: u64, width: u8) -> i64 {
+        let shift = 64 - width as u32;
+        ((bits << shift) as i64) >> shift
+    }
+
+    impl BitPackCodec {
+        /// Returns the width values of `column` are packed in: the configured width,
+        /// capped at the bits of its integer type.
+        fn column_width(&self, column: &ColumnData) -> u8 {
+            self.width.min(column.type_width() as u8 * 8)
+        }
+
+        /// Decompresses the packed values in `input_buf` and appends them to the
+        /// integer column `output`.
+        ///
+        /// `uncompress_size` is the size of the integers, which is converted to the
+        /// size of their packed form for the inner codec.
+        fn decompress_packed(
+            &mut self,
+            input_buf: &[u8],
+            output: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let width = self.column_width(output);
+            let packed_size = uncompress_size
+                .map(|size| packed_len(size / output.type_width(), width));
+            let mut packed = ColumnData::VecU8(Vec::new());
+            let n = self.inner.decompress(input_buf, &mut packed, packed_size)?;
+            let (width, bits) = match packed {
+                ColumnData::VecU8(x) => unpack(&x, output.type_width() as u8 * 8)?,
+                _ => unreachable!(),
+            };
+            let bits = bits.into_iter();
+            match output {
+                ColumnData::VecU16(x) => x.extend(bits.map(|b| b as u16)),
+                ColumnData::VecU32(x) | ColumnData::VecU24(x) => {
+                    x.extend(bits.map(|b| b as u32))
+                }
+                ColumnData::VecU64(x) => x.extend(bits),
+                ColumnData::VecI8(x) => {
+                    x.extend(bits.map(|b| sign_extend(b, width) as i8))
+                }
+                ColumnData::VecI16(x) => {
+                    x.extend(bits.map(|b| sign_extend(b, width) as i16))
+                }
+                ColumnData::VecI32(x) | ColumnData::VecI24(x) => {
+                    x.extend(bits.map(|b| sign_extend(b, width) as i32))
+                }
+                ColumnData::VecI64(x) => x.extend(bits.map(|b| sign_extend(b, width))),
+                ColumnData::VecU8(x) => x.extend(bits.map(|b| b as u8)),
+                ColumnData::VecF32(_) | ColumnData::VecF64(_) => unreachable!(),
+            }
+            Ok(n)
+        }
+    }
+
+    /// Returns whether `column` holds integers, which are bit packed.
+    fn is_integer(column: &ColumnData) -> bool {
+        !matches!(column, ColumnData::VecF32(_) | ColumnData::VecF64(_))
+    }
+
+    impl Codec for BitPackCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            if is_integer(output_buf_columndata) {
+                self.decompress_packed(input_buf, output_buf_columndata, uncompress_size)
+            } else {
+                self.inner
+                    .decompress(input_buf, output_buf_columndata, uncompress_size)
+            }
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            if is_integer(output_buf_columndata) {
+                let input_buf = std::fs::read(path)?;
+                self.decompress_packed(&input_buf, output_buf_columndata, uncompress_size)
+            } else {
+                self.inner
+                    .decompress_file(path, output_buf_columndata, uncompress_size)
+            }
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let width = self.column_width(input_buf_columndata);
+            let packed = match input_buf_columndata {
+                ColumnData::VecU8(x) => {
+                    pack(unsigned_bits(x.iter().map(|&v| v as u64), width)?, width)
+                }
+                ColumnData::VecU16(x) => {
+                    pack(unsigned_bits(x.iter().map(|&v| v as u64), width)?, width)
+                }
+                ColumnData::VecU32(x) | ColumnData::VecU24(x) => {
+                    pack(unsigned_bits(x.iter().map(|&v| v as u64), width)?, width)
+                }
+                ColumnData::VecU64(x) => {
+                    pack(unsigned_bits(x.iter().copied(), width)?, width)
+                }
+                ColumnData::VecI8(x) => {
+                    pack(signed_bits(x.iter().map(|&v| v as i64), width)?, width)
+                }
+                ColumnData::VecI16(x) => {
+                    pack(signed_bits(x.iter().map(|&v| v as i64), width)?, width)
+                }
+                ColumnData::VecI32(x) | ColumnData::VecI24(x) => {
+                    pack(signed_bits(x.iter().map(|&v| v as i64), width)?, width)
+                }
+                ColumnData::VecI64(x) => {
+                    pack(signed_bits(x.iter().copied(), width)?, width)
+                }
+                ColumnData::VecF32(_) | ColumnData::VecF64(_) => {
+                    return self.inner.compress(input_buf_columndata, output_buf)
+                }
+            };
+            self.inner.compress(&ColumnData::VecU8(packed), output_buf)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            estimate_compress_duration,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            // every packed value unpacks to up to 64 bits
+            let unpack_ratio = 64.0 / self.width as f64;
+            self.inner.max_expansion_ratio().map(|ratio| ratio * unpack_ratio)
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("BitPack", self.width, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.width))
+        }
+    }
+}
+pub use bit_pack_codec::*;
+
+mod stored_codec {
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Marks a buffer holding the values uncompressed.
+    const STORED: u8 = 0;
+    /// Marks a buffer compressed by the inner codec.
+    const COMPRESSED: u8 = 1;
+
+    /// Codec wrapper that stores a column uncompressed when the inner codec does not
+    /// reach a minimum compression ratio.
+    ///
+    /// Every buffer starts with one byte telling whether the rest is compressed or
+    /// stored. Created by `create_codec` when a minimum compression ratio is set in
+    /// `CodecOptions`.
+    pub struct StoredFallbackCodec {
+        inner: Box<dyn Codec>,
+        min_ratio: f64,
+        last_was_stored: bool,
+    }
+
+    impl StoredFallbackCodec {
+        /// Creates a new codec storing the input of `inner` raw whenever it compresses
+        /// by less than `min_ratio`.
+        pub fn new(inner: Box<dyn Codec>, min_ratio: f64) -> Self {
+            Self {
+                inner,
+                min_ratio,
+                last_was_stored: false,
+            }
+        }
+    }
+
+    impl Codec for StoredFallbackCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            match input_buf.first() {
+                Some(&STORED) => {
+                    output_buf_columndata.convert_from_u8(&input_buf[1..])?;
+                    Ok(input_buf.len() - 1)
+                }
+                Some(&COMPRESSED) => self.inner.decompress(
+                    &input_buf[1..],
+                    output_buf_columndata,
+                    uncompress_size,
+                ),
+                Some(flag) => Err(general_err!("Unknown stored/compressed flag {}", flag)),
+                None => Err(eof_err!("Missing stored/compressed flag")),
+            }
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            match input_buf.first() {
+                Some(&STORED) => {
+                    let bytes = input_buf[1..].to_vec();
+                    output_buf_columndata.convert_from_u8(&bytes)?;
+                    Ok(bytes)
+                }
+                Some(&COMPRESSED) => self.inner.decompress_with_bytes(
+                    &input_buf[1..],
+                    output_buf_columndata,
+                    uncompress_size,
+                ),
+                Some(flag) => Err(general_err!("Unknown stored/compressed flag {}", flag)),
+                None => Err(eof_err!("Missing stored/compressed flag")),
+            }
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let offset = output_buf.len();
+            output_buf.push(COMPRESSED);
+            self.inner.compress(input_buf_columndata, output_buf)?;
+
+            let raw_len = input_buf_columndata.len() * input_buf_columndata.type_width();
+            let compressed_len = output_buf.len() - offset - 1;
+            self.last_was_stored = (raw_len as f64) < self.min_ratio * compressed_len as f64;
+            if self.last_was_stored {
+                output_buf.truncate(offset);
+                output_buf.push(STORED);
+                input_buf_columndata.convert_to_u8(output_buf)?;
+            }
+            Ok(())
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn framing_overhead(&self) -> usize {
+            1 + self.inner.framing_overhead()
+        }
+
+        fn last_was_stored(&self) -> bool {
+            self.last_was_stored
+        }
+
+        fn reset(&mut self) {
+            self.last_was_stored = false;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&(
+                "StoredFallback",
+                self.min_ratio.to_bits(),
+                self.inner.config_fingerprint(),
+            ))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.min_ratio))
+        }
+    }
+}
+pub use stored_codec::*;
+
+mod context_prefix_codec {
+    use std::time::Duration;
+
+    use crate::compression::{decompress_typed_with_bytes, fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Codec wrapper that compresses a fixed prefix ahead of the value bytes of every
+    /// input, and checks and strips it again on decompression.
+    ///
+    /// Created by `create_codec` when a context prefix is set in `CodecOptions`.
+    pub struct ContextPrefixCodec {
+        inner: Box<dyn Codec>,
+        prefix: Vec<u8>,
+        last_element_count: usize,
+    }
+
+    impl ContextPrefixCodec {
+        /// Creates a new codec compressing `prefix` ahead of every input of `inner`.
+        pub fn new(inner: Box<dyn Codec>, prefix: Vec<u8>) -> Self {
+            Self {
+                inner,
+                prefix,
+                last_element_count: 0,
+            }
+        }
+    }
+
+    impl Codec for ContextPrefixCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let mut bytes = ColumnData::VecU8(Vec::new());
+            let size = uncompress_size.map(|size| size + self.prefix.len());
+            self.inner.decompress(input_buf, &mut bytes, size)?;
+            let bytes = match bytes {
+                ColumnData::VecU8(bytes) => bytes,
+                _ => unreachable!(),
+            };
+            if !bytes.starts_with(&self.prefix) {
+                return Err(general_err!(
+                    "Context prefix decompress: missing the {} byte prefix",
+                    self.prefix.len()
+                ));
+            }
+            let values = bytes[self.prefix.len()..].to_vec();
+            output_buf_columndata.convert_from_u8(&values)?;
+            Ok(values.len())
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(self, input_buf, output_buf_columndata, uncompress_size)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut input_buf = self.prefix.clone();
+            input_buf_columndata.convert_to_u8(&mut input_buf)?;
+            self.inner.compress(&ColumnData::VecU8(input_buf), output_buf)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn max_input_len(&self) -> usize {
+            self.inner.max_input_len().saturating_sub(self.prefix.len())
+        }
+
+        fn estimate_compress_duration(&self, input_len_bytes: usize) -> Duration {
+            self.inner
+                .estimate_compress_duration(input_len_bytes + self.prefix.len())
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("ContextPrefix", &self.prefix, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.prefix.clone()))
+        }
+    }
+}
+pub use context_prefix_codec::*;
+
+mod dedup_codec {
+    use std::collections::HashMap;
+
+    use crate::compression::{decompress_typed_with_bytes, fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Size of the blocks compared for duplicates; shorter repeats are left to the
+    /// inner codec.
+    const DEDUP_BLOCK_LEN: usize = 1024;
+
+    /// Length of the dedup header: literal length as big-endian u64, then reference
+    /// count as big-endian u32.
+    const DEDUP_HEADER_LEN: usize = 12;
+
+    /// Length of a reference: duplicate block index, then the index of the earlier
+    /// block it repeats, both as big-endian u32.
+    const REFERENCE_LEN: usize = 8;
+
+    /// Index of a block repeating an earlier block, and the index of that block.
+    type Reference = (u32, u32);
+
+    /// Codec wrapper that replaces blocks of the value bytes repeating an earlier block
+    /// by a reference to it, before compressing the remaining bytes with another codec.
+    ///
+    /// The bytes are cut into blocks of `DEDUP_BLOCK_LEN` bytes. The reference table is
+    /// written uncompressed ahead of the inner codec's output. This catches repeats
+    /// further apart than the window of the inner codec. Created by `create_codec` for
+    /// byte codecs when `CodecOptions` enables intra-column deduplication.
+    pub struct DedupCodec {
+        inner: Box<dyn Codec>,
+        last_element_count: usize,
+    }
+
+    impl DedupCodec {
+        /// Creates a new codec deduplicating the input of `inner`.
+        pub fn new(inner: Box<dyn Codec>) -> Self {
+            Self {
+                inner,
+                last_element_count: 0,
+            }
+        }
+    }
+
+    /// Splits `bytes` into the blocks that are not a repeat, concatenated, and the
+    /// references `(block, earlier block)` of those that are.
+    fn dedup(bytes: &[u8]) -> (Vec<u8>, Vec<Reference>) {
+        let mut first_seen: HashMap<&[u8], u32> = HashMap::new();
+        let mut literal = Vec::with_capacity(bytes.len());
+        let mut references = Vec::new();
+        for (i, block) in bytes.chunks(DEDUP_BLOCK_LEN).enumerate() {
+            let i = i as u32;
+            if block.len() < DEDUP_BLOCK_LEN {
+                literal.extend_from_slice(block);
+            } else if let Some(&earlier) = first_seen.get(block) {
+                references.push((i, earlier));
+            } else {
+                first_seen.insert(block, i);
+                literal.extend_from_slice(block);
+            }
+        }
+        (literal, references)
+    }
+
+    /// Rebuilds the bytes `dedup` split into `literal` and `references`.
+    fn undedup(literal: &[u8], references: &[Reference]) -> Result<Vec<u8>> {
+        let literal_len = literal.len();
+        let len = literal_len + references.len() * DEDUP_BLOCK_LEN;
+        let mut bytes = Vec::with_capacity(len);
+        let mut literal = literal.chunks(DEDUP_BLOCK_LEN);
+        let mut references = references.iter().peekable();
+        let mut block = 0;
+        while bytes.len() < len {
+            match references.peek() {
+                Some(&&(i, earlier)) if i == block => {
+                    references.next();
+                    let start = earlier as usize * DEDUP_BLOCK_LEN;
+                    if earlier >= block || start + DEDUP_BLOCK_LEN > bytes.len() {
+                        return Err(general_err!(
+                            "Dedup decompress: block {} refers to missing block {}",
+                            block,
+                            earlier
+                        ));
+                    }
+                    bytes.extend_from_within(start..start + DEDUP_BLOCK_LEN);
+                }
+                _ => match literal.next() {
+                    Some(chunk) => bytes.extend_from_slice(chunk),
+                    None => break,
+                },
+            }
+            block += 1;
+        }
+        if bytes.len() != len || references.next().is_some() {
+            return Err(general_err!(
+                "Dedup decompress: references do not match {} literal bytes",
+                literal_len
+            ));
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the header and the reference table, returning the literal length, the
+    /// references and the rest of `input`.
+    fn read_header(input: &[u8]) -> Result<(usize, Vec<Reference>, &[u8])> {
+        if input.len() < DEDUP_HEADER_LEN {
+            return Err(eof_err!(
+                "dedup header needs {} bytes, got {}",
+                DEDUP_HEADER_LEN,
+                input.len()
+            ));
+        }
+        let mut literal_len = [0; 8];
+        literal_len.copy_from_slice(&input[..8]);
+        let mut count = [0; 4];
+        count.copy_from_slice(&input[8..DEDUP_HEADER_LEN]);
+        let count = u32::from_be_bytes(count) as usize;
+        let input = &input[DEDUP_HEADER_LEN..];
+        if input.len() / REFERENCE_LEN < count {
+            return Err(eof_err!(
+                "dedup table of {} references truncated to {} bytes",
+                count,
+                input.len()
+            ));
+        }
+        let (table, payload) = input.split_at(count * REFERENCE_LEN);
+        let references = table
+            .chunks(REFERENCE_LEN)
+            .map(|reference| {
+                let mut block = [0; 4];
+                block.copy_from_slice(&reference[..4]);
+                let mut earlier = [0; 4];
+                earlier.copy_from_slice(&reference[4..]);
+                (u32::from_be_bytes(block), u32::from_be_bytes(earlier))
+            })
+            .collect();
+        Ok((u64::from_be_bytes(literal_len) as usize, references, payload))
+    }
+
+    impl Codec for DedupCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let (literal_len, references, payload) = read_header(input_buf)?;
+            let mut literal = ColumnData::VecU8(Vec::new());
+            self.inner.decompress(payload, &mut literal, Some(literal_len))?;
+            let bytes = match literal {
+                ColumnData::VecU8(x) => undedup(&x, &references)?,
+                _ => unreachable!(),
+            };
+            output_buf_columndata.convert_from_u8(&bytes)?;
+            Ok(bytes.len())
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(self, input_buf, output_buf_columndata, uncompress_size)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            self.last_element_count = input_buf_columndata.len();
+            let mut bytes = Vec::new();
+            input_buf_columndata.convert_to_u8(&mut bytes)?;
+            let (literal, references) = dedup(&bytes);
+
+            let offset = output_buf.len();
+            output_buf.extend_from_slice(&(literal.len() as u64).to_be_bytes());
+            output_buf.extend_from_slice(&(references.len() as u32).to_be_bytes());
+            for (block, earlier) in &references {
+                output_buf.extend_from_slice(&block.to_be_bytes());
+                output_buf.extend_from_slice(&earlier.to_be_bytes());
+            }
+            let result = self.inner.compress(&ColumnData::VecU8(literal), output_buf);
+            if result.is_err() {
+                output_buf.truncate(offset);
+            }
+            result
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            last_was_stored,
+            last_was_truncated,
+            last_skipped_frames,
+            estimate_compress_duration,
+            is_lossless,
+            backend_version,
+            name,
+        );
+
+        fn framing_overhead(&self) -> usize {
+            self.inner.framing_overhead() + DEDUP_HEADER_LEN
+        }
+
+        fn max_input_len(&self) -> usize {
+            // block indexes must fit the u32 reference table
+            let max_blocks = u32::MAX as usize * DEDUP_BLOCK_LEN;
+            self.inner.max_input_len().min(max_blocks)
+        }
+
+        fn max_expansion_ratio(&self) -> Option<f64> {
+            // every reference expands to a whole block
+            let reference_ratio = (DEDUP_BLOCK_LEN / REFERENCE_LEN) as f64;
+            self.inner
+                .max_expansion_ratio()
+                .map(|ratio| ratio.max(reference_ratio))
+        }
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("Dedup", DEDUP_BLOCK_LEN, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec()))
+        }
+    }
+}
+pub use dedup_codec::*;
+
+mod retry_codec {
+    use std::io;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Returns whether `err` may succeed if the call is repeated.
+    ///
+    /// Compression libraries report resource pressure as I/O errors; interrupted and
+    /// timed out calls, would-block conditions and allocation failures are transient.
+    /// Corrupt input, unsupported types and every other error are not.
+    pub fn is_transient(err: &ParquetError) -> bool {
+        match err {
+            ParquetError::External(e) => match e.downcast_ref::<io::Error>() {
+                Some(e) => matches!(
+                    e.kind(),
+                    io::ErrorKind::Interrupted
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::OutOfMemory
+                ),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Calls `attempt` until it succeeds, fails with an error that is not transient,
+    /// or has been retried `retries` times.
+    fn with_retries<T>(retries: u8, mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut left = retries;
+        loop {
+            match attempt() {
+                Err(e) if left > 0 && is_transient(&e) => left -= 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Drops any values a failed attempt appended past the first `len`.
+    fn restore(output_buf_columndata: &mut ColumnData, len: usize) {
+        if output_buf_columndata.len() != len {
+            *output_buf_columndata = output_buf_columndata.slice(0, len);
+        }
+    }
+
+    /// Codec wrapper that retries calls to another codec that fail with a transient
+    /// error, discarding any partial output before each retry.
+    ///
+    /// Created by `create_codec` when `CodecOptions` sets a transient retry count.
+    pub struct RetryCodec {
+        inner: Box<dyn Codec>,
+        retries: u8,
+    }
+
+    impl RetryCodec {
+        /// Creates a new codec retrying the calls to `inner` up to `retries` times.
+        pub fn new(inner: Box<dyn Codec>, retries: u8) -> Self {
+            Self { inner, retries }
+        }
+    }
+
+    impl Codec for RetryCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let len = output_buf_columndata.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                restore(output_buf_columndata, len);
+                inner.decompress(input_buf, output_buf_columndata, uncompress_size)
+            })
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let len = output_buf_columndata.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                restore(output_buf_columndata, len);
+                inner.decompress_file(path, output_buf_columndata, uncompress_size)
+            })
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            let len = output_buf_columndata.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                restore(output_buf_columndata, len);
+                inner.decompress_with_bytes(input_buf, output_buf_columndata, uncompress_size)
+            })
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let offset = output_buf.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                output_buf.truncate(offset);
+                inner.compress(input_buf_columndata, output_buf)
+            })
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
+            let offset = output_buf.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                output_buf.truncate(offset);
+                inner.compress_within(input_buf_columndata, output_buf, budget)
+            })
+        }
+
+        fn decompress_block(
+            &mut self,
+            input_buf: &[u8],
+            block_index: usize,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let len = output_buf_columndata.len();
+            let inner = &mut self.inner;
+            with_retries(self.retries, || {
+                restore(output_buf_columndata, len);
+                inner.decompress_block(
+                    input_buf,
+                    block_index,
+                    output_buf_columndata,
+                    uncompress_size,
+                )
+            })
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            reset,
+        );
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("Retry", self.retries, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.retries))
+        }
+    }
+}
+pub use retry_codec::*;
+
+#[cfg(feature = "allocator")]
+mod scratch_alloc {
+    use std::cell::RefCell;
+    use std::fmt;
+    use std::path::Path;
+    use std::ptr::NonNull;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+
+    use crate::compression::{fingerprint, Codec};
+    use crate::errors::Result;
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    thread_local! {
+        /// Allocator of the `ScratchAllocatorCodec` currently running on this thread.
+        static CURRENT: RefCell<Option<ScratchAllocator>> = const { RefCell::new(None) };
+    }
+
+    /// Shared handle to the allocator codec scratch buffers are taken from.
+    ///
+    /// Set with `CodecOptionsBuilder::set_allocator`. Buffers keep a handle to the
+    /// allocator they came from, so they are always freed by it.
+    #[derive(Clone)]
+    pub struct ScratchAllocator {
+        /// The custom allocator, or `None` for the global one.
+        inner: Option<Arc<dyn Allocator + Send + Sync>>,
+    }
+
+    impl ScratchAllocator {
+        /// Creates a new handle to `allocator`.
+        pub fn new<A: Allocator + Send + Sync + 'static>(allocator: A) -> Self {
+            Self {
+                inner: Some(Arc::new(allocator)),
+            }
+        }
+
+        /// Returns the allocator of the codec currently running on this thread, or the
+        /// global allocator.
+        pub(crate) fn current() -> Self {
+            CURRENT
+                .with(|current| current.borrow().clone())
+                .unwrap_or(Self { inner: None })
+        }
+    }
+
+    impl fmt::Debug for ScratchAllocator {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.inner {
+                Some(_) => write!(f, "ScratchAllocator(custom)"),
+                None => write!(f, "ScratchAllocator(global)"),
+            }
+        }
+    }
+
+    impl PartialEq for ScratchAllocator {
+        /// Handles are equal if they share the same allocator.
+        fn eq(&self, other: &Self) -> bool {
+            match (&self.inner, &other.inner) {
+                (Some(a), Some(b)) => {
+                    Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+                }
+                (None, None) => true,
+                _ => false,
+            }
+        }
+    }
+
+    unsafe impl Allocator for ScratchAllocator {
+        fn allocate(&self, layout: Layout) -> std::result::Result<NonNull<[u8]>, AllocError> {
+            match &self.inner {
+                Some(allocator) => allocator.allocate(layout),
+                None => Global.allocate(layout),
+            }
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            match &self.inner {
+                Some(allocator) => allocator.deallocate(ptr, layout),
+                None => Global.deallocate(ptr, layout),
+            }
+        }
+    }
+
+    /// Restores the previous scratch allocator of this thread when dropped, also on
+    /// panic.
+    struct CurrentGuard(Option<ScratchAllocator>);
+
+    impl CurrentGuard {
+        fn set(allocator: &ScratchAllocator) -> Self {
+            Self(CURRENT.with(|current| current.replace(Some(allocator.clone()))))
+        }
+    }
+
+    impl Drop for CurrentGuard {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CURRENT.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    /// Codec wrapper that makes `inner` take its scratch buffers from a custom
+    /// allocator. Created by `create_codec` when an allocator is set in `CodecOptions`.
+    ///
+    /// Buffers the compression libraries allocate internally, and the output buffers,
+    /// still use the global allocator.
+    pub struct ScratchAllocatorCodec {
+        inner: Box<dyn Codec>,
+        allocator: ScratchAllocator,
+    }
+
+    impl ScratchAllocatorCodec {
+        /// Creates a new codec running `inner` with scratch buffers from `allocator`.
+        pub fn new(inner: Box<dyn Codec>, allocator: ScratchAllocator) -> Self {
+            Self { inner, allocator }
+        }
+    }
+
+    impl Codec for ScratchAllocatorCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let _guard = CurrentGuard::set(&self.allocator);
+            self.inner
+                .decompress(input_buf, output_buf_columndata, uncompress_size)
+        }
+
+        fn decompress_file(
+            &mut self,
+            path: &Path,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            let _guard = CurrentGuard::set(&self.allocator);
+            self.inner
+                .decompress_file(path, output_buf_columndata, uncompress_size)
+        }
+
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            let _guard = CurrentGuard::set(&self.allocator);
+            self.inner
+                .decompress_with_bytes(input_buf, output_buf_columndata, uncompress_size)
+        }
+
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            let _guard = CurrentGuard::set(&self.allocator);
+            self.inner.compress(input_buf_columndata, output_buf)
+        }
+
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
         ) -> Result<usize> {
+            let _guard = CurrentGuard::set(&self.allocator);
+            self.inner
+                .compress_within(input_buf_columndata, output_buf, budget)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            framing_overhead,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            concat_frames,
+            decompress_view,
+            supports_seek,
+            decompress_block,
+            reset,
+        );
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("ScratchAllocator", self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.allocator.clone()))
+        }
+    }
+}
+#[cfg(feature = "allocator")]
+pub use scratch_alloc::*;
+
+mod frame_io {
+    use std::io::{self, Read, Write};
+
+    #[cfg(feature = "async")]
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    #[cfg(feature = "async")]
+    use crate::compression::STREAM_CHUNK_SIZE;
+    use crate::compression::{checked_size_hint, Codec};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the record header: compressed size and uncompressed size, both as
+    /// big-endian u32.
+    const RECORD_HEADER_LEN: usize = 8;
+
+    /// Compressed size marking the checksum trailer, whose uncompressed size field holds
+    /// the CRC32 instead.
+    const TRAILER_MARKER: u32 = u32::MAX;
+
+    /// Compares the CRC32 `actual` of the data read with the `expected` one from the
+    /// checksum trailer.
+    #[cfg(feature = "crc")]
+    fn check_crc(expected: u32, actual: u32) -> Result<()> {
+        match expected == actual {
+            true => Ok(()),
+            false => Err(general_err!(
+                "Checksum mismatch: trailer has {:#010x}, data has {:#010x}",
+                expected,
+                actual
+            )),
+        }
+    }
+
+    /// Appends self-framed compressed records to a writer.
+    ///
+    /// Every `write_batch` call produces one record holding an 8-byte header, the
+    /// compressed size followed by the uncompressed size in bytes, and the compressed
+    /// batch. Records can be read back in order with [`FrameReader`].
+    pub struct FrameWriter<W: Write> {
+        writer: W,
+        codec: Box<dyn Codec>,
+        buf: Vec<u8>,
+        #[cfg(feature = "crc")]
+        crc: Option<crc32fast::Hasher>,
+    }
+
+    impl<W: Write> FrameWriter<W> {
+        /// Creates a new frame writer compressing every batch with `codec`.
+        pub fn new(writer: W, codec: Box<dyn Codec>) -> Self {
+            Self {
+                writer,
+                codec,
+                buf: Vec::new(),
+                #[cfg(feature = "crc")]
+                crc: None,
+            }
+        }
 
-            let mut output_buf = Vec::new();
+        /// Computes a CRC32 of the uncompressed bytes of all batches as they are
+        /// written, and appends it as a trailer record on `finish`.
+        ///
+        /// The CRC is updated batch by batch, so the stream is never buffered whole.
+        #[cfg(feature = "crc")]
+        pub fn with_checksum(mut self) -> Self {
+            self.crc = Some(crc32fast::Hasher::new());
+            self
+        }
 
-            let mut decoder = lz4::Decoder::new(input_buf)?;
-            let mut buffer: [u8; LZ4_BUFFER_SIZE] = [0; LZ4_BUFFER_SIZE];
-            let mut total_len = 0;
-            loop {
-                let len = decoder.read(&mut buffer)?;
-                if len == 0 {
-                    break;
+        /// Compresses `input` and appends it to the writer as a single record.
+        pub fn write_batch(&mut self, input: &ColumnData) -> Result<()> {
+            self.buf.clear();
+            self.codec.compress(input, &mut self.buf)?;
+
+            let compressed_size = u32::try_from(self.buf.len())
+                .ok()
+                .filter(|size| *size != TRAILER_MARKER)
+                .ok_or_else(|| general_err!("Record too large: {} bytes", self.buf.len()))?;
+            let uncompressed_size = input.len() * input.type_width();
+            let uncompressed_size = u32::try_from(uncompressed_size).map_err(|_| {
+                general_err!("Record too large: {} bytes", uncompressed_size)
+            })?;
+            self.writer.write_all(&compressed_size.to_be_bytes())?;
+            self.writer.write_all(&uncompressed_size.to_be_bytes())?;
+            self.writer.write_all(&self.buf)?;
+
+            #[cfg(feature = "crc")]
+            if let Some(crc) = &mut self.crc {
+                self.buf.clear();
+                input.convert_to_u8(&mut self.buf)?;
+                crc.update(&self.buf);
+            }
+            Ok(())
+        }
+
+        /// Writes the checksum trailer if enabled, flushes the underlying writer and
+        /// returns it.
+        pub fn finish(mut self) -> Result<W> {
+            #[cfg(feature = "crc")]
+            if let Some(crc) = self.crc.take() {
+                self.writer.write_all(&TRAILER_MARKER.to_be_bytes())?;
+                self.writer.write_all(&crc.finalize().to_be_bytes())?;
+            }
+            self.writer.flush()?;
+            Ok(self.writer)
+        }
+    }
+
+    /// Iterates over the records written by a [`FrameWriter`], decompressing each into
+    /// a column of the same element type as `example`.
+    ///
+    /// A checksum trailer ends the iteration; it is only verified if enabled with
+    /// `with_checksum`.
+    pub struct FrameReader<R: Read> {
+        reader: R,
+        codec: Box<dyn Codec>,
+        example: ColumnData,
+        buf: Vec<u8>,
+        finished: bool,
+        #[cfg(feature = "crc")]
+        crc: Option<crc32fast::Hasher>,
+    }
+
+    impl<R: Read> FrameReader<R> {
+        /// Creates a new frame reader decompressing every record with `codec`.
+        pub fn new(reader: R, codec: Box<dyn Codec>, example: &ColumnData) -> Self {
+            let mut example = example.clone();
+            example.clear();
+            Self {
+                reader,
+                codec,
+                example,
+                buf: Vec::new(),
+                finished: false,
+                #[cfg(feature = "crc")]
+                crc: None,
+            }
+        }
+
+        /// Verifies the checksum trailer written by [`FrameWriter::with_checksum`],
+        /// updating the CRC as every record is decompressed.
+        ///
+        /// Iteration fails if the trailer is missing or does not match the data read.
+        #[cfg(feature = "crc")]
+        pub fn with_checksum(mut self) -> Self {
+            self.crc = Some(crc32fast::Hasher::new());
+            self
+        }
+
+        /// Reads the next record header, returning `None` on a clean end of input.
+        fn read_header(&mut self) -> Result<Option<(usize, usize)>> {
+            let mut header = [0; RECORD_HEADER_LEN];
+            let mut read = 0;
+            while read < RECORD_HEADER_LEN {
+                match self.reader.read(&mut header[read..]) {
+                    Ok(0) if read == 0 => return Ok(None),
+                    Ok(0) => return Err(eof_err!("Truncated record header")),
+                    Ok(n) => read += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e.into()),
                 }
-                total_len += len;
-                output_buf.write_all(&buffer[0..len])?;
+            }
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&header[..4]);
+            let compressed_size = u32::from_be_bytes(bytes) as usize;
+            bytes.copy_from_slice(&header[4..]);
+            let uncompressed_size = u32::from_be_bytes(bytes) as usize;
+            Ok(Some((compressed_size, uncompressed_size)))
+        }
+
+        fn read_record(&mut self) -> Result<Option<ColumnData>> {
+            if self.finished {
+                return Ok(None);
+            }
+            let (compressed_size, uncompressed_size) = match self.read_header()? {
+                Some((size, checksum)) if size == TRAILER_MARKER as usize => {
+                    self.finished = true;
+                    self.verify_checksum(Some(checksum as u32))?;
+                    return Ok(None);
+                }
+                Some(sizes) => sizes,
+                None => {
+                    self.finished = true;
+                    self.verify_checksum(None)?;
+                    return Ok(None);
+                }
+            };
+            // the sizes come from the input, so the record is read as it arrives rather
+            // than allocated up front
+            self.buf.clear();
+            (&mut self.reader)
+                .take(compressed_size as u64)
+                .read_to_end(&mut self.buf)?;
+            if self.buf.len() < compressed_size {
+                return Err(eof_err!(
+                    "Truncated record: {} of {} bytes",
+                    self.buf.len(),
+                    compressed_size
+                ));
             }
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+            let mut output = self.example.clone();
+            let size = checked_size_hint(
+                self.codec.as_ref(),
+                uncompressed_size as u64,
+                1,
+                self.buf.len(),
+            )?;
+            self.codec.decompress(&self.buf, &mut output, size)?;
 
-            Ok(total_len)
+            #[cfg(feature = "crc")]
+            if let Some(crc) = &mut self.crc {
+                self.buf.clear();
+                output.convert_to_u8(&mut self.buf)?;
+                crc.update(&self.buf);
+            }
+            Ok(Some(output))
         }
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+        /// Compares the running CRC with the `expected` one from the trailer, if
+        /// checksums are enabled.
+        #[cfg(feature = "crc")]
+        fn verify_checksum(&mut self, expected: Option<u32>) -> Result<()> {
+            let actual = match self.crc.take() {
+                Some(crc) => crc.finalize(),
+                None => return Ok(()),
+            };
+            match expected {
+                Some(expected) => check_crc(expected, actual),
+                None => Err(eof_err!("Missing checksum trailer")),
+            }
+        }
 
-            let mut encoder = lz4::EncoderBuilder::new().build(output_buf)?;
-            let mut from = 0;
-            loop {
-                let to = std::cmp::min(from + LZ4_BUFFER_SIZE, input_buf.len());
-                encoder.write_all(&input_buf[from..to])?;
-                from += LZ4_BUFFER_SIZE;
-                if from >= input_buf.len() {
-                    break;
+        #[cfg(not(feature = "crc"))]
+        fn verify_checksum(&mut self, _expected: Option<u32>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<R: Read> Iterator for FrameReader<R> {
+        type Item = Result<ColumnData>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.read_record().transpose()
+        }
+    }
+
+    /// Runs `f` with `codec` on the blocking thread pool, returning the codec with the
+    /// result so it can be reused.
+    #[cfg(feature = "async")]
+    async fn run_blocking<T: Send + 'static>(
+        mut codec: Box<dyn Codec>,
+        f: impl FnOnce(&mut dyn Codec) -> Result<T> + Send + 'static,
+    ) -> Result<(Box<dyn Codec>, T)> {
+        let (codec, result) = tokio::task::spawn_blocking(move || {
+            let result = f(codec.as_mut());
+            (codec, result)
+        })
+        .await
+        .map_err(|e| general_err!("Codec task failed: {}", e))?;
+        Ok((codec, result?))
+    }
+
+    /// Compresses the bytes of `reader` into `writer` without blocking the async
+    /// runtime, returning the writer once it is flushed.
+    ///
+    /// The input is read in chunks of 64 KiB, each compressed with `codec` on tokio's
+    /// blocking thread pool and written as one record in the format of
+    /// [`FrameWriter`], so memory use does not grow with the input. Read the output
+    /// back with [`decompress_async`] or [`FrameReader`].
+    #[cfg(feature = "async")]
+    pub async fn compress_async<R, W>(
+        mut reader: R,
+        mut writer: W,
+        mut codec: Box<dyn Codec>,
+    ) -> Result<W>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        loop {
+            let mut chunk = Vec::with_capacity(STREAM_CHUNK_SIZE);
+            (&mut reader)
+                .take(STREAM_CHUNK_SIZE as u64)
+                .read_to_end(&mut chunk)
+                .await?;
+            if chunk.is_empty() {
+                break;
+            }
+            let uncompressed_size = chunk.len() as u32;
+            let (returned, compressed) = run_blocking(codec, move |codec| {
+                let mut compressed = Vec::new();
+                codec.compress(&ColumnData::VecU8(chunk), &mut compressed)?;
+                Ok(compressed)
+            })
+            .await?;
+            codec = returned;
+
+            let compressed_size = u32::try_from(compressed.len())
+                .ok()
+                .filter(|size| *size != TRAILER_MARKER)
+                .ok_or_else(|| general_err!("Record too large: {} bytes", compressed.len()))?;
+            writer.write_all(&compressed_size.to_be_bytes()).await?;
+            writer.write_all(&uncompressed_size.to_be_bytes()).await?;
+            writer.write_all(&compressed).await?;
+        }
+        writer.flush().await?;
+        Ok(writer)
+    }
+
+    /// Decompresses the records of `reader`, as written by [`compress_async`] or
+    /// [`FrameWriter`], into `writer` as bytes without blocking the async runtime,
+    /// returning the writer once it is flushed.
+    ///
+    /// Every record is decompressed with `codec` on tokio's blocking thread pool. A
+    /// checksum trailer, as written by `FrameWriter::with_checksum`, ends the input.
+    /// With the `crc` feature, a CRC32 of the bytes is updated as every record is
+    /// decompressed and checked against the trailer. The bytes are written as they are
+    /// decompressed, so a mismatch is only reported once they all have been.
+    #[cfg(feature = "async")]
+    pub async fn decompress_async<R, W>(
+        mut reader: R,
+        mut writer: W,
+        mut codec: Box<dyn Codec>,
+    ) -> Result<W>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        #[cfg(feature = "crc")]
+        let mut crc = crc32fast::Hasher::new();
+        loop {
+            let mut header = Vec::with_capacity(RECORD_HEADER_LEN);
+            (&mut reader)
+                .take(RECORD_HEADER_LEN as u64)
+                .read_to_end(&mut header)
+                .await?;
+            match header.len() {
+                0 => break,
+                RECORD_HEADER_LEN => {}
+                _ => return Err(eof_err!("Truncated record header")),
+            }
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&header[..4]);
+            let compressed_size = u32::from_be_bytes(bytes);
+            bytes.copy_from_slice(&header[4..]);
+            if compressed_size == TRAILER_MARKER {
+                #[cfg(feature = "crc")]
+                check_crc(u32::from_be_bytes(bytes), crc.finalize())?;
+                break;
+            }
+            let uncompressed_size = u32::from_be_bytes(bytes);
+
+            let mut compressed = Vec::new();
+            (&mut reader)
+                .take(compressed_size as u64)
+                .read_to_end(&mut compressed)
+                .await?;
+            if compressed.len() < compressed_size as usize {
+                return Err(eof_err!(
+                    "Truncated record: {} of {} bytes",
+                    compressed.len(),
+                    compressed_size
+                ));
+            }
+            let size = checked_size_hint(
+                codec.as_ref(),
+                uncompressed_size as u64,
+                1,
+                compressed.len(),
+            )?;
+            let (returned, decompressed) = run_blocking(codec, move |codec| {
+                let mut output = ColumnData::VecU8(Vec::new());
+                codec.decompress(&compressed, &mut output, size)?;
+                match output {
+                    ColumnData::VecU8(x) => Ok(x),
+                    _ => unreachable!(),
+                }
+            })
+            .await?;
+            codec = returned;
+            #[cfg(feature = "crc")]
+            crc.update(&decompressed);
+            writer.write_all(&decompressed).await?;
+        }
+        writer.flush().await?;
+        Ok(writer)
+    }
+}
+pub use frame_io::*;
+
+mod column_schema {
+    use crate::basic::ConvertedType;
+    use crate::errors::{ParquetError, Result};
+
+    /// Magic bytes starting a schema header.
+    const SCHEMA_MAGIC: &[u8; 4] = b"PQCS";
+
+    /// Describes the column a standalone compressed buffer holds, so that it can be
+    /// interpreted without external metadata.
+    ///
+    /// Written in front of the compressed values by `Codec::compress_with_schema` and
+    /// read back with [`read_schema`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ColumnSchema {
+        /// Name of the column.
+        pub name: String,
+        /// Logical type of the values, e.g. `ConvertedType::UTF8` or `ConvertedType::NONE`.
+        pub converted_type: ConvertedType,
+    }
+
+    impl ColumnSchema {
+        /// Creates a new schema for the column `name` of logical type `converted_type`.
+        pub fn new(name: impl Into<String>, converted_type: ConvertedType) -> Self {
+            Self {
+                name: name.into(),
+                converted_type,
+            }
+        }
+    }
+
+    /// Appends the schema header: the magic bytes, then the name and the logical type
+    /// name, each prefixed with its length as big-endian u16.
+    pub(crate) fn write_schema(schema: &ColumnSchema, output_buf: &mut Vec<u8>) -> Result<()> {
+        output_buf.extend_from_slice(SCHEMA_MAGIC);
+        for field in [schema.name.clone(), schema.converted_type.to_string()] {
+            let len = u16::try_from(field.len())
+                .map_err(|_| general_err!("Schema field too long: {} bytes", field.len()))?;
+            output_buf.extend_from_slice(&len.to_be_bytes());
+            output_buf.extend_from_slice(field.as_bytes());
+        }
+        Ok(())
+    }
+
+    /// Splits `input_buf` into the schema in its header and the compressed values after it.
+    pub(crate) fn split_schema(input_buf: &[u8]) -> Result<(ColumnSchema, &[u8])> {
+        if !input_buf.starts_with(SCHEMA_MAGIC) {
+            return Err(general_err!("Input does not start with a column schema"));
+        }
+        let mut rest = &input_buf[SCHEMA_MAGIC.len()..];
+        let mut fields = Vec::with_capacity(2);
+        for _ in 0..2 {
+            if rest.len() < 2 {
+                return Err(eof_err!("Truncated column schema"));
+            }
+            let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+            let field = rest
+                .get(2..2 + len)
+                .ok_or_else(|| eof_err!("Truncated column schema"))?;
+            let field = std::str::from_utf8(field)
+                .map_err(|e| general_err!("Invalid column schema: {}", e))?;
+            fields.push(field.to_string());
+            rest = &rest[2 + len..];
+        }
+        let converted_type = fields[1].parse()?;
+        let name = fields.swap_remove(0);
+        Ok((ColumnSchema::new(name, converted_type), rest))
+    }
+
+    /// Reads the schema header written by `Codec::compress_with_schema`.
+    pub fn read_schema(input_buf: &[u8]) -> Result<ColumnSchema> {
+        split_schema(input_buf).map(|(schema, _)| schema)
+    }
+}
+pub use column_schema::*;
+
+mod sparse {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::{ColumnData, DataTypeConstraint};
+
+    /// Length of the sparse header: total length and exception count as big-endian
+    /// u64, then the compressed index length as big-endian u32.
+    const SPARSE_HEADER_LEN: usize = 20;
+
+    /// Compresses a column that is mostly `default`, storing only the total length and
+    /// the `(index, value)` pairs of the elements that differ from it.
+    ///
+    /// Indices and values are compressed separately with `codec`. Floats are compared
+    /// bitwise, so `-0.0` and NaN payloads are kept exactly. Read the result back with
+    /// [`decompress_sparse`].
+    pub fn compress_sparse<T: DataTypeConstraint>(
+        values: &ColumnData,
+        default: T,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        let (indices, exceptions) = match values {
+            ColumnData::VecU8(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecU8)?,
+            ColumnData::VecU16(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecU16)?,
+            ColumnData::VecU32(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecU32)?,
+            ColumnData::VecU64(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecU64)?,
+            ColumnData::VecI8(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecI8)?,
+            ColumnData::VecI16(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecI16)?,
+            ColumnData::VecI32(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecI32)?,
+            ColumnData::VecI64(x) => split_sparse(x, &default, |a, b| a == b, ColumnData::VecI64)?,
+            ColumnData::VecF32(x) => {
+                split_sparse(x, &default, |a, b| a.to_bits() == b.to_bits(), ColumnData::VecF32)?
+            }
+            ColumnData::VecF64(x) => {
+                split_sparse(x, &default, |a, b| a.to_bits() == b.to_bits(), ColumnData::VecF64)?
+            }
+            // decompress_sparse rebuilds the column from a primitive type
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => {
+                return Err(nyi_err!("Sparse {} columns are not supported", values.typename()))
+            }
+        };
+
+        let mut output = Vec::with_capacity(SPARSE_HEADER_LEN);
+        output.extend_from_slice(&(values.len() as u64).to_be_bytes());
+        output.extend_from_slice(&(indices.len() as u64).to_be_bytes());
+        output.extend_from_slice(&[0u8; 4]);
+        if !indices.is_empty() {
+            let mut codec = create_codec(codec, options)?
+                .ok_or_else(|| general_err!("compress_sparse requires a compression codec"))?;
+            codec.compress(&ColumnData::VecU64(indices), &mut output)?;
+            let indices_len = output.len() - SPARSE_HEADER_LEN;
+            let indices_len = u32::try_from(indices_len).map_err(|_| {
+                general_err!("compress_sparse: indices too large: {} bytes", indices_len)
+            })?;
+            output[16..SPARSE_HEADER_LEN].copy_from_slice(&indices_len.to_be_bytes());
+            codec.compress(&exceptions, &mut output)?;
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs a column compressed with [`compress_sparse`], filling every
+    /// position without an exception with `default`.
+    ///
+    /// `codec` and `default` must match the ones used to compress.
+    pub fn decompress_sparse<T: DataTypeConstraint>(
+        input_buf: &[u8],
+        default: T,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<ColumnData> {
+        if input_buf.len() < SPARSE_HEADER_LEN {
+            return Err(eof_err!(
+                "sparse column header needs {} bytes, got {}",
+                SPARSE_HEADER_LEN,
+                input_buf.len()
+            ));
+        }
+        let read_u64 = |at: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&input_buf[at..at + 8]);
+            u64::from_be_bytes(bytes)
+        };
+        let (len, count) = (read_u64(0), read_u64(8));
+        if count > len {
+            return Err(general_err!(
+                "Sparse column has {} exceptions for {} values",
+                count,
+                len
+            ));
+        }
+        let mut indices_len = [0u8; 4];
+        indices_len.copy_from_slice(&input_buf[16..SPARSE_HEADER_LEN]);
+        let indices_end = SPARSE_HEADER_LEN + u32::from_be_bytes(indices_len) as usize;
+        if indices_end > input_buf.len() {
+            return Err(eof_err!(
+                "sparse column indices end at byte {}, input has {}",
+                indices_end,
+                input_buf.len()
+            ));
+        }
+
+        let mut indices = ColumnData::VecU64(Vec::new());
+        let mut exceptions = ColumnData::try_new::<T>(&[])?;
+        // the defaults are filled in without any input bytes, so only the size of the
+        // whole column bounds the length
+        let len = usize::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_mul(exceptions.type_width()))
+            .filter(|&size| size <= isize::MAX as usize)
+            .map(|size| size / exceptions.type_width())
+            .ok_or_else(|| general_err!("Sparse column length {} is too large", len))?;
+        if count > 0 {
+            let mut codec = create_codec(codec, options)?
+                .ok_or_else(|| general_err!("decompress_sparse requires a compression codec"))?;
+            let indices_buf = &input_buf[SPARSE_HEADER_LEN..indices_end];
+            let indices_size = checked_size_hint(
+                codec.as_ref(),
+                count,
+                std::mem::size_of::<u64>(),
+                indices_buf.len(),
+            )?;
+            codec.decompress(indices_buf, &mut indices, indices_size)?;
+            let exceptions_buf = &input_buf[indices_end..];
+            let exceptions_size = checked_size_hint(
+                codec.as_ref(),
+                count,
+                exceptions.type_width(),
+                exceptions_buf.len(),
+            )?;
+            codec.decompress(exceptions_buf, &mut exceptions, exceptions_size)?;
+        }
+        let indices = match indices {
+            ColumnData::VecU64(x) => x,
+            _ => unreachable!(),
+        };
+
+        Ok(match exceptions {
+            ColumnData::VecU8(x) => ColumnData::VecU8(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecU16(x) => ColumnData::VecU16(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecU32(x) => ColumnData::VecU32(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecU64(x) => ColumnData::VecU64(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecI8(x) => ColumnData::VecI8(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecI16(x) => ColumnData::VecI16(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecI32(x) => ColumnData::VecI32(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecI64(x) => ColumnData::VecI64(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecF32(x) => ColumnData::VecF32(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecF64(x) => ColumnData::VecF64(fill_sparse(len, &default, &indices, x)?),
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => unreachable!(),
+        })
+    }
+
+    /// Downcasts `default` to the column's element type `T`.
+    fn downcast_default<T: DataTypeConstraint, D: DataTypeConstraint>(default: &D) -> Result<T> {
+        default.as_any().downcast_ref::<T>().copied().ok_or_else(|| {
+            general_err!(
+                "Sparse default of {} does not match {} column",
+                default.typename(),
+                T::default().typename()
+            )
+        })
+    }
+
+    /// Splits `values` into the indices and values of the elements that are not
+    /// `is_default` to the default.
+    fn split_sparse<T: DataTypeConstraint, D: DataTypeConstraint>(
+        values: &[T],
+        default: &D,
+        is_default: fn(&T, &T) -> bool,
+        wrap: fn(Vec<T>) -> ColumnData,
+    ) -> Result<(Vec<u64>, ColumnData)> {
+        let default = downcast_default::<T, D>(default)?;
+        let mut indices = Vec::new();
+        let mut exceptions = Vec::new();
+        for (i, v) in values.iter().enumerate() {
+            if !is_default(v, &default) {
+                indices.push(i as u64);
+                exceptions.push(*v);
+            }
+        }
+        Ok((indices, wrap(exceptions)))
+    }
+
+    /// Builds a column of `len` defaults with `exceptions` written at `indices`.
+    fn fill_sparse<T: DataTypeConstraint, D: DataTypeConstraint>(
+        len: usize,
+        default: &D,
+        indices: &[u64],
+        exceptions: Vec<T>,
+    ) -> Result<Vec<T>> {
+        if indices.len() != exceptions.len() {
+            return Err(general_err!(
+                "Sparse column has {} indices but {} values",
+                indices.len(),
+                exceptions.len()
+            ));
+        }
+        let mut values = vec![downcast_default::<T, D>(default)?; len];
+        for (&i, v) in indices.iter().zip(exceptions) {
+            let slot = values.get_mut(i as usize).ok_or_else(|| {
+                general_err!("Sparse index {} is out of bounds for length {}", i, len)
+            })?;
+            *slot = v;
+        }
+        Ok(values)
+    }
+}
+pub use sparse::*;
+
+mod list {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the list header: list count as big-endian u64, then the compressed
+    /// offsets length as big-endian u32.
+    const LIST_HEADER_LEN: usize = 12;
+
+    /// Compresses a column of lists, such as a Parquet repeated field, by flattening
+    /// the lists into one column and recording where each list ends.
+    ///
+    /// The end offsets and the flattened values are compressed separately with
+    /// `codec`. All lists must hold the same element type. Read the result back with
+    /// [`decompress_list`].
+    pub fn compress_list(
+        values: &[ColumnData],
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(LIST_HEADER_LEN);
+        output.extend_from_slice(&(values.len() as u64).to_be_bytes());
+        output.extend_from_slice(&[0u8; 4]);
+        let first = match values.first() {
+            Some(first) => first,
+            None => return Ok(output),
+        };
+
+        let mut flat = first.slice(0, 0);
+        let mut offsets = Vec::with_capacity(values.len());
+        for list in values {
+            flat.extend_from(list)?;
+            offsets.push(flat.len() as u64);
+        }
+
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("compress_list requires a compression codec"))?;
+        codec.compress(&ColumnData::VecU64(offsets), &mut output)?;
+        let offsets_len = output.len() - LIST_HEADER_LEN;
+        let offsets_len = u32::try_from(offsets_len).map_err(|_| {
+            general_err!("compress_list: offsets too large: {} bytes", offsets_len)
+        })?;
+        output[8..LIST_HEADER_LEN].copy_from_slice(&offsets_len.to_be_bytes());
+        if flat.len() > 0 {
+            codec.compress(&flat, &mut output)?;
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs the lists compressed with [`compress_list`].
+    ///
+    /// `element_type` is a column of the element type the lists were compressed with,
+    /// e.g. `ColumnData::VecI32(vec![])`; its values are ignored. `codec` must match
+    /// the one used to compress.
+    pub fn decompress_list(
+        input_buf: &[u8],
+        element_type: &ColumnData,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<ColumnData>> {
+        if input_buf.len() < LIST_HEADER_LEN {
+            return Err(eof_err!(
+                "list column header needs {} bytes, got {}",
+                LIST_HEADER_LEN,
+                input_buf.len()
+            ));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&input_buf[..8]);
+        let count = u64::from_be_bytes(count);
+        let mut offsets_len = [0u8; 4];
+        offsets_len.copy_from_slice(&input_buf[8..LIST_HEADER_LEN]);
+        let offsets_end = LIST_HEADER_LEN + u32::from_be_bytes(offsets_len) as usize;
+        if offsets_end > input_buf.len() {
+            return Err(eof_err!(
+                "list column offsets end at byte {}, input has {}",
+                offsets_end,
+                input_buf.len()
+            ));
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("decompress_list requires a compression codec"))?;
+        let offsets_buf = &input_buf[LIST_HEADER_LEN..offsets_end];
+        let offsets_size = checked_size_hint(
+            codec.as_ref(),
+            count,
+            std::mem::size_of::<u64>(),
+            offsets_buf.len(),
+        )?;
+        let mut offsets = ColumnData::VecU64(Vec::new());
+        codec.decompress(offsets_buf, &mut offsets, offsets_size)?;
+        let offsets = match offsets {
+            ColumnData::VecU64(x) => x,
+            _ => unreachable!(),
+        };
+        if offsets.len() as u64 != count {
+            return Err(general_err!(
+                "List column has {} offsets for {} lists",
+                offsets.len(),
+                count
+            ));
+        }
+
+        let total = offsets[offsets.len() - 1];
+        let mut flat = element_type.slice(0, 0);
+        if total > 0 {
+            let flat_buf = &input_buf[offsets_end..];
+            let flat_size =
+                checked_size_hint(codec.as_ref(), total, flat.type_width(), flat_buf.len())?;
+            codec.decompress(flat_buf, &mut flat, flat_size)?;
+        }
+        if flat.len() as u64 != total {
+            return Err(general_err!(
+                "List column offsets end at {} but {} values were decoded",
+                total,
+                flat.len()
+            ));
+        }
+
+        let total = flat.len();
+        let mut lists = Vec::with_capacity(offsets.len());
+        let mut start = 0;
+        for &end in &offsets {
+            let end = end as usize;
+            if end < start || end > total {
+                return Err(general_err!("List column offset {} is out of order", end));
+            }
+            lists.push(flat.slice(start, end - start));
+            start = end;
+        }
+        Ok(lists)
+    }
+}
+pub use list::*;
+
+mod complex {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the complex header: pair count as big-endian u64, then the layout
+    /// byte.
+    const COMPLEX_HEADER_LEN: usize = 9;
+
+    /// Layout byte of components kept as interleaved `re, im` pairs.
+    const INTERLEAVED: u8 = 0;
+
+    /// Layout byte of components split into a real stream then an imaginary stream.
+    const SPLIT: u8 = 1;
+
+    /// Returns the float type of each component of the complex type `typename`,
+    /// `"complex64"` or `"complex128"`.
+    pub fn complex_component_type(typename: &str) -> Option<&'static str> {
+        match typename {
+            "complex64" => Some("f32"),
+            "complex128" => Some("f64"),
+            _ => None,
+        }
+    }
+
+    /// Compresses a column of complex numbers stored as interleaved `re, im` float
+    /// pairs: `VecF32` for complex64 or `VecF64` for complex128.
+    ///
+    /// With `split_streams`, all real parts are written before all imaginary parts,
+    /// which usually compresses better as each stream is smoother than the pairs.
+    /// Floats are moved bitwise, so NaN payloads and `-0.0` are kept exactly. Read the
+    /// result back with [`decompress_complex`].
+    pub fn compress_complex(
+        values: &ColumnData,
+        split_streams: bool,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        let reordered = match values {
+            ColumnData::VecF32(x) => ColumnData::VecF32(split_pairs(x, split_streams)?),
+            ColumnData::VecF64(x) => ColumnData::VecF64(split_pairs(x, split_streams)?),
+            _ => {
+                return Err(general_err!(
+                    "Complex columns hold f32 or f64 pairs, got {}",
+                    values.typename()
+                ))
+            }
+        };
+
+        let mut output = Vec::with_capacity(COMPLEX_HEADER_LEN);
+        output.extend_from_slice(&(values.len() as u64 / 2).to_be_bytes());
+        output.push(if split_streams { SPLIT } else { INTERLEAVED });
+        if reordered.len() > 0 {
+            let mut codec = create_codec(codec, options)?
+                .ok_or_else(|| general_err!("compress_complex requires a compression codec"))?;
+            codec.compress(&reordered, &mut output)?;
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs the interleaved `re, im` pairs compressed with
+    /// [`compress_complex`], as `VecF32` for `"complex64"` or `VecF64` for
+    /// `"complex128"`.
+    ///
+    /// `codec` must match the one used to compress.
+    pub fn decompress_complex(
+        input_buf: &[u8],
+        typename: &str,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<ColumnData> {
+        let mut values = match complex_component_type(typename) {
+            Some("f32") => ColumnData::VecF32(Vec::new()),
+            Some(_) => ColumnData::VecF64(Vec::new()),
+            None => return Err(general_err!("{} is not a complex type", typename)),
+        };
+        if input_buf.len() < COMPLEX_HEADER_LEN {
+            return Err(eof_err!(
+                "complex column header needs {} bytes, got {}",
+                COMPLEX_HEADER_LEN,
+                input_buf.len()
+            ));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&input_buf[..8]);
+        let count = u64::from_be_bytes(count);
+        let split_streams = match input_buf[8] {
+            INTERLEAVED => false,
+            SPLIT => true,
+            layout => return Err(general_err!("Unknown complex column layout {}", layout)),
+        };
+        if count == 0 {
+            return Ok(values);
+        }
+
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("decompress_complex requires a compression codec"))?;
+        let payload = &input_buf[COMPLEX_HEADER_LEN..];
+        let pair_width = 2 * values.type_width();
+        let size = checked_size_hint(codec.as_ref(), count, pair_width, payload.len())?;
+        codec.decompress(payload, &mut values, size)?;
+        if values.len() as u64 != count.saturating_mul(2) {
+            return Err(general_err!(
+                "Complex column has {} pairs but {} floats were decoded",
+                count,
+                values.len()
+            ));
+        }
+        Ok(match values {
+            ColumnData::VecF32(x) => ColumnData::VecF32(join_pairs(&x, split_streams)),
+            ColumnData::VecF64(x) => ColumnData::VecF64(join_pairs(&x, split_streams)),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Copies interleaved pairs, moving all first components before all second
+    /// components if `split_streams` is set.
+    fn split_pairs<T: Copy>(values: &[T], split_streams: bool) -> Result<Vec<T>> {
+        if values.len() % 2 != 0 {
+            return Err(general_err!(
+                "Complex column has an odd number of floats: {}",
+                values.len()
+            ));
+        }
+        if !split_streams {
+            return Ok(values.to_vec());
+        }
+        let mut output = Vec::with_capacity(values.len());
+        output.extend(values.iter().step_by(2));
+        output.extend(values.iter().skip(1).step_by(2));
+        Ok(output)
+    }
+
+    /// Reverses [`split_pairs`], interleaving the two halves of `values` again.
+    fn join_pairs<T: Copy>(values: &[T], split_streams: bool) -> Vec<T> {
+        if !split_streams {
+            return values.to_vec();
+        }
+        let (re, im) = values.split_at(values.len() / 2);
+        re.iter().zip(im).flat_map(|(&r, &i)| [r, i]).collect()
+    }
+}
+pub use complex::*;
+
+mod union {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of the union header: element count then tagged byte length, both as
+    /// big-endian u64.
+    const UNION_HEADER_LEN: usize = 16;
+
+    /// Type tag written before each union element. The values are part of the
+    /// format: new types get new tags, and existing tags never change.
+    const UNION_TAGS: [(u8, &str); 12] = [
+        (0, "u8"),
+        (1, "u16"),
+        (2, "u32"),
+        (3, "u64"),
+        (4, "i8"),
+        (5, "i16"),
+        (6, "i32"),
+        (7, "i64"),
+        (8, "f32"),
+        (9, "f64"),
+        (10, "i24"),
+        (11, "u24"),
+    ];
+
+    /// Returns the tag of the type named `typename`.
+    fn tag_for(typename: &str) -> Result<u8> {
+        UNION_TAGS
+            .iter()
+            .find(|(_, name)| *name == typename)
+            .map(|(tag, _)| *tag)
+            .ok_or_else(|| nyi_err!("Union elements of type {} have no type tag", typename))
+    }
+
+    /// Returns an empty column of the type with tag `tag`, or `None` for unknown tags.
+    fn empty_for_tag(tag: u8) -> Option<ColumnData> {
+        let (_, typename) = UNION_TAGS.iter().find(|(t, _)| *t == tag)?;
+        ColumnData::from_typename(typename)
+    }
+
+    /// Compresses a heterogeneous column whose elements may each have a different
+    /// type. Every entry of `values` is one element: a `ColumnData` holding a single
+    /// value of any type.
+    ///
+    /// Each element is written as a 1-byte type tag followed by its value bytes, and
+    /// the tagged stream is compressed with `codec`. Read the result back with
+    /// [`decompress_union`].
+    pub fn compress_union(
+        values: &[ColumnData],
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        let mut tagged = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            if value.len() != 1 {
+                return Err(general_err!(
+                    "Union element {} holds {} values instead of one",
+                    i,
+                    value.len()
+                ));
+            }
+            tagged.push(tag_for(value.typename())?);
+            value.convert_to_u8(&mut tagged)?;
+        }
+
+        let mut output = Vec::with_capacity(UNION_HEADER_LEN);
+        output.extend_from_slice(&(values.len() as u64).to_be_bytes());
+        output.extend_from_slice(&(tagged.len() as u64).to_be_bytes());
+        if !tagged.is_empty() {
+            let mut codec = create_codec(codec, options)?
+                .ok_or_else(|| general_err!("compress_union requires a compression codec"))?;
+            codec.compress(&ColumnData::VecU8(tagged), &mut output)?;
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs the elements compressed with [`compress_union`], each as a
+    /// single-value `ColumnData` of its own type.
+    ///
+    /// `codec` must match the one used to compress.
+    pub fn decompress_union(
+        input_buf: &[u8],
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<ColumnData>> {
+        if input_buf.len() < UNION_HEADER_LEN {
+            return Err(eof_err!(
+                "union column header needs {} bytes, got {}",
+                UNION_HEADER_LEN,
+                input_buf.len()
+            ));
+        }
+        let mut field = [0u8; 8];
+        field.copy_from_slice(&input_buf[..8]);
+        let count = u64::from_be_bytes(field);
+        field.copy_from_slice(&input_buf[8..UNION_HEADER_LEN]);
+        let size = u64::from_be_bytes(field);
+        if size == 0 {
+            return convert_union_from_u8(&[], count);
+        }
+
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("decompress_union requires a compression codec"))?;
+        let payload = &input_buf[UNION_HEADER_LEN..];
+        let size = checked_size_hint(codec.as_ref(), size, 1, payload.len())?;
+        let mut tagged = ColumnData::VecU8(Vec::new());
+        codec.decompress(payload, &mut tagged, size)?;
+        match tagged {
+            ColumnData::VecU8(bytes) => convert_union_from_u8(&bytes, count),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Decodes `count` tagged elements from `input`, reading each element's type tag
+    /// and then as many value bytes as that type is wide.
+    ///
+    /// An unknown tag is an error, since the width of its value and so the position of
+    /// every later element is unknown.
+    fn convert_union_from_u8(input: &[u8], count: u64) -> Result<Vec<ColumnData>> {
+        // every element takes its tag and at least one value byte
+        if count > (input.len() / 2) as u64 {
+            return Err(general_err!(
+                "Union column has {} elements but only {} bytes",
+                count,
+                input.len()
+            ));
+        }
+        let mut values = Vec::with_capacity(count as usize);
+        let mut offset = 0;
+        while offset < input.len() {
+            let tag = input[offset];
+            let mut value = empty_for_tag(tag).ok_or_else(|| {
+                general_err!("Unsupported union type tag {} at byte {}", tag, offset)
+            })?;
+            let start = offset + 1;
+            let end = start + value.type_width();
+            if end > input.len() {
+                return Err(eof_err!(
+                    "Union element of type {} at byte {} is truncated",
+                    value.typename(),
+                    offset
+                ));
+            }
+            value.convert_from_u8(&input[start..end])?;
+            values.push(value);
+            offset = end;
+        }
+        if values.len() as u64 != count {
+            return Err(general_err!(
+                "Union column has {} elements but {} were decoded",
+                count,
+                values.len()
+            ));
+        }
+        Ok(values)
+    }
+}
+pub use union::*;
+
+mod windows {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of a window header: the kind byte, then the element count of the frame
+    /// as big-endian u64.
+    const WINDOW_HEADER_LEN: usize = 9;
+
+    /// Kind byte of a window holding all of its values.
+    const FULL: u8 = 0;
+
+    /// Kind byte of a window holding only the values it adds to the previous window.
+    const TAIL: u8 = 1;
+
+    /// Compresses the sliding windows of `series` that start every `step` elements
+    /// and hold `window` elements each, one buffer per window. A trailing partial
+    /// window is left out.
+    ///
+    /// With `diff_overlap`, and windows that overlap, every window after the first only
+    /// stores the `step` values it adds to the previous one. That saves compressing
+    /// the shared values again, but then a window can only be read back after the ones
+    /// before it, as [`decompress_windows`] does.
+    pub fn compress_windows(
+        series: &ColumnData,
+        window: usize,
+        step: usize,
+        diff_overlap: bool,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<Vec<u8>>> {
+        if window == 0 || step == 0 {
+            return Err(general_err!(
+                "Window length and step must be positive, got {} and {}",
+                window,
+                step
+            ));
+        }
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("compress_windows requires a compression codec"))?;
+
+        let len = series.len();
+        let mut output = Vec::new();
+        for start in (0..len.saturating_sub(window - 1)).step_by(step) {
+            let (kind, values) = if diff_overlap && step < window && start > 0 {
+                (TAIL, series.slice(start + window - step, step))
+            } else {
+                (FULL, series.slice(start, window))
+            };
+            let mut buffer = Vec::with_capacity(WINDOW_HEADER_LEN);
+            buffer.push(kind);
+            buffer.extend_from_slice(&(values.len() as u64).to_be_bytes());
+            codec.compress(&values, &mut buffer)?;
+            output.push(buffer);
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs the windows compressed with [`compress_windows`], in order, as
+    /// columns of type `typename`.
+    ///
+    /// `codec` must match the one used to compress.
+    pub fn decompress_windows(
+        windows: &[Vec<u8>],
+        typename: &str,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<ColumnData>> {
+        let empty = ColumnData::from_typename(typename)
+            .ok_or_else(|| general_err!("Unsupported window type {}", typename))?;
+        let mut codec = create_codec(codec, options)?
+            .ok_or_else(|| general_err!("decompress_windows requires a compression codec"))?;
+
+        let mut output: Vec<ColumnData> = Vec::with_capacity(windows.len());
+        for (i, buffer) in windows.iter().enumerate() {
+            if buffer.len() < WINDOW_HEADER_LEN {
+                return Err(eof_err!(
+                    "window {} header needs {} bytes, got {}",
+                    i,
+                    WINDOW_HEADER_LEN,
+                    buffer.len()
+                ));
+            }
+            let mut count = [0u8; 8];
+            count.copy_from_slice(&buffer[1..WINDOW_HEADER_LEN]);
+            let count = u64::from_be_bytes(count);
+            let payload = &buffer[WINDOW_HEADER_LEN..];
+
+            let mut values = empty.clone();
+            let size =
+                checked_size_hint(codec.as_ref(), count, values.type_width(), payload.len())?;
+            codec.decompress(payload, &mut values, size)?;
+            if values.len() as u64 != count {
+                return Err(general_err!(
+                    "Window {} has {} values but {} were decoded",
+                    i,
+                    count,
+                    values.len()
+                ));
+            }
+            let count = values.len();
+
+            let window = match (buffer[0], output.last()) {
+                (FULL, _) => values,
+                (TAIL, Some(previous)) if count <= previous.len() => {
+                    let mut window = previous.slice(count, previous.len() - count);
+                    window.extend_from(&values)?;
+                    window
+                }
+                (TAIL, _) => {
+                    return Err(general_err!(
+                        "Window {} extends a missing or shorter previous window",
+                        i
+                    ))
                 }
+                (kind, _) => return Err(general_err!("Unknown window kind {}", kind)),
+            };
+            output.push(window);
+        }
+        Ok(output)
+    }
+}
+pub use windows::*;
+
+mod n_blocks {
+    use crate::compression::{checked_size_hint, create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Length of a block header: the element count of the block as big-endian u64.
+    const BLOCK_HEADER_LEN: usize = 8;
+
+    /// Compresses `values` as `n` independently compressed blocks of consecutive
+    /// elements, one buffer per block, so that readers can decompress the blocks in
+    /// parallel.
+    ///
+    /// The element counts of the blocks differ by at most one, with the longer blocks
+    /// first; when `values` has fewer than `n` elements the last blocks are empty.
+    /// Every block records its element count. Read the blocks back with
+    /// [`decompress_n_blocks`].
+    pub fn compress_into_n_blocks(
+        values: &ColumnData,
+        n: usize,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<Vec<u8>>> {
+        if n == 0 {
+            return Err(general_err!("Number of blocks must be positive, got 0"));
+        }
+        let mut codec = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("compress_into_n_blocks requires a compression codec")
+        })?;
+
+        let (block_len, longer_blocks) = (values.len() / n, values.len() % n);
+        let mut output = Vec::with_capacity(n);
+        let mut start = 0;
+        for i in 0..n {
+            let len = block_len + usize::from(i < longer_blocks);
+            let block = values.slice(start, len);
+            start += len;
+
+            let mut buffer = Vec::with_capacity(BLOCK_HEADER_LEN);
+            buffer.extend_from_slice(&(len as u64).to_be_bytes());
+            codec.compress(&block, &mut buffer)?;
+            output.push(buffer);
+        }
+        Ok(output)
+    }
+
+    /// Reconstructs a column of type `typename` compressed with
+    /// [`compress_into_n_blocks`], decompressing its blocks in order.
+    ///
+    /// `codec` must match the one used to compress. Each block can also be decompressed
+    /// on its own, e.g. on another thread, and the parts concatenated in block order.
+    pub fn decompress_n_blocks(
+        blocks: &[Vec<u8>],
+        typename: &str,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<ColumnData> {
+        let mut output = ColumnData::from_typename(typename)
+            .ok_or_else(|| general_err!("Unsupported block type {}", typename))?;
+        let mut codec = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("decompress_n_blocks requires a compression codec")
+        })?;
+
+        for (i, buffer) in blocks.iter().enumerate() {
+            if buffer.len() < BLOCK_HEADER_LEN {
+                return Err(eof_err!(
+                    "block {} header needs {} bytes, got {}",
+                    i,
+                    BLOCK_HEADER_LEN,
+                    buffer.len()
+                ));
             }
-            encoder.finish().1.map_err(|e| e.into())
+            let mut count = [0u8; 8];
+            count.copy_from_slice(&buffer[..BLOCK_HEADER_LEN]);
+            let count = u64::from_be_bytes(count);
+            let payload = &buffer[BLOCK_HEADER_LEN..];
+
+            let mut values = output.slice(0, 0);
+            let size =
+                checked_size_hint(codec.as_ref(), count, values.type_width(), payload.len())?;
+            codec.decompress(payload, &mut values, size)?;
+            if values.len() as u64 != count {
+                return Err(general_err!(
+                    "Block {} has {} values but {} were decoded",
+                    i,
+                    count,
+                    values.len()
+                ));
+            }
+            output.extend_from(&values)?;
         }
+        Ok(output)
     }
 }
-#[cfg(any(feature = "lz4", test))]
-pub use lz4_codec::*;
-
-#[cfg(any(feature = "zstd", test))]
-mod zstd_codec {
-    use std::io::{self, Write};
+pub use n_blocks::*;
 
-    use crate::compression::Codec;
-    use crate::errors::Result;
+mod lossy_float {
+    use crate::compression::{
+        checked_size_hint, create_codec, decompress_typed_with_bytes, fingerprint,
+        BitPackCodec, Codec, CodecOptions, CodecType,
+    };
+    use crate::errors::{ParquetError, Result};
 
     // enum for columnar data
     use crate::data_type::ColumnData;
 
-    /// Codec for Zstandard compression algorithm.
-    pub struct ZSTDCodec {}
+    /// Length of the quantization header: bits per value, value count as big-endian
+    /// u64, then the minimum and the quantization step as big-endian f64.
+    const LOSSY_HEADER_LEN: usize = 25;
 
-    impl ZSTDCodec {
-        /// Creates new Zstandard compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
+    /// Largest bit budget, the precision of an f64 mantissa.
+    const MAX_BITS_PER_VALUE: u8 = 52;
+
+    /// Codec wrapper that quantizes float columns into a fixed number of bits per
+    /// value and bit packs them before compressing them with another codec.
+    ///
+    /// Each value is mapped to the nearest of `2^bits - 1` equal steps between the
+    /// column's minimum and maximum, which are recorded with the packed values, so a
+    /// decompressed value differs from the original by at most half a step. The codec
+    /// is lossy and only accepts finite `f32` and `f64` values.
+    pub struct LossyFloatCodec {
+        inner: Box<dyn Codec>,
+        bits: u8,
+        /// Number of elements the last `compress` call processed.
+        last_element_count: usize,
+    }
+
+    impl LossyFloatCodec {
+        /// Creates a new codec quantizing float input into `bits` bits per value, from
+        /// 1 to 52, and compressing the packed values with `inner`.
+        pub fn new(inner: Box<dyn Codec>, bits: u8) -> Self {
+            Self {
+                inner: Box::new(BitPackCodec::new(inner, bits)),
+                bits,
+                last_element_count: 0,
+            }
         }
     }
 
-    /// Compression level (1-21) for ZSTD. Choose 1 here for better compression speed.
-    const ZSTD_COMPRESSION_LEVEL: i32 = 1;
+    /// Returns an error unless `bits` is a supported bit budget.
+    fn check_bits(bits: u8) -> Result<()> {
+        if bits == 0 || bits > MAX_BITS_PER_VALUE {
+            return Err(general_err!(
+                "Lossy float bits per value must be between 1 and {}, got {}",
+                MAX_BITS_PER_VALUE,
+                bits
+            ));
+        }
+        Ok(())
+    }
 
-    impl Codec for ZSTDCodec {
+    /// Returns the minimum and the quantization step of `values` for a budget of
+    /// `bits` bits per value.
+    fn quantization_range(values: &[f64], bits: u8) -> Result<(f64, f64)> {
+        if let Some(v) = values.iter().find(|v| !v.is_finite()) {
+            return Err(general_err!("LossyFloat compress: cannot quantize {}", v));
+        }
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if values.is_empty() || min == max {
+            return Ok((values.first().copied().unwrap_or(0.0), 0.0));
+        }
+        let range = max - min;
+        if !range.is_finite() {
+            return Err(general_err!(
+                "LossyFloat compress: range from {} to {} is too wide",
+                min,
+                max
+            ));
+        }
+        Ok((min, range / ((1u64 << bits) - 1) as f64))
+    }
+
+    impl Codec for LossyFloatCodec {
         fn decompress(
             &mut self,
             input_buf: &[u8],
             output_buf_columndata: &mut ColumnData,
             _uncompress_size: Option<usize>,
         ) -> Result<usize> {
+            if input_buf.len() < LOSSY_HEADER_LEN {
+                return Err(eof_err!(
+                    "lossy float header needs {} bytes, got {}",
+                    LOSSY_HEADER_LEN,
+                    input_buf.len()
+                ));
+            }
+            let read_u64 = |at: usize| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&input_buf[at..at + 8]);
+                u64::from_be_bytes(bytes)
+            };
+            let bits = input_buf[0];
+            if bits != self.bits {
+                return Err(general_err!(
+                    "Lossy float column has {} bits per value, codec expects {}",
+                    bits,
+                    self.bits
+                ));
+            }
+            let count = read_u64(1);
+            let min = f64::from_bits(read_u64(9));
+            let step = f64::from_bits(read_u64(17));
+
+            let payload = &input_buf[LOSSY_HEADER_LEN..];
+            let size = checked_size_hint(
+                self.inner.as_ref(),
+                count,
+                std::mem::size_of::<u64>(),
+                payload.len(),
+            )?;
+            let mut codes = ColumnData::VecU64(Vec::new());
+            self.inner.decompress(payload, &mut codes, size)?;
+            let codes = match codes {
+                ColumnData::VecU64(x) => x,
+                _ => unreachable!(),
+            };
+            if codes.len() as u64 != count {
+                return Err(general_err!(
+                    "Lossy float column has {} values but {} were decoded",
+                    count,
+                    codes.len()
+                ));
+            }
+            let count = codes.len();
+            let values = codes.into_iter().map(|code| min + code as f64 * step);
+            match output_buf_columndata {
+                ColumnData::VecF64(x) => x.extend(values),
+                ColumnData::VecF32(x) => x.extend(values.map(|v| v as f32)),
+                other => {
+                    return Err(general_err!(
+                        "LossyFloat decompress: unsupported output type {}",
+                        other.typename()
+                    ))
+                }
+            }
+            Ok(count * output_buf_columndata.type_width())
+        }
 
-            let mut output_buf = Vec::new();
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
+        }
 
-            let mut decoder = zstd::Decoder::new(input_buf)?;
-            let ret = match io::copy(&mut decoder, &mut output_buf) {
-                Ok(n) => Ok(n as usize),
-                Err(e) => Err(e.into()),
+        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            check_bits(self.bits)?;
+            let values = match input_buf_columndata {
+                ColumnData::VecF64(x) => x.clone(),
+                ColumnData::VecF32(x) => x.iter().map(|&v| v as f64).collect(),
+                other => {
+                    return Err(general_err!(
+                        "LossyFloat compress: unsupported input type {}",
+                        other.typename()
+                    ))
+                }
+            };
+            let (min, step) = quantization_range(&values, self.bits)?;
+            let quantize = |v: &f64| {
+                if step > 0.0 {
+                    ((v - min) / step).round() as u64
+                } else {
+                    0
+                }
             };
+            let codes = values.iter().map(quantize).collect();
+
+            output_buf.push(self.bits);
+            output_buf.extend_from_slice(&(values.len() as u64).to_be_bytes());
+            output_buf.extend_from_slice(&min.to_bits().to_be_bytes());
+            output_buf.extend_from_slice(&step.to_bits().to_be_bytes());
+            self.inner.compress(&ColumnData::VecU64(codes), output_buf)?;
+            self.last_element_count = values.len();
+            Ok(())
+        }
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+        fn supports_type(&self, typename: &str) -> bool {
+            matches!(typename, "f32" | "f64")
+        }
 
-            ret
+        fn framing_overhead(&self) -> usize {
+            LOSSY_HEADER_LEN + self.inner.framing_overhead()
         }
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+        forward_to_inner!(
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            estimate_compress_duration,
+            backend_version,
+            name,
+        );
+
+        fn last_element_count(&self) -> usize {
+            self.last_element_count
+        }
 
-            let mut encoder = zstd::Encoder::new(output_buf, ZSTD_COMPRESSION_LEVEL)?;
-            encoder.write_all(&input_buf)?;
-            match encoder.finish() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(e.into()),
-            }
+        fn is_lossless(&self) -> bool {
+            false
+        }
+
+        fn reset(&mut self) {
+            self.last_element_count = 0;
+            self.inner.reset();
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            fingerprint(&("LossyFloat", self.bits, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self {
+                inner: self.inner.clone_codec(),
+                bits: self.bits,
+                last_element_count: 0,
+            })
         }
     }
+
+    /// Quantizes `values` into `bits_per_value` bits each, scaled between their minimum
+    /// and maximum, and compresses the packed values with `codec`.
+    ///
+    /// This is lossy, see [`LossyFloatCodec`]: [`decompress_lossy_float`] returns each
+    /// value to within half of `(max - min) / (2^bits_per_value - 1)`.
+    pub fn compress_lossy_float(
+        values: &[f64],
+        bits_per_value: u8,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        check_bits(bits_per_value)?;
+        let inner = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("compress_lossy_float requires a compression codec")
+        })?;
+        let mut codec = LossyFloatCodec::new(inner, bits_per_value);
+        let mut output = Vec::new();
+        codec.compress(&ColumnData::VecF64(values.to_vec()), &mut output)?;
+        Ok(output)
+    }
+
+    /// Reconstructs the approximate values compressed with [`compress_lossy_float`].
+    ///
+    /// `codec` must match the one used to compress; the bit budget is read from the
+    /// buffer.
+    pub fn decompress_lossy_float(
+        input_buf: &[u8],
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<f64>> {
+        let bits = *input_buf
+            .first()
+            .ok_or_else(|| eof_err!("Missing lossy float bits per value"))?;
+        check_bits(bits)?;
+        let inner = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("decompress_lossy_float requires a compression codec")
+        })?;
+        let mut codec = LossyFloatCodec::new(inner, bits);
+        let mut output = ColumnData::VecF64(Vec::new());
+        codec.decompress(input_buf, &mut output, None)?;
+        output.try_into_vec()
+    }
 }
-#[cfg(any(feature = "zstd", test))]
-pub use zstd_codec::*;
+pub use lossy_float::*;
 
-#[cfg(any(feature = "lz4", test))]
-mod lz4_raw_codec {
-    use crate::compression::Codec;
-    use crate::errors::ParquetError;
-    use crate::errors::Result;
+mod transform {
+    use std::time::Duration;
+
+    use crate::compression::{
+        checked_size_hint, decompress_typed_with_bytes, delta_residuals, fingerprint,
+        split_residual_width, swap_column, Codec, TransformId,
+    };
+    use crate::errors::{ParquetError, Result};
 
     // enum for columnar data
     use crate::data_type::ColumnData;
 
-    /// Codec for LZ4 Raw compression algorithm.
-    pub struct LZ4RawCodec {}
+    /// Reversible rewrite of a column that keeps the type of its values, applied
+    /// before a codec so that the values compress better. All transforms but
+    /// [`TransformId::Rle`] also keep the number of values.
+    ///
+    /// Transforms compose: a slice of transforms applies them in order and reverses
+    /// them in the opposite order.
+    pub trait Transform {
+        /// Rewrites `values` in place.
+        fn forward(&self, values: &mut ColumnData) -> Result<()>;
+
+        /// Reverses [`Transform::forward`] in place.
+        fn inverse(&self, values: &mut ColumnData) -> Result<()>;
+    }
 
-    impl LZ4RawCodec {
-        /// Creates new LZ4 Raw compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
+    impl Transform for TransformId {
+        fn forward(&self, values: &mut ColumnData) -> Result<()> {
+            match self {
+                TransformId::Identity => {}
+                TransformId::Delta => values.delta_encode(),
+                TransformId::FloatSplit => float_split(values, true),
+                TransformId::ByteSwap => swap_column(values, true, true),
+                TransformId::ZigZag => zigzag(values, true),
+                TransformId::Xor => xor(values, true),
+                TransformId::ByteSplit => byte_split(values, true),
+                TransformId::Rle => rle(values, true)?,
+            }
+            Ok(())
+        }
+
+        fn inverse(&self, values: &mut ColumnData) -> Result<()> {
+            match self {
+                TransformId::Identity => {}
+                TransformId::Delta => values.delta_decode(),
+                TransformId::FloatSplit => float_split(values, false),
+                TransformId::ByteSwap => swap_column(values, true, true),
+                TransformId::ZigZag => zigzag(values, false),
+                TransformId::Xor => xor(values, false),
+                TransformId::ByteSplit => byte_split(values, false),
+                TransformId::Rle => rle(values, false)?,
+            }
+            Ok(())
         }
     }
 
-    impl Codec for LZ4RawCodec {
-        fn decompress(
+    impl<T: Transform> Transform for [T] {
+        fn forward(&self, values: &mut ColumnData) -> Result<()> {
+            self.iter().try_for_each(|t| t.forward(values))
+        }
+
+        fn inverse(&self, values: &mut ColumnData) -> Result<()> {
+            self.iter().rev().try_for_each(|t| t.inverse(values))
+        }
+    }
+
+    macro_rules! zigzag_in_place {
+        ($values:expr, $signed:ty, $unsigned:ty, $forward:expr) => {{
+            let shift = <$signed>::BITS - 1;
+            for v in $values.iter_mut() {
+                *v = if $forward {
+                    (*v << 1) ^ (*v >> shift)
+                } else {
+                    ((*v as $unsigned >> 1) as $signed) ^ -(*v & 1)
+                };
+            }
+        }};
+    }
+
+    /// Zigzag encodes or decodes the signed integers of `values`; other columns are
+    /// left unchanged.
+    fn zigzag(values: &mut ColumnData, forward: bool) {
+        match values {
+            ColumnData::VecI8(x) => zigzag_in_place!(x, i8, u8, forward),
+            ColumnData::VecI16(x) => zigzag_in_place!(x, i16, u16, forward),
+            ColumnData::VecI32(x) => zigzag_in_place!(x, i32, u32, forward),
+            ColumnData::VecI64(x) => zigzag_in_place!(x, i64, u64, forward),
+            // encoded in 24 bits, held sign extended like any i24
+            ColumnData::VecI24(x) => {
+                for v in x.iter_mut() {
+                    *v = if forward {
+                        (((*v << 1) ^ (*v >> 23)) << 8) >> 8
+                    } else {
+                        let bits = *v as u32 & 0x00FF_FFFF;
+                        ((bits >> 1) as i32) ^ -((bits & 1) as i32)
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    macro_rules! xor_in_place {
+        ($values:expr, $forward:expr) => {{
+            if $forward {
+                for i in (1..$values.len()).rev() {
+                    $values[i] ^= $values[i - 1];
+                }
+            } else {
+                for i in 1..$values.len() {
+                    $values[i] ^= $values[i - 1];
+                }
+            }
+        }};
+    }
+
+    /// Replaces every value after the first with its XOR with the previous value, or
+    /// reverses this. Floats are XORed by their bits.
+    fn xor(values: &mut ColumnData, forward: bool) {
+        match values {
+            ColumnData::VecU8(x) => xor_in_place!(x, forward),
+            ColumnData::VecU16(x) => xor_in_place!(x, forward),
+            ColumnData::VecU32(x) | ColumnData::VecU24(x) => xor_in_place!(x, forward),
+            ColumnData::VecU64(x) => xor_in_place!(x, forward),
+            ColumnData::VecI8(x) => xor_in_place!(x, forward),
+            ColumnData::VecI16(x) => xor_in_place!(x, forward),
+            ColumnData::VecI32(x) | ColumnData::VecI24(x) => xor_in_place!(x, forward),
+            ColumnData::VecI64(x) => xor_in_place!(x, forward),
+            ColumnData::VecF32(x) => {
+                let mut bits: Vec<u32> = x.iter().map(|v| v.to_bits()).collect();
+                xor_in_place!(bits, forward);
+                *x = bits.into_iter().map(f32::from_bits).collect();
+            }
+            ColumnData::VecF64(x) => {
+                let mut bits: Vec<u64> = x.iter().map(|v| v.to_bits()).collect();
+                xor_in_place!(bits, forward);
+                *x = bits.into_iter().map(f64::from_bits).collect();
+            }
+        }
+    }
+
+    macro_rules! byte_split_in_place {
+        ($values:expr, $ty:ty, $forward:expr) => {{
+            let width = std::mem::size_of::<$ty>();
+            let count = $values.len();
+            let bytes: Vec<u8> = $values.iter().flat_map(|v| v.to_le_bytes()).collect();
+            let mut moved = vec![0u8; bytes.len()];
+            for i in 0..count {
+                for k in 0..width {
+                    if $forward {
+                        moved[k * count + i] = bytes[i * width + k];
+                    } else {
+                        moved[i * width + k] = bytes[k * count + i];
+                    }
+                }
+            }
+            *$values = moved
+                .chunks_exact(width)
+                .map(|c| <$ty>::from_le_bytes(c.try_into().unwrap()))
+                .collect();
+        }};
+    }
+
+    /// Regroups the bytes of the values of `values` by significance, or reverses this.
+    /// Single-byte and 24-bit columns are left unchanged.
+    fn byte_split(values: &mut ColumnData, forward: bool) {
+        match values {
+            ColumnData::VecU16(x) => byte_split_in_place!(x, u16, forward),
+            ColumnData::VecU32(x) => byte_split_in_place!(x, u32, forward),
+            ColumnData::VecU64(x) => byte_split_in_place!(x, u64, forward),
+            ColumnData::VecI16(x) => byte_split_in_place!(x, i16, forward),
+            ColumnData::VecI32(x) => byte_split_in_place!(x, i32, forward),
+            ColumnData::VecI64(x) => byte_split_in_place!(x, i64, forward),
+            ColumnData::VecF32(x) => byte_split_in_place!(x, f32, forward),
+            ColumnData::VecF64(x) => byte_split_in_place!(x, f64, forward),
+            _ => {}
+        }
+    }
+
+    /// Replaces every run of equal values by the value followed by the length of the
+    /// run, as produced by `run`, splitting runs longer than `max_run`.
+    fn rle_encode<T: Copy + PartialEq>(
+        values: &[T],
+        max_run: u64,
+        run: impl Fn(u64) -> T,
+    ) -> Vec<T> {
+        let mut encoded = Vec::new();
+        let mut start = 0;
+        while start < values.len() {
+            let mut len = 1;
+            while start + len < values.len()
+                && (len as u64) < max_run
+                && values[start + len] == values[start]
+            {
+                len += 1;
+            }
+            encoded.push(values[start]);
+            encoded.push(run(len as u64));
+            start += len;
+        }
+        encoded
+    }
+
+    /// Reverses [`rle_encode`], reading the length of every run with `run`.
+    fn rle_decode<T: Copy>(values: &[T], run: impl Fn(T) -> u64) -> Result<Vec<T>> {
+        if values.len() % 2 != 0 {
+            return Err(eof_err!("RLE values end inside a run"));
+        }
+        let mut decoded = Vec::with_capacity(values.len());
+        for pair in values.chunks_exact(2) {
+            match run(pair[1]) {
+                0 => return Err(general_err!("RLE run of length 0")),
+                len => decoded.extend(std::iter::repeat(pair[0]).take(len as usize)),
+            }
+        }
+        Ok(decoded)
+    }
+
+    macro_rules! rle_in_place {
+        ($values:expr, $forward:expr, $unsigned:ty) => {{
+            let max_run = (<$unsigned>::MAX as u64).min(u32::MAX as u64);
+            rle_in_place!($values, $forward, $unsigned, max_run)
+        }};
+        ($values:expr, $forward:expr, $unsigned:ty, $max_run:expr) => {{
+            *$values = if $forward {
+                rle_encode($values, $max_run, |n| n as $unsigned as _)
+            } else {
+                rle_decode($values, |v| v as $unsigned as u64)?
+            };
+        }};
+    }
+
+    /// Longest run of 24-bit values, whose lengths are held in 24 bits.
+    const MAX_RUN_24: u64 = 0x00FF_FFFF;
+
+    /// Longest run of `f32` values, whose lengths are held as subnormal bits so that
+    /// they never read as NaN.
+    const MAX_RUN_F32: u64 = 0x007F_FFFF;
+
+    /// Run-length encodes `values`, or reverses this. Run lengths are held in values
+    /// of the column's type, reinterpreting their bits as unsigned. Floats are
+    /// compared and held by their bits.
+    fn rle(values: &mut ColumnData, forward: bool) -> Result<()> {
+        match values {
+            ColumnData::VecU8(x) => rle_in_place!(x, forward, u8),
+            ColumnData::VecU16(x) => rle_in_place!(x, forward, u16),
+            ColumnData::VecU32(x) => rle_in_place!(x, forward, u32),
+            ColumnData::VecU64(x) => rle_in_place!(x, forward, u64),
+            ColumnData::VecI8(x) => rle_in_place!(x, forward, u8),
+            ColumnData::VecI16(x) => rle_in_place!(x, forward, u16),
+            ColumnData::VecI32(x) => rle_in_place!(x, forward, u32),
+            ColumnData::VecI64(x) => rle_in_place!(x, forward, u64),
+            ColumnData::VecU24(x) => rle_in_place!(x, forward, u32, MAX_RUN_24),
+            // held sign extended like any i24
+            ColumnData::VecI24(x) => {
+                *x = if forward {
+                    rle_encode(x, MAX_RUN_24, |n| ((n as i32) << 8) >> 8)
+                } else {
+                    rle_decode(x, |v| (v as u32 & 0x00FF_FFFF) as u64)?
+                };
+            }
+            ColumnData::VecF32(x) => {
+                let mut bits: Vec<u32> = x.iter().map(|v| v.to_bits()).collect();
+                rle_in_place!(&mut bits, forward, u32, MAX_RUN_F32);
+                *x = bits.into_iter().map(f32::from_bits).collect();
+            }
+            ColumnData::VecF64(x) => {
+                let mut bits: Vec<u64> = x.iter().map(|v| v.to_bits()).collect();
+                rle_in_place!(&mut bits, forward, u64);
+                *x = bits.into_iter().map(f64::from_bits).collect();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a mask of the low `len` bits, for `len` from 1 to 64.
+    fn low_mask(len: u32) -> u64 {
+        u64::MAX >> (64 - len)
+    }
+
+    /// Splits the float columns of `values` into their sign bits, then exponents, then
+    /// mantissas, packed into the bits of as many values as before, or reverses this.
+    /// Other columns are left unchanged.
+    fn float_split(values: &mut ColumnData, forward: bool) {
+        let convert = if forward { split_components } else { join_components };
+        match values {
+            ColumnData::VecF32(x) => {
+                let bits: Vec<u64> = x.iter().map(|v| v.to_bits() as u64).collect();
+                let bits = convert(&bits, 23, 8, 32);
+                *x = bits.into_iter().map(|b| f32::from_bits(b as u32)).collect();
+            }
+            ColumnData::VecF64(x) => {
+                let bits: Vec<u64> = x.iter().map(|v| v.to_bits()).collect();
+                let bits = convert(&bits, 52, 11, 64);
+                *x = bits.into_iter().map(f64::from_bits).collect();
+            }
+            _ => {}
+        }
+    }
+
+    /// Packs the sign bits, exponents and mantissas of the `width`-bit floats `bits`
+    /// one stream after another into `width`-bit words, least significant bit first.
+    fn split_components(
+        bits: &[u64],
+        mantissa_bits: u32,
+        exponent_bits: u32,
+        width: u32,
+    ) -> Vec<u64> {
+        let mut words = Vec::with_capacity(bits.len());
+        let (mut acc, mut acc_bits) = (0u128, 0);
+        let mut push = |value: u64, len: u32| {
+            acc |= (value as u128) << acc_bits;
+            acc_bits += len;
+            if acc_bits >= width {
+                words.push(acc as u64 & low_mask(width));
+                acc >>= width;
+                acc_bits -= width;
+            }
+        };
+        bits.iter().for_each(|b| push(b >> (width - 1), 1));
+        let exponent_mask = low_mask(exponent_bits);
+        bits.iter()
+            .for_each(|b| push((b >> mantissa_bits) & exponent_mask, exponent_bits));
+        let mantissa_mask = low_mask(mantissa_bits);
+        bits.iter().for_each(|b| push(b & mantissa_mask, mantissa_bits));
+        words
+    }
+
+    /// Reverses [`split_components`].
+    fn join_components(
+        words: &[u64],
+        mantissa_bits: u32,
+        exponent_bits: u32,
+        width: u32,
+    ) -> Vec<u64> {
+        let mut next = words.iter();
+        let (mut acc, mut acc_bits) = (0u128, 0);
+        let mut read = |len: u32| {
+            if acc_bits < len {
+                acc |= (next.next().copied().unwrap_or(0) as u128) << acc_bits;
+                acc_bits += width;
+            }
+            let value = acc as u64 & low_mask(len);
+            acc >>= len;
+            acc_bits -= len;
+            value
+        };
+        let signs: Vec<u64> = words.iter().map(|_| read(1)).collect();
+        let exponents: Vec<u64> = words.iter().map(|_| read(exponent_bits)).collect();
+        signs
+            .into_iter()
+            .zip(exponents)
+            .map(|(sign, exponent)| {
+                (sign << (width - 1)) | (exponent << mantissa_bits) | read(mantissa_bits)
+            })
+            .collect()
+    }
+
+    /// Length of the count of transformed values written when a transform changes it.
+    const VALUE_COUNT_LEN: usize = 8;
+
+    /// Codec wrapper that applies a sequence of [`Transform`]s to the values before
+    /// compressing them with another codec, and reverses them after decompressing.
+    ///
+    /// The first [`TransformId::Delta`] keeps differences that overflow the element
+    /// type in a wider type, see [`ColumnData::delta_encode_promoted`], and later
+    /// transforms apply to the wider values. When the transforms include a delta,
+    /// every buffer starts with one byte holding the width in bytes of the values the
+    /// inner codec compressed. When they include [`TransformId::Rle`], the number of
+    /// those values follows as a big-endian u64.
+    pub struct TransformCodec {
+        inner: Box<dyn Codec>,
+        transforms: Vec<TransformId>,
+    }
+
+    impl TransformCodec {
+        /// Creates a new codec applying `transforms`, in order, to the input of `inner`.
+        pub fn new(inner: Box<dyn Codec>, transforms: Vec<TransformId>) -> Self {
+            Self { inner, transforms }
+        }
+
+        /// Returns whether a transform changes the number of values, which is then
+        /// written ahead of the compressed values.
+        fn counts_values(&self) -> bool {
+            self.transforms.contains(&TransformId::Rle)
+        }
+
+        /// Returns the index of the delta transform that may widen the values.
+        fn first_delta(&self) -> Option<usize> {
+            self.transforms
+                .iter()
+                .position(|&t| t == TransformId::Delta)
+        }
+
+        /// Returns whether every transform rewrites each value on its own and writes
+        /// no header, so that frames and blocks of the inner codec stay independent.
+        fn elementwise(&self) -> bool {
+            self.transforms.iter().all(|t| {
+                matches!(
+                    t,
+                    TransformId::Identity | TransformId::ByteSwap | TransformId::ZigZag
+                )
+            })
+        }
+
+        /// Applies the transforms to a copy of `values`, appending the residual width
+        /// byte and the value count to `output_buf` where the transforms need them.
+        fn encode(
+            &self,
+            values: &ColumnData,
+            output_buf: &mut Vec<u8>,
+        ) -> Result<ColumnData> {
+            let mut encoded = values.clone();
+            let first_delta = self.first_delta();
+            for (i, transform) in self.transforms.iter().enumerate() {
+                if Some(i) == first_delta {
+                    encoded.delta_encode_promoted();
+                } else {
+                    transform.forward(&mut encoded)?;
+                }
+            }
+            if first_delta.is_some() {
+                output_buf.push(encoded.type_width() as u8);
+            }
+            if self.counts_values() {
+                output_buf.extend_from_slice(&(encoded.len() as u64).to_be_bytes());
+            }
+            Ok(encoded)
+        }
+
+        /// Decompresses `input_buf` with the inner codec and reverses the transforms,
+        /// appending the values to `output`.
+        fn decode(
             &mut self,
             input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
+            output: &mut ColumnData,
             uncompress_size: Option<usize>,
         ) -> Result<usize> {
-            let mut output_buf = Vec::new();
-
-            let offset = output_buf.len();
-            let required_len = match uncompress_size {
-                Some(uncompress_size) => uncompress_size,
-                None => {
-                    return Err(ParquetError::General(
-                        "LZ4RawCodec unsupported without uncompress_size".into(),
-                    ))
+            let first_delta = self.first_delta();
+            let (mut decoded, input_buf, size) = match first_delta {
+                Some(_) => {
+                    let (width, input_buf) = split_residual_width(input_buf)?;
+                    let (residuals, size) =
+                        delta_residuals(width, output, uncompress_size)?;
+                    (residuals, input_buf, size)
                 }
+                None => (output.slice(0, 0), input_buf, uncompress_size),
             };
-            output_buf.resize(offset + required_len, 0);
-            let ret = match lz4::block::decompress_to_buffer(
-                input_buf,
-                Some(required_len.try_into().unwrap()),
-                &mut output_buf[offset..],
-            ) {
-                Ok(n) => {
-                    if n != required_len {
-                        return Err(ParquetError::General(
-                            "LZ4RawCodec uncompress_size is not the expected one".into(),
-                        ));
-                    }
-                    Ok(n)
+            let (input_buf, size) = match self.counts_values() {
+                true if input_buf.len() < VALUE_COUNT_LEN => {
+                    return Err(eof_err!("Missing transformed value count"))
                 }
-                Err(e) => Err(e.into()),
+                true => {
+                    let (count, input_buf) = input_buf.split_at(VALUE_COUNT_LEN);
+                    let count = u64::from_be_bytes(count.try_into().unwrap());
+                    let size = checked_size_hint(
+                        self.inner.as_ref(),
+                        count,
+                        decoded.type_width(),
+                        input_buf.len(),
+                    )?;
+                    (input_buf, size)
+                }
+                false => (input_buf, size),
             };
+            let n = self.inner.decompress(input_buf, &mut decoded, size)?;
+            for (i, transform) in self.transforms.iter().enumerate().rev() {
+                transform.inverse(&mut decoded)?;
+                if Some(i) == first_delta {
+                    let mut narrowed = output.slice(0, 0);
+                    narrowed.extend_narrowed(&decoded)?;
+                    decoded = narrowed;
+                }
+            }
+            output.extend_from(&decoded)?;
+            Ok(n)
+        }
+    }
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+    impl Codec for TransformCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            self.decode(input_buf, output_buf_columndata, uncompress_size)
+        }
 
-            ret
+        fn decompress_with_bytes(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<Vec<u8>> {
+            decompress_typed_with_bytes(
+                self,
+                input_buf,
+                output_buf_columndata,
+                uncompress_size,
+            )
         }
 
         fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+            let transformed = self.encode(input_buf_columndata, output_buf)?;
+            self.inner.compress(&transformed, output_buf)
+        }
 
+        fn compress_within(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+            budget: Duration,
+        ) -> Result<usize> {
             let offset = output_buf.len();
-            let required_len = lz4::block::compress_bound(input_buf.len())?;
-            output_buf.resize(offset + required_len, 0);
-            match lz4::block::compress_to_buffer(
-                &input_buf,
-                None,
-                false,
-                &mut output_buf[offset..],
-            ) {
-                Ok(n) => {
-                    output_buf.truncate(offset + n);
-                    Ok(())
-                }
-                Err(e) => Err(e.into()),
+            let transformed = self.encode(input_buf_columndata, output_buf)?;
+            let header = output_buf.len() - offset;
+            Ok(header + self.inner.compress_within(&transformed, output_buf, budget)?)
+        }
+
+        forward_to_inner!(
+            compress_stream,
+            decompress_stream,
+            save_state,
+            restore_state,
+            supports_type,
+            last_was_stored,
+            max_input_len,
+            last_was_truncated,
+            last_skipped_frames,
+            max_expansion_ratio,
+            estimate_compress_duration,
+            last_element_count,
+            is_lossless,
+            backend_version,
+            name,
+            reset,
+        );
+
+        fn framing_overhead(&self) -> usize {
+            let width = self.first_delta().map_or(0, |_| 1);
+            let count = match self.counts_values() {
+                true => VALUE_COUNT_LEN,
+                false => 0,
+            };
+            width + count + self.inner.framing_overhead()
+        }
+
+        /// Frames can be merged when the transforms are [`Self::elementwise`].
+        fn concat_frames(&self, frames: &[Vec<u8>]) -> Result<Vec<u8>> {
+            match self.elementwise() {
+                true => self.inner.concat_frames(frames),
+                false => Err(nyi_err!("concat_frames is not supported by this codec")),
+            }
+        }
+
+        /// The bytes are passed through only when every transform is
+        /// [`TransformId::Identity`]; other transforms rewrite them.
+        fn decompress_view(
+            &mut self,
+            input_buf: &[u8],
+            uncompress_size: Option<usize>,
+        ) -> Result<&[u8]> {
+            match self.transforms.iter().all(|&t| t == TransformId::Identity) {
+                true => self.inner.decompress_view(input_buf, uncompress_size),
+                false => Err(nyi_err!("decompress_view is not supported by this codec")),
+            }
+        }
+
+        fn supports_seek(&self) -> bool {
+            self.elementwise() && self.inner.supports_seek()
+        }
+
+        fn decompress_block(
+            &mut self,
+            input_buf: &[u8],
+            block_index: usize,
+            output_buf_columndata: &mut ColumnData,
+            uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            if !self.elementwise() {
+                return Err(nyi_err!(
+                    "{} does not support decompressing single blocks",
+                    self.name()
+                ));
             }
+            let mut decoded = output_buf_columndata.slice(0, 0);
+            let n = self.inner.decompress_block(
+                input_buf,
+                block_index,
+                &mut decoded,
+                uncompress_size,
+            )?;
+            self.transforms.inverse(&mut decoded)?;
+            output_buf_columndata.extend_from(&decoded)?;
+            Ok(n)
+        }
+
+        fn config_fingerprint(&self) -> u64 {
+            let ids: Vec<u8> = self.transforms.iter().map(|&t| t as u8).collect();
+            fingerprint(&("Transform", ids, self.inner.config_fingerprint()))
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(Self::new(self.inner.clone_codec(), self.transforms.clone()))
         }
     }
 }
-#[cfg(any(feature = "lz4", test))]
-pub use lz4_raw_codec::*;
+pub use transform::*;
 
-#[cfg(any(feature = "lz4", test))]
-mod lz4_hadoop_codec {
-    use crate::compression::lz4_codec::LZ4Codec;
-    use crate::compression::lz4_raw_codec::LZ4RawCodec;
-    use crate::compression::Codec;
+mod scheme {
+    use crate::compression::{
+        create_codec, Codec, CodecOptions, CodecType, TransformCodec,
+    };
     use crate::errors::{ParquetError, Result};
-    use std::io;
+    use crate::format::CompressionCodec;
 
     // enum for columnar data
     use crate::data_type::ColumnData;
 
-    /// Size of u32 type.
-    const SIZE_U32: usize = std::mem::size_of::<u32>();
-
-    /// Length of the LZ4_HADOOP prefix.
-    const PREFIX_LEN: usize = SIZE_U32 * 2;
-
-    /// Codec for LZ4 Hadoop compression algorithm.
-    pub struct LZ4HadoopCodec {
-        /// Whether or not to fallback to other LZ4 implementations on error.
-        /// Fallback is done to be backward compatible with older versions of this
-        /// library and older versions parquet-cpp.
-        backward_compatible_lz4: bool,
+    /// Length of the scheme header: the transform id, then the codec id.
+    const SCHEME_HEADER_LEN: usize = 2;
+
+    /// Transform applied to the values before the general codec of a scheme, see
+    /// [`compress_scheme`]. The discriminant is the id written in the scheme header.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum TransformId {
+        /// Values are compressed as they are.
+        Identity = 0,
+        /// Integer values are replaced by their differences, as by `DeltaCodec`.
+        Delta = 1,
+        /// Float values are split into sign, exponent and mantissa streams, packed
+        /// into as many values as before.
+        FloatSplit = 2,
+        /// Values are byte-swapped to little-endian.
+        ByteSwap = 3,
+        /// Signed integers are zigzag encoded, so that small negative values become
+        /// small non-negative ones.
+        ZigZag = 4,
+        /// Values are replaced by their bitwise XOR with the previous value.
+        Xor = 5,
+        /// The bytes of the values are regrouped by significance, all first bytes
+        /// before all second bytes and so on.
+        ByteSplit = 6,
+        /// Runs of equal values are replaced by the value followed by the run length.
+        Rle = 7,
     }
 
-    impl LZ4HadoopCodec {
-        /// Creates new LZ4 Hadoop compression codec.
-        pub(crate) fn new(backward_compatible_lz4: bool) -> Self {
-            Self {
-                backward_compatible_lz4,
+    impl TransformId {
+        /// Returns the transform with header id `id`, or `None` for unknown ids.
+        pub fn from_id(id: u8) -> Option<TransformId> {
+            match id {
+                0 => Some(TransformId::Identity),
+                1 => Some(TransformId::Delta),
+                2 => Some(TransformId::FloatSplit),
+                3 => Some(TransformId::ByteSwap),
+                4 => Some(TransformId::ZigZag),
+                5 => Some(TransformId::Xor),
+                6 => Some(TransformId::ByteSplit),
+                7 => Some(TransformId::Rle),
+                _ => None,
             }
         }
+
+        /// Wraps `codec` so that it applies this transform.
+        fn wrap(self, codec: Box<dyn Codec>) -> Box<dyn Codec> {
+            Box::new(TransformCodec::new(codec, vec![self]))
+        }
     }
 
-    /// Try to decompress the buffer as if it was compressed with the Hadoop Lz4Codec.
-    /// Adapted from pola-rs [compression.rs:try_decompress_hadoop](https://pola-rs.github.io/polars/src/parquet2/compression.rs.html#225)
-    /// Translated from the apache arrow c++ function [TryDecompressHadoop](https://github.com/apache/arrow/blob/bf18e6e4b5bb6180706b1ba0d597a65a4ce5ca48/cpp/src/arrow/util/compression_lz4.cc#L474).
-    /// Returns error if decompression failed.
-    fn try_decompress_hadoop(
-        input_buf: &[u8],
-        output_buf: &mut [u8],
-    ) -> io::Result<usize> {
-        // Parquet files written with the Hadoop Lz4Codec use their own framing.
-        // The input buffer can contain an arbitrary number of "frames", each
-        // with the following structure:
-        // - bytes 0..3: big-endian uint32_t representing the frame decompressed size
-        // - bytes 4..7: big-endian uint32_t representing the frame compressed size
-        // - bytes 8...: frame compressed data
-        //
-        // The Hadoop Lz4Codec source code can be found here:
-        // https://github.com/apache/hadoop/blob/trunk/hadoop-mapreduce-project/hadoop-mapreduce-client/hadoop-mapreduce-client-nativetask/src/main/native/src/codec/Lz4Codec.cc
-        let mut input_len = input_buf.len();
-        let mut input = input_buf;
-        let mut read_bytes = 0;
-        let mut output_len = output_buf.len();
-        let mut output: &mut [u8] = output_buf;
-        while input_len >= PREFIX_LEN {
-            let mut bytes = [0; SIZE_U32];
-            bytes.copy_from_slice(&input[0..4]);
-            let expected_decompressed_size = u32::from_be_bytes(bytes);
-            let mut bytes = [0; SIZE_U32];
-            bytes.copy_from_slice(&input[4..8]);
-            let expected_compressed_size = u32::from_be_bytes(bytes);
-            input = &input[PREFIX_LEN..];
-            input_len -= PREFIX_LEN;
+    /// Creates the codec applying `transform` before the general codec `codec_type`.
+    fn scheme_codec(
+        transform: TransformId,
+        codec_type: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Box<dyn Codec>> {
+        let codec = create_codec(codec_type, options)?
+            .ok_or_else(|| general_err!("Compression schemes require a compression codec"))?;
+        Ok(transform.wrap(codec))
+    }
 
-            if input_len < expected_compressed_size as usize {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Not enough bytes for Hadoop frame",
-                ));
-            }
+    /// Compresses `data` with `transform` followed by the general codec `codec`,
+    /// behind a 2-byte header naming both, so that [`decompress_scheme`] can decode
+    /// any pairing without being told which was used.
+    ///
+    /// The codec id is the codec's Parquet `CompressionCodec` number. The transform is
+    /// applied by a [`TransformCodec`], so its output, including any header the
+    /// transform needs, follows the scheme header.
+    pub fn compress_scheme(
+        transform: TransformId,
+        codec: CodecType,
+        data: &ColumnData,
+        options: &CodecOptions,
+    ) -> Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(SCHEME_HEADER_LEN);
+        output.push(transform as u8);
+        output.push(CompressionCodec::from(codec).0 as u8);
+        scheme_codec(transform, codec, options)?.compress(data, &mut output)?;
+        Ok(output)
+    }
 
-            if output_len < expected_decompressed_size as usize {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Not enough bytes to hold advertised output",
-                ));
-            }
-            let decompressed_size = lz4::block::decompress_to_buffer(
-                &input[..expected_compressed_size as usize],
-                Some(output_len as i32),
-                output,
-            )?;
-            if decompressed_size != expected_decompressed_size as usize {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Unexpected decompressed size",
-                ));
-            }
-            input_len -= expected_compressed_size as usize;
-            output_len -= expected_decompressed_size as usize;
-            read_bytes += expected_decompressed_size as usize;
-            if input_len > expected_compressed_size as usize {
-                input = &input[expected_compressed_size as usize..];
-                output = &mut output[expected_decompressed_size as usize..];
-            } else {
-                break;
-            }
-        }
-        if input_len == 0 {
-            Ok(read_bytes)
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Not all input are consumed",
-            ))
+    /// Decompresses a buffer written by [`compress_scheme`] into values of type
+    /// `typename`, reading the transform and the codec from its header.
+    ///
+    /// `options` must match the ones used to compress.
+    pub fn decompress_scheme(
+        input_buf: &[u8],
+        typename: &str,
+        uncompress_size: Option<usize>,
+        options: &CodecOptions,
+    ) -> Result<ColumnData> {
+        if input_buf.len() < SCHEME_HEADER_LEN {
+            return Err(eof_err!(
+                "scheme header needs {} bytes, got {}",
+                SCHEME_HEADER_LEN,
+                input_buf.len()
+            ));
         }
+        let transform = TransformId::from_id(input_buf[0])
+            .ok_or_else(|| general_err!("Unknown scheme transform id {}", input_buf[0]))?;
+        let codec = CodecType::try_from(CompressionCodec(input_buf[1] as i32))?;
+        let mut values = ColumnData::from_typename(typename)
+            .ok_or_else(|| general_err!("Unsupported scheme value type {}", typename))?;
+
+        scheme_codec(transform, codec, options)?.decompress(
+            &input_buf[SCHEME_HEADER_LEN..],
+            &mut values,
+            uncompress_size,
+        )?;
+        Ok(values)
     }
+}
+pub use scheme::*;
 
-    impl Codec for LZ4HadoopCodec {
-        fn decompress(
-            &mut self,
-            input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            uncompress_size: Option<usize>,
-        ) -> Result<usize> {
+mod parquet_column {
+    use std::fs::File;
+    use std::path::Path;
 
-            let mut output_buf = Vec::new();
+    use crate::compression::{create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+    use crate::file::reader::{FileReader, SerializedFileReader};
+    use crate::record::Field;
+    use crate::schema::types::Type as SchemaType;
 
-            let output_len = output_buf.len();
-            let required_len = match uncompress_size {
-                Some(n) => n,
-                None => {
-                    return Err(ParquetError::General(
-                        "LZ4HadoopCodec unsupported without uncompress_size".into(),
-                    ))
-                }
-            };
-            output_buf.resize(output_len + required_len, 0);
-            let ret = match try_decompress_hadoop(input_buf, &mut output_buf[output_len..]) {
-                Ok(n) => {
-                    if n != required_len {
-                        return Err(ParquetError::General(
-                            "LZ4HadoopCodec uncompress_size is not the expected one"
-                                .into(),
-                        ));
-                    }
-                    Ok(n)
-                }
-                Err(e) if !self.backward_compatible_lz4 => Err(e.into()),
-                // Fallback done to be backward compatible with older versions of this
-                // libray and older versions of parquet-cpp.
-                Err(_) => {
-                    // Truncate any inserted element before tryingg next algorithm.
-                    output_buf.truncate(output_len);
-                    match LZ4Codec::new().decompress(
-                        input_buf,
-                        output_buf_columndata,
-                        uncompress_size,
-                    ) {
-                        Ok(n) => Ok(n),
-                        Err(_) => {
-                            // Truncate any inserted element before tryingg next algorithm.
-                            output_buf.truncate(output_len);
-                            LZ4RawCodec::new().decompress(
-                                input_buf,
-                                output_buf_columndata,
-                                uncompress_size,
-                            )
-                        }
-                    }
-                }
-            };
+    // enum for columnar data
+    use crate::data_type::ColumnData;
+
+    /// Reads the top-level column `column` of the Parquet file at `path` and
+    /// compresses its values with `codec`.
+    ///
+    /// Returns the codec the values were compressed with and the compressed bytes.
+    /// Only numeric, boolean, date and timestamp columns without nulls can be
+    /// compressed; booleans become `u8` values of 0 or 1.
+    pub fn compress_parquet_column(
+        path: &Path,
+        column: &str,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<(CodecType, Vec<u8>)> {
+        let reader = SerializedFileReader::new(File::open(path)?)?;
+        let schema = reader.metadata().file_metadata().schema();
+        let field = schema
+            .get_fields()
+            .iter()
+            .find(|f| f.name() == column)
+            .cloned()
+            .ok_or_else(|| {
+                general_err!("Column {} not found in {}", column, path.display())
+            })?;
+        let projection = SchemaType::group_type_builder(schema.name())
+            .with_fields(&mut vec![field])
+            .build()?;
+
+        let mut values = None;
+        for row in reader.get_row_iter(Some(projection))? {
+            let (_, field) = row.get_column_iter().next().ok_or_else(|| {
+                general_err!("Row of {} has no column {}", path.display(), column)
+            })?;
+            push_field(&mut values, field, column)?;
+        }
+        let values = match values {
+            Some(values) => values,
+            None => return Err(general_err!("Column {} has no rows", column)),
+        };
+
+        let mut output = Vec::new();
+        let mut compressor = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("compress_parquet_column requires a compression codec")
+        })?;
+        compressor.compress(&values, &mut output)?;
+        Ok((codec, output))
+    }
 
-            output_buf_columndata.convert_from_u8(&output_buf);
+    /// Returns an empty column of the element type that holds `field`'s values.
+    fn empty_column(field: &Field, column: &str) -> Result<ColumnData> {
+        Ok(match field {
+            Field::Bool(_) | Field::UByte(_) => ColumnData::VecU8(Vec::new()),
+            Field::Byte(_) => ColumnData::VecI8(Vec::new()),
+            Field::Short(_) => ColumnData::VecI16(Vec::new()),
+            Field::Int(_) | Field::Date(_) => ColumnData::VecI32(Vec::new()),
+            Field::Long(_) | Field::TimestampMillis(_) | Field::TimestampMicros(_) => {
+                ColumnData::VecI64(Vec::new())
+            }
+            Field::UShort(_) => ColumnData::VecU16(Vec::new()),
+            Field::UInt(_) => ColumnData::VecU32(Vec::new()),
+            Field::ULong(_) => ColumnData::VecU64(Vec::new()),
+            Field::Float(_) => ColumnData::VecF32(Vec::new()),
+            Field::Double(_) => ColumnData::VecF64(Vec::new()),
+            _ => {
+                return Err(nyi_err!(
+                    "Cannot compress column {} holding {:?}",
+                    column,
+                    field
+                ))
+            }
+        })
+    }
 
-            ret
+    /// Appends `field` to `values`, starting a column of its type if there is none.
+    fn push_field(values: &mut Option<ColumnData>, field: &Field, column: &str) -> Result<()> {
+        let values = match values {
+            Some(values) => values,
+            None => values.insert(empty_column(field, column)?),
+        };
+        match (values, field) {
+            (ColumnData::VecU8(x), Field::Bool(v)) => x.push(*v as u8),
+            (ColumnData::VecU8(x), Field::UByte(v)) => x.push(*v),
+            (ColumnData::VecI8(x), Field::Byte(v)) => x.push(*v),
+            (ColumnData::VecI16(x), Field::Short(v)) => x.push(*v),
+            (ColumnData::VecI32(x), Field::Int(v) | Field::Date(v)) => x.push(*v),
+            (
+                ColumnData::VecI64(x),
+                Field::Long(v) | Field::TimestampMillis(v) | Field::TimestampMicros(v),
+            ) => x.push(*v),
+            (ColumnData::VecU16(x), Field::UShort(v)) => x.push(*v),
+            (ColumnData::VecU32(x), Field::UInt(v)) => x.push(*v),
+            (ColumnData::VecU64(x), Field::ULong(v)) => x.push(*v),
+            (ColumnData::VecF32(x), Field::Float(v)) => x.push(*v),
+            (ColumnData::VecF64(x), Field::Double(v)) => x.push(*v),
+            (values, field) => {
+                return Err(general_err!(
+                    "Column {} of {} values holds {:?}",
+                    column,
+                    values.typename(),
+                    field
+                ))
+            }
         }
+        Ok(())
+    }
+}
+pub use parquet_column::*;
 
-        fn compress(&mut self, input_buf_columndata: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
-            let mut input_buf = Vec::new();
-            input_buf_columndata.convert_to_u8(&mut input_buf);
+mod data_page {
+    use crate::compression::{create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
+    use crate::file::statistics::Statistics;
 
-            // Allocate memory to store the LZ4_HADOOP prefix.
-            let offset = output_buf.len();
-            output_buf.resize(offset + PREFIX_LEN, 0);
+    // enum for columnar data
+    use crate::data_type::ColumnData;
 
-            // Append LZ4_RAW compressed bytes after prefix.
-            LZ4RawCodec::new().compress(input_buf_columndata, output_buf)?;
+    /// Compressed values of a column with the statistics a Parquet data page header
+    /// carries for them, see [`compress_as_data_page`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DataPage {
+        /// The compressed values.
+        pub buf: Vec<u8>,
+        /// Size of the values before compression, in bytes.
+        pub uncompressed_size: usize,
+        /// Number of values in the page.
+        pub num_values: u32,
+        /// Minimum, maximum and null count of the values, typed as the Parquet
+        /// physical type that holds them.
+        pub statistics: Statistics,
+    }
 
-            // Prepend decompressed size and compressed size in big endian to be compatible
-            // with LZ4_HADOOP.
-            let output_buf = &mut output_buf[offset..];
-            let compressed_size = output_buf.len() - PREFIX_LEN;
-            let compressed_size = compressed_size as u32;
-            let uncompressed_size = input_buf.len() as u32;
-            output_buf[..SIZE_U32].copy_from_slice(&uncompressed_size.to_be_bytes());
-            output_buf[SIZE_U32..PREFIX_LEN].copy_from_slice(&compressed_size.to_be_bytes());
+    /// Returns the minimum and maximum of `values` in their own order, or `None` for
+    /// an empty column.
+    fn min_max<T: Copy + Ord>(values: &[T]) -> (Option<T>, Option<T>) {
+        (values.iter().min().copied(), values.iter().max().copied())
+    }
 
-            Ok(())
+    /// Returns the minimum and maximum of the floats in `values`, ignoring NaNs, with
+    /// a zero minimum written as `-0.0` and a zero maximum as `+0.0`, as the Parquet
+    /// format requires.
+    fn float_min_max<T: Copy + PartialOrd + Default + std::ops::Neg<Output = T>>(
+        values: impl Iterator<Item = T>,
+    ) -> (Option<T>, Option<T>) {
+        let zero = T::default();
+        let mut bounds: Option<(T, T)> = None;
+        // NaN is the only value that is unordered with itself
+        for v in values.filter(|v| v.partial_cmp(v).is_some()) {
+            bounds = Some(match bounds {
+                Some((min, max)) => (
+                    if v < min { v } else { min },
+                    if v > max { v } else { max },
+                ),
+                None => (v, v),
+            });
+        }
+        match bounds {
+            Some((min, max)) => (
+                Some(if min == zero { -zero } else { min }),
+                Some(if max == zero { zero } else { max }),
+            ),
+            None => (None, None),
         }
     }
-}
-#[cfg(any(feature = "lz4", test))]
-pub use lz4_hadoop_codec::*;
 
-#[cfg(any(feature = "q_compress", test))]
-mod qcom_codec {
-    use crate::compression::Codec;
-    use crate::errors::Result;
+    /// Returns the statistics of `values` typed as the Parquet physical type that
+    /// holds them: `INT32` for integers of up to 32 bits, `INT64` for 64-bit integers,
+    /// `FLOAT` and `DOUBLE` for floats.
+    ///
+    /// Unsigned values are ordered as unsigned and stored with the bits of the
+    /// physical type, as Parquet does for its `UINT_32` and `UINT_64` logical types.
+    fn statistics(values: &ColumnData) -> Statistics {
+        fn int32<T: Copy + Ord>(values: &[T], to_i32: fn(T) -> i32) -> Statistics {
+            let (min, max) = min_max(values);
+            Statistics::int32(min.map(to_i32), max.map(to_i32), None, 0, false)
+        }
+        match values {
+            ColumnData::VecU8(x) => int32(x, |v| v as i32),
+            ColumnData::VecU16(x) => int32(x, |v| v as i32),
+            ColumnData::VecU32(x) | ColumnData::VecU24(x) => int32(x, |v| v as i32),
+            ColumnData::VecI8(x) => int32(x, |v| v as i32),
+            ColumnData::VecI16(x) => int32(x, |v| v as i32),
+            ColumnData::VecI32(x) | ColumnData::VecI24(x) => int32(x, |v| v),
+            ColumnData::VecU64(x) => {
+                let (min, max) = min_max(x);
+                let (min, max) = (min.map(|v| v as i64), max.map(|v| v as i64));
+                Statistics::int64(min, max, None, 0, false)
+            }
+            ColumnData::VecI64(x) => {
+                let (min, max) = min_max(x);
+                Statistics::int64(min, max, None, 0, false)
+            }
+            ColumnData::VecF32(x) => {
+                let (min, max) = float_min_max(x.iter().copied());
+                Statistics::float(min, max, None, 0, false)
+            }
+            ColumnData::VecF64(x) => {
+                let (min, max) = float_min_max(x.iter().copied());
+                Statistics::double(min, max, None, 0, false)
+            }
+        }
+    }
+
+    /// Compresses `values` with `codec` and computes the statistics of the Parquet
+    /// data page that holds them, a step towards writing the output as a Parquet
+    /// column chunk.
+    ///
+    /// Columns have no nulls, so the null count is always 0. Returns an error for
+    /// more values than a page can count.
+    pub fn compress_as_data_page(
+        values: &ColumnData,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<DataPage> {
+        let num_values = u32::try_from(values.len()).map_err(|_| {
+            general_err!("{} values do not fit in a data page", values.len())
+        })?;
+        let mut compressor = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("compress_as_data_page requires a compression codec")
+        })?;
+        let mut buf = Vec::new();
+        compressor.compress(values, &mut buf)?;
+        Ok(DataPage {
+            buf,
+            uncompressed_size: values.len() * values.type_width(),
+            num_values,
+            statistics: statistics(values),
+        })
+    }
+}
+pub use data_page::*;
+
+#[cfg(feature = "arrow")]
+mod record_batch {
+    use std::sync::Arc;
+
+    use arrow_array::cast::as_primitive_array;
+    use arrow_array::types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    };
+    use arrow_array::{
+        Array, ArrayRef, ArrowPrimitiveType, Float32Array, Float64Array, Int16Array,
+        Int32Array, Int64Array, Int8Array, RecordBatch, UInt16Array, UInt32Array,
+        UInt64Array, UInt8Array,
+    };
+    use arrow_schema::{DataType as ArrowDataType, SchemaRef};
+
+    use crate::compression::{create_codec, CodecOptions, CodecType};
+    use crate::errors::{ParquetError, Result};
 
-    use q_compress::{auto_compress, auto_decompress, DEFAULT_COMPRESSION_LEVEL};
-    
     // enum for columnar data
     use crate::data_type::ColumnData;
 
-    /// Codec for LZ4 Raw compression algorithm.
-    pub struct QComCodec {}
+    /// Compresses every column of `batch` with `codec`, returning each column's name
+    /// and compressed bytes in schema order.
+    ///
+    /// Integer and floating point columns without nulls are supported; any other
+    /// column is an error. Read the result back with [`decompress_record_batch`].
+    pub fn compress_record_batch(
+        batch: &RecordBatch,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut compressor = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("compress_record_batch requires a compression codec")
+        })?;
+        let schema = batch.schema();
+        let mut columns = Vec::with_capacity(batch.num_columns());
+        for (field, array) in schema.fields().iter().zip(batch.columns()) {
+            if array.null_count() > 0 {
+                return Err(nyi_err!(
+                    "Cannot compress arrow column {} with nulls",
+                    field.name()
+                ));
+            }
+            let values = array_to_column(field.name(), array.as_ref())?;
+            let mut output = Vec::new();
+            compressor.compress(&values, &mut output)?;
+            columns.push((field.name().clone(), output));
+        }
+        Ok(columns)
+    }
 
-    impl QComCodec {
-        /// Creates new LZ4 Raw compression codec.
-        pub(crate) fn new() -> Self {
-            Self {}
+    /// Rebuilds a batch of `schema` from the columns compressed with
+    /// [`compress_record_batch`], looking each field up by name in `columns`.
+    ///
+    /// `codec` must match the one used to compress.
+    pub fn decompress_record_batch(
+        columns: &[(String, Vec<u8>)],
+        schema: SchemaRef,
+        codec: CodecType,
+        options: &CodecOptions,
+    ) -> Result<RecordBatch> {
+        let mut decompressor = create_codec(codec, options)?.ok_or_else(|| {
+            general_err!("decompress_record_batch requires a compression codec")
+        })?;
+        let mut arrays = Vec::with_capacity(schema.fields().len());
+        for field in schema.fields() {
+            let (_, compressed) = columns
+                .iter()
+                .find(|(name, _)| name == field.name())
+                .ok_or_else(|| general_err!("Missing compressed column {}", field.name()))?;
+            let mut values = empty_column(field.name(), field.data_type())?;
+            decompressor.decompress(compressed, &mut values, None)?;
+            arrays.push(column_to_array(values));
         }
+        Ok(RecordBatch::try_new(schema, arrays)?)
     }
 
-    impl Codec for QComCodec {
-        fn decompress(
-            &mut self,
-            input_buf: &[u8],
-            output_buf_columndata: &mut ColumnData,
-            _uncompress_size: Option<usize>,
-        ) -> Result<usize> {
+    /// Copies the values of a primitive arrow array.
+    fn primitive_values<T: ArrowPrimitiveType>(array: &dyn Array) -> Vec<T::Native> {
+        as_primitive_array::<T>(array).values().to_vec()
+    }
 
-            match output_buf_columndata {
-                ColumnData::VecU8(_) | ColumnData::VecI8(_) => {
-                    panic!("Error: QCOM does not handle u8/i8 data");
-                },
-                ColumnData::VecU16(x) => {
-                    x.append( &mut auto_decompress::<u16>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecU32(x) => {
-                    x.append( &mut auto_decompress::<u32>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecU64(x) => {
-                    x.append( &mut auto_decompress::<u64>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecI16(x) => {
-                    x.append( &mut auto_decompress::<i16>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecI32(x) => {
-                    x.append( &mut auto_decompress::<i32>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecI64(x) => {
-                    x.append( &mut auto_decompress::<i64>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecF32(x) => {
-                    x.append( &mut auto_decompress::<f32>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
-                ColumnData::VecF64(x) => {
-                    x.append( &mut auto_decompress::<f64>(input_buf).expect("failed to decompress") );
-                    Ok(x.len())
-                },
+    /// Copies the values of the arrow column `name` into a column of the same type.
+    fn array_to_column(name: &str, array: &dyn Array) -> Result<ColumnData> {
+        Ok(match array.data_type() {
+            ArrowDataType::Int8 => ColumnData::VecI8(primitive_values::<Int8Type>(array)),
+            ArrowDataType::Int16 => ColumnData::VecI16(primitive_values::<Int16Type>(array)),
+            ArrowDataType::Int32 => ColumnData::VecI32(primitive_values::<Int32Type>(array)),
+            ArrowDataType::Int64 => ColumnData::VecI64(primitive_values::<Int64Type>(array)),
+            ArrowDataType::UInt8 => ColumnData::VecU8(primitive_values::<UInt8Type>(array)),
+            ArrowDataType::UInt16 => ColumnData::VecU16(primitive_values::<UInt16Type>(array)),
+            ArrowDataType::UInt32 => ColumnData::VecU32(primitive_values::<UInt32Type>(array)),
+            ArrowDataType::UInt64 => ColumnData::VecU64(primitive_values::<UInt64Type>(array)),
+            ArrowDataType::Float32 => {
+                ColumnData::VecF32(primitive_values::<Float32Type>(array))
             }
-        }
+            ArrowDataType::Float64 => {
+                ColumnData::VecF64(primitive_values::<Float64Type>(array))
+            }
+            other => {
+                return Err(nyi_err!(
+                    "Cannot compress arrow column {} of type {}",
+                    name,
+                    other
+                ))
+            }
+        })
+    }
 
-        fn compress(
-            &mut self, 
-            input_buf_columndata: &ColumnData, 
-            output_buf: &mut Vec<u8>,
-        ) -> Result<()> {
+    /// Returns an empty column of the element type of the arrow column `name`.
+    fn empty_column(name: &str, data_type: &ArrowDataType) -> Result<ColumnData> {
+        Ok(match data_type {
+            ArrowDataType::Int8 => ColumnData::VecI8(Vec::new()),
+            ArrowDataType::Int16 => ColumnData::VecI16(Vec::new()),
+            ArrowDataType::Int32 => ColumnData::VecI32(Vec::new()),
+            ArrowDataType::Int64 => ColumnData::VecI64(Vec::new()),
+            ArrowDataType::UInt8 => ColumnData::VecU8(Vec::new()),
+            ArrowDataType::UInt16 => ColumnData::VecU16(Vec::new()),
+            ArrowDataType::UInt32 => ColumnData::VecU32(Vec::new()),
+            ArrowDataType::UInt64 => ColumnData::VecU64(Vec::new()),
+            ArrowDataType::Float32 => ColumnData::VecF32(Vec::new()),
+            ArrowDataType::Float64 => ColumnData::VecF64(Vec::new()),
+            other => {
+                return Err(nyi_err!(
+                    "Cannot decompress arrow column {} of type {}",
+                    name,
+                    other
+                ))
+            }
+        })
+    }
 
-            match input_buf_columndata {
-                ColumnData::VecU8(_) | ColumnData::VecI8(_) => {
-                    panic!("Error: QCOM does not handle u8/i8 data");
-                },
-                ColumnData::VecU16(x) => {
-                    output_buf.append( &mut auto_compress::<u16>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecU32(x) => {
-                    output_buf.append( &mut auto_compress::<u32>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecU64(x) => {
-                    output_buf.append( &mut auto_compress::<u64>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecI16(x) => {
-                    output_buf.append( &mut auto_compress::<i16>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecI32(x) => {
-                    output_buf.append( &mut auto_compress::<i32>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecI64(x) => {
-                    output_buf.append( &mut auto_compress::<i64>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecF32(x) => {
-                    output_buf.append( &mut auto_compress::<f32>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
-                ColumnData::VecF64(x) => {
-                    output_buf.append( &mut auto_compress::<f64>(x, DEFAULT_COMPRESSION_LEVEL) );
-                },
+    /// Wraps the values of a column created by `empty_column` in an arrow array.
+    fn column_to_array(values: ColumnData) -> ArrayRef {
+        match values {
+            ColumnData::VecI8(x) => Arc::new(Int8Array::from(x)),
+            ColumnData::VecI16(x) => Arc::new(Int16Array::from(x)),
+            ColumnData::VecI32(x) => Arc::new(Int32Array::from(x)),
+            ColumnData::VecI64(x) => Arc::new(Int64Array::from(x)),
+            ColumnData::VecU8(x) => Arc::new(UInt8Array::from(x)),
+            ColumnData::VecU16(x) => Arc::new(UInt16Array::from(x)),
+            ColumnData::VecU32(x) => Arc::new(UInt32Array::from(x)),
+            ColumnData::VecU64(x) => Arc::new(UInt64Array::from(x)),
+            ColumnData::VecF32(x) => Arc::new(Float32Array::from(x)),
+            ColumnData::VecF64(x) => Arc::new(Float64Array::from(x)),
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => {
+                unreachable!("empty_column never creates 24-bit columns")
             }
-            Ok(())
         }
-
     }
 }
-#[cfg(any(feature = "q_compress", test))]
-pub use qcom_codec::*;
+#[cfg(feature = "arrow")]
+pub use record_batch::*;
 
 #[cfg(test)]
 mod tests {
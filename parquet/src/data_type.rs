@@ -30,7 +30,7 @@ use crate::errors::{ParquetError, Result};
 use crate::util::{bit_util::FromBytes, memory::ByteBufferPtr};
 
 // convert byte array
-use byteorder::{ByteOrder, BigEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
 
 // downcast datatypeconstraint to concrete types
 use std::any::Any;
@@ -1300,6 +1300,42 @@ impl DataTypeConstraint for f64 {
     fn as_any(&self) -> &dyn Any { self }
 }
 
+macro_rules! delta_encode_in_place {
+    ($values:expr) => {{
+        for i in (1..$values.len()).rev() {
+            $values[i] = $values[i].wrapping_sub($values[i - 1]);
+        }
+    }};
+}
+
+macro_rules! delta_decode_in_place {
+    ($values:expr) => {{
+        for i in 1..$values.len() {
+            $values[i] = $values[i].wrapping_add($values[i - 1]);
+        }
+    }};
+}
+
+/// Whether a difference between neighbouring `$values` lies outside `$min..=$max`.
+macro_rules! delta_overflows {
+    ($values:expr, $min:expr, $max:expr) => {
+        $values
+            .windows(2)
+            .any(|w| !($min as i64..=$max as i64).contains(&(w[1] as i64 - w[0] as i64)))
+    };
+}
+
+/// Element types of the `ColumnData` variants, in declaration order.
+const SUPPORTED_TYPENAMES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64", "i24", "u24",
+];
+
+/// Returns every element typename `ColumnData` can hold and convert to and from bytes,
+/// as returned by [`ColumnData::typename`], independent of any codec.
+pub fn supported_typenames() -> &'static [&'static str] {
+    &SUPPORTED_TYPENAMES
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ColumnData {
     VecU8 (Vec<u8>),
@@ -1312,26 +1348,211 @@ pub enum ColumnData {
     VecI64(Vec<i64>),
     VecF32(Vec<f32>),
     VecF64(Vec<f64>),
+    /// Signed 24-bit integers, held sign-extended in `i32` and serialized as 3 bytes.
+    VecI24(Vec<i32>),
+    /// Unsigned 24-bit integers, held in `u32` and serialized as 3 bytes.
+    VecU24(Vec<u32>),
+}
+
+/// Copies `input` as values of type `U`, or returns an error naming the expected and
+/// actual types of the first element that is not a `U`.
+fn downcast_values<U: DataTypeConstraint, T: DataTypeConstraint>(
+    input: &[T],
+) -> Result<Vec<U>> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            x.as_any().downcast_ref::<U>().copied().ok_or_else(|| {
+                general_err!(
+                    "Mixed element types: expected {}, found {} at index {}",
+                    U::default().typename(),
+                    x.typename(),
+                    i
+                )
+            })
+        })
+        .collect()
 }
 
 impl ColumnData {
-    pub fn new<T: DataTypeConstraint>(input: &Vec<T>) -> ColumnData {
-        match std::any::type_name::<T>() {
-            "u8" => ColumnData::VecU8(input.iter().map(|x| *x.as_any().downcast_ref::<u8>().expect("not u8 found")).collect::<Vec<_>>()),
-            "u16" => ColumnData::VecU16(input.iter().map(|x| *x.as_any().downcast_ref::<u16>().expect("not u16 found")).collect::<Vec<_>>()),
-            "u32" => ColumnData::VecU32(input.iter().map(|x| *x.as_any().downcast_ref::<u32>().expect("not u32 found")).collect::<Vec<_>>()),
-            "u64" => ColumnData::VecU64(input.iter().map(|x| *x.as_any().downcast_ref::<u64>().expect("not u64 found")).collect::<Vec<_>>()),
-            "i8" => ColumnData::VecI8(input.iter().map(|x| *x.as_any().downcast_ref::<i8>().expect("not i8 found")).collect::<Vec<_>>()),
-            "i16" => ColumnData::VecI16(input.iter().map(|x| *x.as_any().downcast_ref::<i16>().expect("not i16 found")).collect::<Vec<_>>()),
-            "i32" => ColumnData::VecI32(input.iter().map(|x| *x.as_any().downcast_ref::<i32>().expect("not i32 found")).collect::<Vec<_>>()),
-            "i64" => ColumnData::VecI64(input.iter().map(|x| *x.as_any().downcast_ref::<i64>().expect("not i64 found")).collect::<Vec<_>>()),
-            "f32" => ColumnData::VecF32(input.iter().map(|x| *x.as_any().downcast_ref::<f32>().expect("not f32 found")).collect::<Vec<_>>()),
-            "f64" => ColumnData::VecF64(input.iter().map(|x| *x.as_any().downcast_ref::<f64>().expect("not f64 found")).collect::<Vec<_>>()),
-            _ => panic!("Error: ColumnData::new with type {:?} is not acceptable.", std::any::type_name::<T>()),
-        }
-    }
-
-    pub fn convert_from_u8(&mut self, input: &Vec<u8>) {
+    /// Returns a column holding a copy of `input`.
+    ///
+    /// Panics if `T` is not one of the element types a column can hold; see
+    /// [`ColumnData::try_new`] for a fallible version.
+    pub fn new<T: DataTypeConstraint>(input: &[T]) -> ColumnData {
+        match Self::try_new(input) {
+            Ok(column) => column,
+            Err(e) => panic!("Error: ColumnData::new: {}", e),
+        }
+    }
+
+    /// Returns a column holding a copy of `input`, or an error naming `T` if it is not
+    /// one of the element types a column can hold, e.g. a caller's own implementation
+    /// of [`DataTypeConstraint`].
+    ///
+    /// The column takes the type the first element reports through `typename()`, so
+    /// a caller's enum or newtype over the builtin types is accepted as long as its
+    /// `as_any` returns the builtin value. An element of another type is an error
+    /// naming the expected and actual types, rather than a panic.
+    pub fn try_new<T: DataTypeConstraint>(input: &[T]) -> Result<ColumnData> {
+        let typename = input.first().map_or(std::any::type_name::<T>(), |x| x.typename());
+        Ok(match typename {
+            "u8" => ColumnData::VecU8(downcast_values(input)?),
+            "u16" => ColumnData::VecU16(downcast_values(input)?),
+            "u32" => ColumnData::VecU32(downcast_values(input)?),
+            "u64" => ColumnData::VecU64(downcast_values(input)?),
+            "i8" => ColumnData::VecI8(downcast_values(input)?),
+            "i16" => ColumnData::VecI16(downcast_values(input)?),
+            "i32" => ColumnData::VecI32(downcast_values(input)?),
+            "i64" => ColumnData::VecI64(downcast_values(input)?),
+            "f32" => ColumnData::VecF32(downcast_values(input)?),
+            "f64" => ColumnData::VecF64(downcast_values(input)?),
+            _ => {
+                return Err(general_err!(
+                    "Unsupported data type {}",
+                    std::any::type_name::<T>()
+                ))
+            }
+        })
+    }
+
+    /// Returns a column taking ownership of `input` without copying it, or an error
+    /// naming `T` if it is not one of the element types a column can hold.
+    pub fn try_from_vec<T: DataTypeConstraint>(input: Vec<T>) -> Result<ColumnData> {
+        let input: Box<dyn Any> = Box::new(input);
+        let input = match input.downcast::<Vec<u8>>() {
+            Ok(x) => return Ok(ColumnData::VecU8(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<u16>>() {
+            Ok(x) => return Ok(ColumnData::VecU16(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<u32>>() {
+            Ok(x) => return Ok(ColumnData::VecU32(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<u64>>() {
+            Ok(x) => return Ok(ColumnData::VecU64(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<i8>>() {
+            Ok(x) => return Ok(ColumnData::VecI8(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<i16>>() {
+            Ok(x) => return Ok(ColumnData::VecI16(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<i32>>() {
+            Ok(x) => return Ok(ColumnData::VecI32(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<i64>>() {
+            Ok(x) => return Ok(ColumnData::VecI64(*x)),
+            Err(x) => x,
+        };
+        let input = match input.downcast::<Vec<f32>>() {
+            Ok(x) => return Ok(ColumnData::VecF32(*x)),
+            Err(x) => x,
+        };
+        match input.downcast::<Vec<f64>>() {
+            Ok(x) => Ok(ColumnData::VecF64(*x)),
+            Err(_) => Err(general_err!(
+                "Unsupported data type {}",
+                std::any::type_name::<T>()
+            )),
+        }
+    }
+
+    /// Consumes the column and returns its values as a `Vec<T>` without copying them,
+    /// or an error if `T` is not the column's element type. 24-bit columns are read
+    /// as the `i32` or `u32` values they hold.
+    pub fn try_into_vec<T: DataTypeConstraint>(self) -> Result<Vec<T>> {
+        let typename = self.typename();
+        let values: Box<dyn Any> = match self {
+            ColumnData::VecU8(x) => Box::new(x),
+            ColumnData::VecU16(x) => Box::new(x),
+            ColumnData::VecU32(x) | ColumnData::VecU24(x) => Box::new(x),
+            ColumnData::VecU64(x) => Box::new(x),
+            ColumnData::VecI8(x) => Box::new(x),
+            ColumnData::VecI16(x) => Box::new(x),
+            ColumnData::VecI32(x) | ColumnData::VecI24(x) => Box::new(x),
+            ColumnData::VecI64(x) => Box::new(x),
+            ColumnData::VecF32(x) => Box::new(x),
+            ColumnData::VecF64(x) => Box::new(x),
+        };
+        values.downcast::<Vec<T>>().map(|x| *x).map_err(|_| {
+            general_err!(
+                "Cannot read {} column as {}",
+                typename,
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Returns the size in bytes of one element of this column.
+    pub fn type_width(&self) -> usize {
+        match self {
+            ColumnData::VecU8(_) => std::mem::size_of::<u8>(),
+            ColumnData::VecU16(_) => std::mem::size_of::<u16>(),
+            ColumnData::VecU32(_) => std::mem::size_of::<u32>(),
+            ColumnData::VecU64(_) => std::mem::size_of::<u64>(),
+            ColumnData::VecI8(_) => std::mem::size_of::<i8>(),
+            ColumnData::VecI16(_) => std::mem::size_of::<i16>(),
+            ColumnData::VecI32(_) => std::mem::size_of::<i32>(),
+            ColumnData::VecI64(_) => std::mem::size_of::<i64>(),
+            ColumnData::VecF32(_) => std::mem::size_of::<f32>(),
+            ColumnData::VecF64(_) => std::mem::size_of::<f64>(),
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => 3,
+        }
+    }
+
+    /// Decodes the big-endian bytes in `input` and appends the values to this column.
+    ///
+    /// Returns an error if the length of `input` is not a multiple of the element width.
+    pub fn convert_from_u8(&mut self, input: &[u8]) -> Result<()> {
+        self.convert_from_u8_ordered(input, false)
+    }
+
+    /// Decodes the bytes in `input`, little-endian if `little_endian` is set and
+    /// big-endian otherwise, and appends the values to this column.
+    ///
+    /// With the `bytemuck` feature, bytes already in the host's byte order are
+    /// reinterpreted in bulk when `input` is aligned for the element type, instead of
+    /// being decoded per element. Returns an error if the length of `input` is not a
+    /// multiple of the element width.
+    pub fn convert_from_u8_ordered(
+        &mut self,
+        input: &[u8],
+        little_endian: bool,
+    ) -> Result<()> {
+        let width = self.type_width();
+        if input.len() % width != 0 {
+            return Err(general_err!(
+                "{} bytes is not a whole number of {} values",
+                input.len(),
+                self.typename()
+            ));
+        }
+        #[cfg(feature = "bytemuck")]
+        if little_endian == cfg!(target_endian = "little")
+            && self.extend_from_native(input)
+        {
+            return Ok(());
+        }
+        if little_endian {
+            self.read_values::<LittleEndian>(input);
+        } else {
+            self.read_values::<BigEndian>(input);
+        }
+        Ok(())
+    }
+
+    /// Decodes `input`, a whole number of values in byte order `E`, and appends the
+    /// values to this column.
+    fn read_values<E: ByteOrder>(&mut self, input: &[u8]) {
         match self {
             ColumnData::VecU8(x) => {
                 x.extend_from_slice(input);
@@ -1339,50 +1560,83 @@ impl ColumnData {
             ColumnData::VecU16(x) => {
                 let orig_output_len = x.len();
                 x.resize(orig_output_len + input.len() / std::mem::size_of::<u16>(), 0u16);
-                BigEndian::read_u16_into(input, &mut x[orig_output_len..]); 
+                E::read_u16_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecU32(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<u32>(), 0u32);
-                BigEndian::read_u32_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<u32>(), 0u32);
+                E::read_u32_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecU64(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<u64>(), 0u64);
-                BigEndian::read_u64_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<u64>(), 0u64);
+                E::read_u64_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecI8(x) => {
                 x.extend_from_slice(&input.iter().map(|&x| x as i8).collect::<Vec<_>>());
             },
             ColumnData::VecI16(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<i16>(), 0i16);
-                BigEndian::read_i16_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<i16>(), 0i16);
+                E::read_i16_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecI32(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<i32>(), 0i32);
-                BigEndian::read_i32_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<i32>(), 0i32);
+                E::read_i32_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecI64(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<i64>(), 0i64);
-                BigEndian::read_i64_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<i64>(), 0i64);
+                E::read_i64_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecF32(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<f32>(), 0f32);
-                BigEndian::read_f32_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<f32>(), 0f32);
+                E::read_f32_into(input, &mut x[orig_output_len..]); 
             },
             ColumnData::VecF64(x) => {
                 let orig_output_len = x.len();
-                x.resize(input.len() / std::mem::size_of::<f64>(), 0f64);
-                BigEndian::read_f64_into(input, &mut x[orig_output_len..]); 
+                x.resize(orig_output_len + input.len() / std::mem::size_of::<f64>(), 0f64);
+                E::read_f64_into(input, &mut x[orig_output_len..]); 
+            },
+            ColumnData::VecI24(x) => {
+                x.extend(input.chunks_exact(3).map(E::read_i24));
+            },
+            ColumnData::VecU24(x) => {
+                x.extend(input.chunks_exact(3).map(E::read_u24));
             },
         }
     }
 
-    pub fn convert_to_u8(&self, output: &mut Vec<u8>) {
+    /// Encodes the values of this column as big-endian bytes appended to `output`.
+    pub fn convert_to_u8<B: ByteSink>(&self, output: &mut B) -> Result<()> {
+        self.convert_to_u8_ordered(output, false)
+    }
+
+    /// Encodes the values of this column as bytes appended to `output`, little-endian
+    /// if `little_endian` is set and big-endian otherwise.
+    ///
+    /// With the `bytemuck` feature, values whose byte order already matches the host's
+    /// are copied in bulk instead of being encoded per element.
+    pub fn convert_to_u8_ordered<B: ByteSink>(
+        &self,
+        output: &mut B,
+        little_endian: bool,
+    ) -> Result<()> {
+        #[cfg(feature = "bytemuck")]
+        if little_endian == cfg!(target_endian = "little") && self.write_native(output) {
+            return Ok(());
+        }
+        if little_endian {
+            self.write_values::<LittleEndian, B>(output)
+        } else {
+            self.write_values::<BigEndian, B>(output)
+        }
+    }
+
+    /// Encodes the values of this column in byte order `E`, appending them to `output`.
+    fn write_values<E: ByteOrder, B: ByteSink>(&self, output: &mut B) -> Result<()> {
         let orig_output_len = output.len();
         match self {
             ColumnData::VecU8(x) => {
@@ -1390,42 +1644,104 @@ impl ColumnData {
             },
             ColumnData::VecU16(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<u16>(), 0u8);
-                BigEndian::write_u16_into(&x, &mut output[orig_output_len..]); 
+                E::write_u16_into(&x, &mut output.as_mut_slice()[orig_output_len..]); 
             },
             ColumnData::VecU32(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<u32>(), 0u8);
-                BigEndian::write_u32_into(&x, &mut output[orig_output_len..]);  
+                E::write_u32_into(&x, &mut output.as_mut_slice()[orig_output_len..]);  
             },
             ColumnData::VecU64(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<u64>(), 0u8);
-                BigEndian::write_u64_into(&x, &mut output[orig_output_len..]); 
+                E::write_u64_into(&x, &mut output.as_mut_slice()[orig_output_len..]); 
             },
             ColumnData::VecI8(x) => {
                 output.extend_from_slice(&x.iter().map(|&x| x as u8).collect::<Vec<_>>());
             },
             ColumnData::VecI16(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<i16>(), 0u8);
-                BigEndian::write_i16_into(&x, &mut output[orig_output_len..]); 
+                E::write_i16_into(&x, &mut output.as_mut_slice()[orig_output_len..]); 
             },
             ColumnData::VecI32(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<i32>(), 0u8);
-                BigEndian::write_i32_into(&x, &mut output[orig_output_len..]); 
+                E::write_i32_into(&x, &mut output.as_mut_slice()[orig_output_len..]); 
             },
             ColumnData::VecI64(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<i64>(), 0u8);
-                BigEndian::write_i64_into(&x, &mut output[orig_output_len..]);  
+                E::write_i64_into(&x, &mut output.as_mut_slice()[orig_output_len..]);  
             },
             ColumnData::VecF32(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<f32>(), 0u8);
-                BigEndian::write_f32_into(&x, &mut output[orig_output_len..]); 
+                E::write_f32_into(&x, &mut output.as_mut_slice()[orig_output_len..]); 
             },
             ColumnData::VecF64(x) => {
                 output.resize(orig_output_len + x.len() * std::mem::size_of::<f64>(), 0u8);
-                BigEndian::write_f64_into(&x, &mut output[orig_output_len..]);  
+                E::write_f64_into(&x, &mut output.as_mut_slice()[orig_output_len..]);  
+            },
+            ColumnData::VecI24(x) => {
+                if let Some(v) = x.iter().find(|&&v| wrap_i24(v) != v) {
+                    return Err(general_err!("Value {} does not fit in i24", v));
+                }
+                output.resize(orig_output_len + x.len() * 3, 0u8);
+                for (v, chunk) in x.iter().zip(output.as_mut_slice()[orig_output_len..].chunks_exact_mut(3)) {
+                    E::write_i24(chunk, *v);
+                }
             },
+            ColumnData::VecU24(x) => {
+                if let Some(v) = x.iter().find(|&&v| wrap_u24(v) != v) {
+                    return Err(general_err!("Value {} does not fit in u24", v));
+                }
+                output.resize(orig_output_len + x.len() * 3, 0u8);
+                for (v, chunk) in x.iter().zip(output.as_mut_slice()[orig_output_len..].chunks_exact_mut(3)) {
+                    E::write_u24(chunk, *v);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Fast path of `convert_from_u8_ordered` for bytes in the host's byte order:
+    /// reinterprets `input` in bulk rather than decoding it per element. Returns
+    /// `false`, appending nothing, if `input` is not aligned for the element type or
+    /// the type has no native layout.
+    #[cfg(feature = "bytemuck")]
+    fn extend_from_native(&mut self, input: &[u8]) -> bool {
+        match self {
+            ColumnData::VecU8(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecU16(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecU32(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecU64(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecI8(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecI16(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecI32(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecI64(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecF32(x) => extend_from_native_bytes(input, x),
+            ColumnData::VecF64(x) => extend_from_native_bytes(input, x),
+            // widened to 32 bits in memory
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => false,
         }
     }
 
+    /// Fast path of `convert_to_u8_ordered` for values in the host's byte order:
+    /// copies them in bulk rather than encoding them per element. Returns `false`,
+    /// appending nothing, if the type has no native layout.
+    #[cfg(feature = "bytemuck")]
+    fn write_native<B: ByteSink>(&self, output: &mut B) -> bool {
+        match self {
+            ColumnData::VecU8(x) => output.extend_from_slice(x),
+            ColumnData::VecU16(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecU32(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecU64(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecI8(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecI16(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecI32(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecI64(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecF32(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecF64(x) => output.extend_from_slice(bytemuck::cast_slice(x)),
+            ColumnData::VecI24(_) | ColumnData::VecU24(_) => return false,
+        }
+        true
+    }
+
     pub fn clear(&mut self) {
         match self {
             ColumnData::VecU8(x) => {
@@ -1458,6 +1774,298 @@ impl ColumnData {
             ColumnData::VecF64(x) => {
                 x.clear();
             },
+            ColumnData::VecI24(x) => {
+                x.clear();
+            },
+            ColumnData::VecU24(x) => {
+                x.clear();
+            },
+        }
+    }
+
+    /// Returns the name of the element type held by this column, e.g. `"u32"`.
+    pub fn typename(&self) -> &'static str {
+        match self {
+            ColumnData::VecU8(_) => "u8",
+            ColumnData::VecU16(_) => "u16",
+            ColumnData::VecU32(_) => "u32",
+            ColumnData::VecU64(_) => "u64",
+            ColumnData::VecI8(_) => "i8",
+            ColumnData::VecI16(_) => "i16",
+            ColumnData::VecI32(_) => "i32",
+            ColumnData::VecI64(_) => "i64",
+            ColumnData::VecF32(_) => "f32",
+            ColumnData::VecF64(_) => "f64",
+            ColumnData::VecI24(_) => "i24",
+            ColumnData::VecU24(_) => "u24",
+        }
+    }
+
+    /// Returns an empty column holding elements of type `typename`, one of
+    /// [`supported_typenames`], or `None` for any other name.
+    pub fn from_typename(typename: &str) -> Option<ColumnData> {
+        match typename {
+            "u8" => Some(ColumnData::VecU8(Vec::new())),
+            "u16" => Some(ColumnData::VecU16(Vec::new())),
+            "u32" => Some(ColumnData::VecU32(Vec::new())),
+            "u64" => Some(ColumnData::VecU64(Vec::new())),
+            "i8" => Some(ColumnData::VecI8(Vec::new())),
+            "i16" => Some(ColumnData::VecI16(Vec::new())),
+            "i32" => Some(ColumnData::VecI32(Vec::new())),
+            "i64" => Some(ColumnData::VecI64(Vec::new())),
+            "f32" => Some(ColumnData::VecF32(Vec::new())),
+            "f64" => Some(ColumnData::VecF64(Vec::new())),
+            "i24" => Some(ColumnData::VecI24(Vec::new())),
+            "u24" => Some(ColumnData::VecU24(Vec::new())),
+            _ => None,
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        match self {
+            ColumnData::VecU8(x) => x.reserve(additional),
+            ColumnData::VecU16(x) => x.reserve(additional),
+            ColumnData::VecU32(x) => x.reserve(additional),
+            ColumnData::VecU64(x) => x.reserve(additional),
+            ColumnData::VecI8(x) => x.reserve(additional),
+            ColumnData::VecI16(x) => x.reserve(additional),
+            ColumnData::VecI32(x) => x.reserve(additional),
+            ColumnData::VecI64(x) => x.reserve(additional),
+            ColumnData::VecF32(x) => x.reserve(additional),
+            ColumnData::VecF64(x) => x.reserve(additional),
+            ColumnData::VecI24(x) => x.reserve(additional),
+            ColumnData::VecU24(x) => x.reserve(additional),
+        }
+    }
+
+    /// Reserves capacity for exactly `additional` more elements, for callers that know
+    /// the final length.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        match self {
+            ColumnData::VecU8(x) => x.reserve_exact(additional),
+            ColumnData::VecU16(x) => x.reserve_exact(additional),
+            ColumnData::VecU32(x) => x.reserve_exact(additional),
+            ColumnData::VecU64(x) => x.reserve_exact(additional),
+            ColumnData::VecI8(x) => x.reserve_exact(additional),
+            ColumnData::VecI16(x) => x.reserve_exact(additional),
+            ColumnData::VecI32(x) => x.reserve_exact(additional),
+            ColumnData::VecI64(x) => x.reserve_exact(additional),
+            ColumnData::VecF32(x) => x.reserve_exact(additional),
+            ColumnData::VecF64(x) => x.reserve_exact(additional),
+            ColumnData::VecI24(x) => x.reserve_exact(additional),
+            ColumnData::VecU24(x) => x.reserve_exact(additional),
+        }
+    }
+
+    /// Returns a new column holding `len` elements starting at `start`.
+    pub fn slice(&self, start: usize, len: usize) -> ColumnData {
+        match self {
+            ColumnData::VecU8(x) => ColumnData::VecU8(x[start..start + len].to_vec()),
+            ColumnData::VecU16(x) => ColumnData::VecU16(x[start..start + len].to_vec()),
+            ColumnData::VecU32(x) => ColumnData::VecU32(x[start..start + len].to_vec()),
+            ColumnData::VecU64(x) => ColumnData::VecU64(x[start..start + len].to_vec()),
+            ColumnData::VecI8(x) => ColumnData::VecI8(x[start..start + len].to_vec()),
+            ColumnData::VecI16(x) => ColumnData::VecI16(x[start..start + len].to_vec()),
+            ColumnData::VecI32(x) => ColumnData::VecI32(x[start..start + len].to_vec()),
+            ColumnData::VecI64(x) => ColumnData::VecI64(x[start..start + len].to_vec()),
+            ColumnData::VecF32(x) => ColumnData::VecF32(x[start..start + len].to_vec()),
+            ColumnData::VecF64(x) => ColumnData::VecF64(x[start..start + len].to_vec()),
+            ColumnData::VecI24(x) => ColumnData::VecI24(x[start..start + len].to_vec()),
+            ColumnData::VecU24(x) => ColumnData::VecU24(x[start..start + len].to_vec()),
+        }
+    }
+
+    /// Appends the elements of `other` to this column.
+    ///
+    /// Returns an error if `other` holds a different element type.
+    pub fn extend_from(&mut self, other: &ColumnData) -> Result<()> {
+        match (self, other) {
+            (ColumnData::VecU8(x), ColumnData::VecU8(y)) => x.extend_from_slice(y),
+            (ColumnData::VecU16(x), ColumnData::VecU16(y)) => x.extend_from_slice(y),
+            (ColumnData::VecU32(x), ColumnData::VecU32(y)) => x.extend_from_slice(y),
+            (ColumnData::VecU64(x), ColumnData::VecU64(y)) => x.extend_from_slice(y),
+            (ColumnData::VecI8(x), ColumnData::VecI8(y)) => x.extend_from_slice(y),
+            (ColumnData::VecI16(x), ColumnData::VecI16(y)) => x.extend_from_slice(y),
+            (ColumnData::VecI32(x), ColumnData::VecI32(y)) => x.extend_from_slice(y),
+            (ColumnData::VecI64(x), ColumnData::VecI64(y)) => x.extend_from_slice(y),
+            (ColumnData::VecF32(x), ColumnData::VecF32(y)) => x.extend_from_slice(y),
+            (ColumnData::VecF64(x), ColumnData::VecF64(y)) => x.extend_from_slice(y),
+            (ColumnData::VecI24(x), ColumnData::VecI24(y)) => x.extend_from_slice(y),
+            (ColumnData::VecU24(x), ColumnData::VecU24(y)) => x.extend_from_slice(y),
+            (x, y) => {
+                return Err(general_err!(
+                    "Cannot extend a {} column with {} data",
+                    x.typename(),
+                    y.typename()
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces every integer element after the first with its difference from the
+    /// previous element, wrapping on overflow. Float columns are left unchanged.
+    ///
+    /// Sorted columns turn into runs of small values, which compress better.
+    pub fn delta_encode(&mut self) {
+        match self {
+            ColumnData::VecU8(x) => delta_encode_in_place!(x),
+            ColumnData::VecU16(x) => delta_encode_in_place!(x),
+            ColumnData::VecU32(x) => delta_encode_in_place!(x),
+            ColumnData::VecU64(x) => delta_encode_in_place!(x),
+            ColumnData::VecI8(x) => delta_encode_in_place!(x),
+            ColumnData::VecI16(x) => delta_encode_in_place!(x),
+            ColumnData::VecI32(x) => delta_encode_in_place!(x),
+            ColumnData::VecI64(x) => delta_encode_in_place!(x),
+            ColumnData::VecI24(x) => {
+                delta_encode_in_place!(x);
+                x.iter_mut().for_each(|v| *v = wrap_i24(*v));
+            }
+            ColumnData::VecU24(x) => {
+                delta_encode_in_place!(x);
+                x.iter_mut().for_each(|v| *v = wrap_u24(*v));
+            }
+            ColumnData::VecF32(_) | ColumnData::VecF64(_) => {}
+        }
+    }
+
+    /// Reverses [`ColumnData::delta_encode`].
+    pub fn delta_decode(&mut self) {
+        match self {
+            ColumnData::VecU8(x) => delta_decode_in_place!(x),
+            ColumnData::VecU16(x) => delta_decode_in_place!(x),
+            ColumnData::VecU32(x) => delta_decode_in_place!(x),
+            ColumnData::VecU64(x) => delta_decode_in_place!(x),
+            ColumnData::VecI8(x) => delta_decode_in_place!(x),
+            ColumnData::VecI16(x) => delta_decode_in_place!(x),
+            ColumnData::VecI32(x) => delta_decode_in_place!(x),
+            ColumnData::VecI64(x) => delta_decode_in_place!(x),
+            ColumnData::VecI24(x) => {
+                delta_decode_in_place!(x);
+                x.iter_mut().for_each(|v| *v = wrap_i24(*v));
+            }
+            ColumnData::VecU24(x) => {
+                delta_decode_in_place!(x);
+                x.iter_mut().for_each(|v| *v = wrap_u24(*v));
+            }
+            ColumnData::VecF32(_) | ColumnData::VecF64(_) => {}
+        }
+    }
+
+    /// Delta encodes like [`ColumnData::delta_encode`], but first widens the column
+    /// with [`ColumnData::delta_widened`] when a difference does not fit in a signed
+    /// integer of the element width, so that no difference wraps.
+    ///
+    /// Returns whether the column was widened. 64-bit columns have no wider type and
+    /// always wrap.
+    pub fn delta_encode_promoted(&mut self) -> bool {
+        let overflows = match self {
+            ColumnData::VecU8(x) => delta_overflows!(x, i8::MIN, i8::MAX),
+            ColumnData::VecI8(x) => delta_overflows!(x, i8::MIN, i8::MAX),
+            ColumnData::VecU16(x) => delta_overflows!(x, i16::MIN, i16::MAX),
+            ColumnData::VecI16(x) => delta_overflows!(x, i16::MIN, i16::MAX),
+            ColumnData::VecU32(x) => delta_overflows!(x, i32::MIN, i32::MAX),
+            ColumnData::VecI32(x) => delta_overflows!(x, i32::MIN, i32::MAX),
+            ColumnData::VecU24(x) => delta_overflows!(x, -(1 << 23), (1 << 23) - 1),
+            ColumnData::VecI24(x) => delta_overflows!(x, -(1 << 23), (1 << 23) - 1),
+            _ => false,
+        };
+        let widened = match overflows {
+            true => self.delta_widened(),
+            false => None,
+        };
+        let promoted = widened.is_some();
+        if let Some(wide) = widened {
+            *self = wide;
+        }
+        self.delta_encode();
+        promoted
+    }
+
+    /// Returns a copy of this column converted to the signed type twice as wide that
+    /// [`ColumnData::delta_encode_promoted`] widens it to, or `None` for 64-bit and
+    /// float columns. 24-bit columns widen to `i32`.
+    pub fn delta_widened(&self) -> Option<ColumnData> {
+        Some(match self {
+            ColumnData::VecU8(x) => {
+                ColumnData::VecI16(x.iter().map(|&v| v as i16).collect())
+            }
+            ColumnData::VecI8(x) => {
+                ColumnData::VecI16(x.iter().map(|&v| v as i16).collect())
+            }
+            ColumnData::VecU16(x) => {
+                ColumnData::VecI32(x.iter().map(|&v| v as i32).collect())
+            }
+            ColumnData::VecI16(x) => {
+                ColumnData::VecI32(x.iter().map(|&v| v as i32).collect())
+            }
+            ColumnData::VecU24(x) => {
+                ColumnData::VecI32(x.iter().map(|&v| v as i32).collect())
+            }
+            ColumnData::VecI24(x) => ColumnData::VecI32(x.clone()),
+            ColumnData::VecU32(x) => {
+                ColumnData::VecI64(x.iter().map(|&v| v as i64).collect())
+            }
+            ColumnData::VecI32(x) => {
+                ColumnData::VecI64(x.iter().map(|&v| v as i64).collect())
+            }
+            _ => return None,
+        })
+    }
+
+    /// Appends the elements of `wide`, a column of this column's type or of the type
+    /// [`ColumnData::delta_widened`] returns for it, converting them back to this
+    /// column's type.
+    ///
+    /// Returns an error if `wide` holds another element type.
+    pub fn extend_narrowed(&mut self, wide: &ColumnData) -> Result<()> {
+        match (self, wide) {
+            (ColumnData::VecU8(x), ColumnData::VecI16(y)) => {
+                x.extend(y.iter().map(|&v| v as u8))
+            }
+            (ColumnData::VecI8(x), ColumnData::VecI16(y)) => {
+                x.extend(y.iter().map(|&v| v as i8))
+            }
+            (ColumnData::VecU16(x), ColumnData::VecI32(y)) => {
+                x.extend(y.iter().map(|&v| v as u16))
+            }
+            (ColumnData::VecI16(x), ColumnData::VecI32(y)) => {
+                x.extend(y.iter().map(|&v| v as i16))
+            }
+            (ColumnData::VecU24(x), ColumnData::VecI32(y)) => {
+                x.extend(y.iter().map(|&v| v as u32))
+            }
+            (ColumnData::VecI24(x), ColumnData::VecI32(y)) => x.extend_from_slice(y),
+            (ColumnData::VecU32(x), ColumnData::VecI64(y)) => {
+                x.extend(y.iter().map(|&v| v as u32))
+            }
+            (ColumnData::VecI32(x), ColumnData::VecI64(y)) => {
+                x.extend(y.iter().map(|&v| v as i32))
+            }
+            (x, y) => return x.extend_from(y),
+        }
+        Ok(())
+    }
+
+    /// Checks that every element lies within `min..=max`.
+    ///
+    /// Returns an error naming the first offending value and its index, or if the bounds
+    /// are not of this column's element type. NaN is never within range.
+    pub fn check_range<T: DataTypeConstraint>(&self, min: T, max: T) -> Result<()> {
+        match self {
+            ColumnData::VecU8(x) => check_range_of(x, &min, &max),
+            ColumnData::VecU16(x) => check_range_of(x, &min, &max),
+            ColumnData::VecU32(x) => check_range_of(x, &min, &max),
+            ColumnData::VecU64(x) => check_range_of(x, &min, &max),
+            ColumnData::VecI8(x) => check_range_of(x, &min, &max),
+            ColumnData::VecI16(x) => check_range_of(x, &min, &max),
+            ColumnData::VecI32(x) => check_range_of(x, &min, &max),
+            ColumnData::VecI64(x) => check_range_of(x, &min, &max),
+            ColumnData::VecF32(x) => check_range_of(x, &min, &max),
+            ColumnData::VecF64(x) => check_range_of(x, &min, &max),
+            ColumnData::VecI24(x) => check_range_of(x, &min, &max),
+            ColumnData::VecU24(x) => check_range_of(x, &min, &max),
         }
     }
 
@@ -1493,7 +2101,145 @@ impl ColumnData {
             ColumnData::VecF64(x) => {
                 x.len()
             },
+            ColumnData::VecI24(x) => {
+                x.len()
+            },
+            ColumnData::VecU24(x) => {
+                x.len()
+            },
+        }
+    }
+
+    /// Returns the sum of the values as `f64`, which may round large 64-bit integers.
+    pub fn sum_f64(&self) -> f64 {
+        match self {
+            ColumnData::VecU8(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecU16(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecU32(x) | ColumnData::VecU24(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecU64(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecI8(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecI16(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecI32(x) | ColumnData::VecI24(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecI64(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecF32(x) => x.iter().map(|&v| v as f64).sum(),
+            ColumnData::VecF64(x) => x.iter().sum(),
+        }
+    }
+}
+
+/// Wraps `value` into the signed 24-bit range, as if computed with 24-bit arithmetic.
+fn wrap_i24(value: i32) -> i32 {
+    (value << 8) >> 8
+}
+
+/// Wraps `value` into the unsigned 24-bit range, as if computed with 24-bit arithmetic.
+fn wrap_u24(value: u32) -> u32 {
+    value & 0x00FF_FFFF
+}
+
+/// Growable byte buffer that [`ColumnData::convert_to_u8`] appends to.
+///
+/// Implemented for `Vec<u8>`, and with the `allocator` feature for the
+/// `allocator_api2` vector, so that codec scratch buffers can live in a custom
+/// allocator.
+pub trait ByteSink {
+    /// Returns the number of bytes in the buffer.
+    fn len(&self) -> usize;
+
+    /// Returns whether the buffer holds no bytes.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Resizes the buffer to `new_len` bytes, filling new bytes with `value`.
+    fn resize(&mut self, new_len: usize, value: u8);
+
+    /// Appends `bytes` to the buffer.
+    fn extend_from_slice(&mut self, bytes: &[u8]);
+
+    /// Returns the bytes of the buffer.
+    fn as_mut_slice(&mut self) -> &mut [u8];
+}
+
+impl ByteSink for Vec<u8> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn resize(&mut self, new_len: usize, value: u8) {
+        self.resize(new_len, value)
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+#[cfg(feature = "allocator")]
+impl<A: allocator_api2::alloc::Allocator> ByteSink for allocator_api2::vec::Vec<u8, A> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn resize(&mut self, new_len: usize, value: u8) {
+        self.resize(new_len, value)
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+/// Compares `values` against bounds of a possibly different element type, downcasting
+/// the bounds to `T` first.
+fn check_range_of<T: DataTypeConstraint, B: DataTypeConstraint>(
+    values: &[T],
+    min: &B,
+    max: &B,
+) -> Result<()> {
+    let (min, max) = match (
+        min.as_any().downcast_ref::<T>(),
+        max.as_any().downcast_ref::<T>(),
+    ) {
+        (Some(min), Some(max)) => (min, max),
+        _ => {
+            return Err(general_err!(
+                "Valid range of {} does not match {} column",
+                min.typename(),
+                T::default().typename()
+            ))
+        }
+    };
+    match values.iter().position(|v| !(min <= v && v <= max)) {
+        Some(index) => Err(general_err!(
+            "Value {} at index {} is outside the valid range [{}, {}]",
+            values[index],
+            index,
+            min,
+            max
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Appends the native-endian values in `input` to `output` by reinterpreting the
+/// bytes in place. Returns `false`, appending nothing, if `input` is misaligned.
+#[cfg(feature = "bytemuck")]
+fn extend_from_native_bytes<T: bytemuck::Pod>(input: &[u8], output: &mut Vec<T>) -> bool {
+    match bytemuck::try_cast_slice::<u8, T>(input) {
+        Ok(values) => {
+            output.extend_from_slice(values);
+            true
         }
+        Err(_) => false,
     }
 }
 
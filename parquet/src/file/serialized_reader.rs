@@ -485,7 +485,7 @@ pub(crate) fn decode_page(
             )?;
 
             decompressed.clear();
-            output_buf_columndata.convert_to_u8(&mut decompressed);
+            output_buf_columndata.convert_to_u8(&mut decompressed)?;
 
             if decompressed.len() != uncompressed_size {
                 return Err(general_err!(
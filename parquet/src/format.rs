@@ -462,6 +462,8 @@ impl CompressionCodec {
   pub const ZSTD: CompressionCodec = CompressionCodec(6);
   pub const LZ4_RAW: CompressionCodec = CompressionCodec(7);
   pub const QCOM: CompressionCodec = CompressionCodec(8);
+  pub const BZIP2: CompressionCodec = CompressionCodec(9);
+  pub const XZ: CompressionCodec = CompressionCodec(10);
   pub const ENUM_VALUES: &'static [Self] = &[
     Self::UNCOMPRESSED,
     Self::SNAPPY,
@@ -472,6 +474,8 @@ impl CompressionCodec {
     Self::ZSTD,
     Self::LZ4_RAW,
     Self::QCOM,
+    Self::BZIP2,
+    Self::XZ,
   ];
 }
 
@@ -498,6 +502,8 @@ impl From<i32> for CompressionCodec {
       6 => CompressionCodec::ZSTD,
       7 => CompressionCodec::LZ4_RAW,
       8 => CompressionCodec::QCOM,
+      9 => CompressionCodec::BZIP2,
+      10 => CompressionCodec::XZ,
       _ => CompressionCodec(i)
     }
   }
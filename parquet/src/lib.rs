@@ -91,3 +91,6 @@ pub mod bloom_filter;
 pub mod file;
 pub mod record;
 pub mod schema;
+
+#[cfg(feature = "test_common")]
+pub mod test_util;
@@ -23,7 +23,7 @@ use chrono::{TimeZone, Utc};
 use num_bigint::{BigInt, Sign};
 
 use crate::basic::{ConvertedType, Type as PhysicalType};
-use crate::data_type::{ByteArray, Decimal, Int96};
+use crate::data_type::{ByteArray, ColumnData, Decimal, Int96};
 use crate::errors::{ParquetError, Result};
 use crate::schema::types::ColumnDescPtr;
 
@@ -667,6 +667,80 @@ impl Field {
         }
     }
 
+    /// Converts decompressed column values into fields, using the physical and
+    /// converted type of `descr` to pick the variant, e.g. `Date` for an INT32 column
+    /// with converted type DATE.
+    ///
+    /// Integer columns narrower than 32 bits are widened to INT32, as Parquet stores
+    /// them, and `u64` values are reinterpreted as INT64. Returns an error if the values
+    /// do not fit the physical type of `descr`, including a `u32` value above
+    /// `i32::MAX`.
+    pub fn convert_column_data(
+        descr: &ColumnDescPtr,
+        values: &ColumnData,
+    ) -> Result<Vec<Self>> {
+        let fields = match (descr.physical_type(), values) {
+            (PhysicalType::INT32, ColumnData::VecU8(x)) => x
+                .iter()
+                .map(|v| Field::convert_int32(descr, *v as i32))
+                .collect(),
+            (PhysicalType::INT32, ColumnData::VecI8(x)) => x
+                .iter()
+                .map(|v| Field::convert_int32(descr, *v as i32))
+                .collect(),
+            (PhysicalType::INT32, ColumnData::VecU16(x)) => x
+                .iter()
+                .map(|v| Field::convert_int32(descr, *v as i32))
+                .collect(),
+            (PhysicalType::INT32, ColumnData::VecI16(x)) => x
+                .iter()
+                .map(|v| Field::convert_int32(descr, *v as i32))
+                .collect(),
+            (PhysicalType::INT32, ColumnData::VecU32(x)) => {
+                let mut fields = Vec::with_capacity(x.len());
+                for v in x {
+                    let v = i32::try_from(*v).map_err(|_| {
+                        general_err!(
+                            "u32 value {} does not fit in INT32 column {}",
+                            v,
+                            descr.name()
+                        )
+                    })?;
+                    fields.push(Field::convert_int32(descr, v));
+                }
+                fields
+            }
+            (PhysicalType::INT32, ColumnData::VecI32(x) | ColumnData::VecI24(x)) => {
+                x.iter().map(|v| Field::convert_int32(descr, *v)).collect()
+            }
+            (PhysicalType::INT32, ColumnData::VecU24(x)) => x
+                .iter()
+                .map(|v| Field::convert_int32(descr, *v as i32))
+                .collect(),
+            (PhysicalType::INT64, ColumnData::VecU64(x)) => x
+                .iter()
+                .map(|v| Field::convert_int64(descr, *v as i64))
+                .collect(),
+            (PhysicalType::INT64, ColumnData::VecI64(x)) => {
+                x.iter().map(|v| Field::convert_int64(descr, *v)).collect()
+            }
+            (PhysicalType::FLOAT, ColumnData::VecF32(x)) => {
+                x.iter().map(|v| Field::convert_float(descr, *v)).collect()
+            }
+            (PhysicalType::DOUBLE, ColumnData::VecF64(x)) => {
+                x.iter().map(|v| Field::convert_double(descr, *v)).collect()
+            }
+            (physical_type, values) => {
+                return Err(general_err!(
+                    "Cannot convert {} values to fields of physical type {}",
+                    values.typename(),
+                    physical_type
+                ))
+            }
+        };
+        Ok(fields)
+    }
+
     #[cfg(any(feature = "json", test))]
     pub fn to_json_value(&self) -> Value {
         use base64::prelude::BASE64_STANDARD;
@@ -0,0 +1,61 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Random data generators for writing codec tests outside this crate.
+//!
+//! Enabled with the `test_common` feature.
+//!
+//! ```
+//! use parquet::basic::Compression;
+//! use parquet::compression::{create_codec, CodecOptionsBuilder};
+//! use parquet::data_type::ColumnData;
+//! use parquet::test_util::random_column;
+//!
+//! let data = random_column::<f64>(100);
+//!
+//! let options = CodecOptionsBuilder::default().build();
+//! let mut codec = create_codec(Compression::ZSTD, &options).unwrap().unwrap();
+//! let mut compressed = Vec::new();
+//! codec.compress(&data, &mut compressed).unwrap();
+//!
+//! let mut decompressed = ColumnData::new(&Vec::<f64>::new());
+//! codec.decompress(&compressed, &mut decompressed, None).unwrap();
+//! assert_eq!(decompressed, data);
+//! ```
+
+use rand::distributions::{Distribution, Standard};
+use rand::{thread_rng, Rng};
+
+use crate::data_type::{ColumnData, DataTypeConstraint};
+
+/// Returns `n` values of type `T` drawn uniformly from the whole range of `T`, or from
+/// `[0, 1)` for floats.
+pub fn random_typed_vec<T: DataTypeConstraint>(n: usize) -> Vec<T>
+where
+    Standard: Distribution<T>,
+{
+    thread_rng().sample_iter(Standard).take(n).collect()
+}
+
+/// Returns a column of `n` random values of type `T`, as generated by
+/// [`random_typed_vec`].
+pub fn random_column<T: DataTypeConstraint>(n: usize) -> ColumnData
+where
+    Standard: Distribution<T>,
+{
+    ColumnData::new(&random_typed_vec::<T>(n))
+}
@@ -8,13 +8,14 @@ use log::{Level, debug};
 use parquet::basic::Compression as CodecType;
 use parquet::compression::Codec;
 use parquet::{basic::Compression, compression::{create_codec, CodecOptionsBuilder}};
-use byteorder::{ByteOrder, BigEndian};
+use byteorder::{ByteOrder, BigEndian, LittleEndian};
 use rand::{
     distributions::{uniform::SampleUniform, Distribution, Standard},
     thread_rng, Rng,
 };
 
 use parquet::data_type::DataTypeConstraint;
+use parquet::test_util::{random_column, random_typed_vec};
 
 /// 
 /// Usage:
@@ -46,35 +47,13 @@ fn create_test_codec(codec: CodecType) -> Box<dyn Codec> {
     codec
 }
 
-fn generate_test_data<T: DataTypeConstraint>(datasize: usize, data: &mut Vec<T>) where Standard: Distribution<T> {
-    for _i in 0..datasize {
-        data.push(rand::thread_rng().gen::<T>());
-    }
-}
-
-fn random_bytes(n: usize) -> Vec<u8> {
-    let mut result = vec![];
-    let mut rng = thread_rng();
-    for _ in 0..n {
-        result.push(rng.gen_range(0..255));
-    }
-    result
-}
-
-fn random_numbers<T>(n: usize) -> Vec<T> where Standard: Distribution<T>
-{
-    let mut rng = thread_rng();
-    Standard.sample_iter(&mut rng).take(n).collect()
-}
-
 
 fn test_codec_qcom_generic<T: DataTypeConstraint + 'static + Copy>(c: CodecType) where Standard: Distribution<T>
 {
     let size = 100usize;
 
-    let mut internal_data : Vec<T> = Vec::<T>::new();
+    let internal_data: Vec<T> = random_typed_vec(size);
 
-    generate_test_data(size, &mut internal_data);
 
     let data : ColumnData = ColumnData::new(&internal_data);
 
@@ -183,17 +162,17 @@ fn test_codec_with_size<T: DataTypeConstraint + 'static + Copy>(c: CodecType) wh
     let sizes = vec![10];
     
     for size in sizes {
-        let mut internal_data : Vec<T> = Vec::new();
+        let internal_data: Vec<T> = random_typed_vec(size);
 
         debug!("sizeof {} {:?}", std::any::type_name::<T>().to_string(), std::mem::size_of::<T>().to_string());
 
-        generate_test_data(size, &mut internal_data);
 
         debug!("internal_data: {:?} {:?}", internal_data.len(), internal_data);
 
         match c {
             CodecType::SNAPPY | CodecType::GZIP | CodecType::BROTLI 
-            | CodecType::LZ4 | CodecType::ZSTD | CodecType::LZ4_RAW => {
+            | CodecType::LZ4 | CodecType::ZSTD | CodecType::LZ4_RAW
+            | CodecType::BZIP2 | CodecType::XZ => {
                 test_roundtrip(c, &internal_data,  Some((internal_data.len() * std::mem::size_of::<T>()) as usize));
                 // test_roundtrip(c, &internal_data, &data, None);
             },
@@ -202,6 +181,7 @@ fn test_codec_with_size<T: DataTypeConstraint + 'static + Copy>(c: CodecType) wh
         
     }
 }
+
 #[test]
 fn test_codec_snappy_u8() {
     test_codec_with_size::<u8>(CodecType::SNAPPY);
@@ -210,6 +190,10 @@ fn test_codec_snappy_u8() {
 fn test_codec_snappy_u64() {
     test_codec_with_size::<u64>(CodecType::SNAPPY);
 }
+#[test]
+fn test_codec_snappy_i8() {
+    test_codec_with_size::<i8>(CodecType::SNAPPY);
+}
 
 #[test]
 fn test_codec_gzip_u8() {
@@ -256,6 +240,66 @@ fn test_codec_lz4_raw_64() {
     test_codec_with_size::<u64>(CodecType::LZ4_RAW);
 }
 
+#[cfg(feature = "bzip2")]
+#[test]
+fn test_codec_bzip2_u8() {
+    test_codec_with_size::<u8>(CodecType::BZIP2);
+}
+#[cfg(feature = "bzip2")]
+#[test]
+fn test_codec_bzip2_u64() {
+    test_codec_with_size::<u64>(CodecType::BZIP2);
+}
+#[cfg(feature = "bzip2")]
+#[test]
+fn test_codec_bzip2_without_size() {
+    for size in [100, 10000, 100000] {
+        let mut data: Vec<u8> = random_typed_vec(size);
+        test_roundtrip(CodecType::BZIP2, &data, None);
+    }
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn test_codec_xz_i64() {
+    use parquet::compression::sniff_codec;
+
+    let options = CodecOptionsBuilder::default().set_xz_preset(9).build();
+    let mut codec = create_codec(CodecType::XZ, &options).unwrap().unwrap();
+    let mut gzip = create_codec(CodecType::GZIP, &options).unwrap().unwrap();
+
+    let mut rng = thread_rng();
+    let data: Vec<i64> = (0..100_000).map(|i| i * 7 + rng.gen_range(0..100)).collect();
+    let data = ColumnData::new(&data);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    assert_eq!(sniff_codec(&compressed), Some(CodecType::XZ));
+
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, data);
+
+    let mut gzip_compressed = Vec::new();
+    gzip.compress(&data, &mut gzip_compressed).unwrap();
+    assert!(compressed.len() < gzip_compressed.len());
+
+    let options = CodecOptionsBuilder::default().set_xz_preset(10).build();
+    assert!(create_codec(CodecType::XZ, &options).is_err());
+}
+#[cfg(feature = "xz")]
+#[test]
+fn test_codec_xz_u8() {
+    test_codec_with_size::<u8>(CodecType::XZ);
+}
+#[cfg(feature = "xz")]
+#[test]
+fn test_codec_xz_without_size() {
+    for size in [100, 10000, 100000] {
+        let mut data: Vec<u8> = random_typed_vec(size);
+        test_roundtrip(CodecType::XZ, &data, None);
+    }
+}
+
 #[test]
 fn test_codec_qcom_u16() {
     test_codec_qcom_generic::<u16>(CodecType::QCOM);
@@ -287,4 +331,3692 @@ fn test_codec_qcom_f32() {
 #[test]
 fn test_codec_qcom_f64() {
     test_codec_qcom_generic::<f64>(CodecType::QCOM);
-}
\ No newline at end of file
+}
+#[test]
+fn test_codec_compress_vectored() {
+    let internal_data: Vec<i64> = random_typed_vec(300);
+
+    let chunks: Vec<ColumnData> =
+        internal_data.chunks(100).map(ColumnData::new).collect();
+    let chunk_refs: Vec<&ColumnData> = chunks.iter().collect();
+    let data = ColumnData::new(&internal_data);
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+
+    let mut vectored = Vec::new();
+    codec.compress_vectored(&chunk_refs, &mut vectored).expect("compress_vectored failed");
+
+    let mut combined = Vec::new();
+    codec.compress(&data, &mut combined).expect("compress failed");
+
+    assert_eq!(vectored, combined);
+
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    codec.decompress(&vectored, &mut decompressed, None).expect("decompress failed");
+    assert_eq!(data, decompressed);
+
+    // streaming codecs feed the chunks to their encoder, the others compress a copy
+    for codec_type in [
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::SNAPPY,
+        CodecType::QCOM,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let mut vectored = Vec::new();
+        codec.compress_vectored(&chunk_refs, &mut vectored).unwrap();
+        assert_eq!(codec.last_element_count(), internal_data.len(), "{:?}", codec_type);
+
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        let size = internal_data.len() * 8;
+        codec.decompress(&vectored, &mut decompressed, Some(size)).unwrap();
+        assert_eq!(data, decompressed, "{:?}", codec_type);
+    }
+
+    let mixed = ColumnData::new(&vec![1i32, 2]);
+    let err = codec
+        .compress_vectored(&[chunk_refs[0], &mixed], &mut Vec::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("Cannot extend a i64 column"), "{}", err);
+}
+
+#[test]
+fn test_codec_conversion_error_context() {
+    let data = ColumnData::VecU8(vec![1, 2, 3, 4, 5, 6, 7]);
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).expect("compress failed");
+
+    // 7 bytes can't be decoded as f64 values
+    let mut decompressed = ColumnData::new(&Vec::<f64>::new());
+    let err = codec
+        .decompress(&compressed, &mut decompressed, None)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("ZSTD decompress: conversion to f64 failed"), "{}", err);
+}
+
+#[test]
+fn test_codec_deterministic_output() {
+    let internal_data: Vec<i64> = random_typed_vec(1000);
+    let data = ColumnData::new(&internal_data);
+
+    let codec_options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(false)
+        .set_deterministic(true)
+        .build();
+
+    for c in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+        CodecType::QCOM,
+    ] {
+        let mut first = Vec::new();
+        create_codec(c, &codec_options).unwrap().unwrap()
+            .compress(&data, &mut first).expect("compress failed");
+
+        let mut second = Vec::new();
+        create_codec(c, &codec_options).unwrap().unwrap()
+            .compress(&data, &mut second).expect("compress failed");
+
+        assert_eq!(first, second, "{:?} output is not deterministic", c);
+    }
+}
+
+#[test]
+fn test_codec_recommend_codec() {
+    use parquet::compression::recommend_codec;
+
+    let ramp: Vec<i64> = (0..100_000i64).collect();
+    let data = ColumnData::new(&ramp);
+
+    let recommended = recommend_codec(&data, &CodecOptionsBuilder::default().build());
+    assert!(
+        matches!(recommended, CodecType::QCOM | CodecType::ZSTD),
+        "unexpected recommendation {:?}",
+        recommended
+    );
+}
+
+#[test]
+fn test_codec_frame_writer_reader() {
+    use parquet::compression::{FrameReader, FrameWriter};
+
+    let batches: Vec<ColumnData> = (0..3)
+        .map(|_| {
+            let internal_data: Vec<i32> = random_typed_vec(50);
+            ColumnData::new(&internal_data)
+        })
+        .collect();
+
+    let mut writer = FrameWriter::new(Vec::new(), create_test_codec(CodecType::LZ4_RAW));
+    for batch in &batches {
+        writer.write_batch(batch).expect("write_batch failed");
+    }
+    let log = writer.finish().expect("finish failed");
+
+    let reader = FrameReader::new(
+        log.as_slice(),
+        create_test_codec(CodecType::LZ4_RAW),
+        &ColumnData::new(&Vec::<i32>::new()),
+    );
+    let read_back: Vec<ColumnData> = reader.map(|batch| batch.expect("read failed")).collect();
+    assert_eq!(batches, read_back);
+
+    // a record header claiming 4 GiB is an error once the input ends, not an allocation
+    let mut truncated = (u32::MAX - 1).to_be_bytes().to_vec();
+    truncated.extend_from_slice(&u32::MAX.to_be_bytes());
+    truncated.extend_from_slice(&[1, 2, 3]);
+    let mut reader = FrameReader::new(
+        truncated.as_slice(),
+        create_test_codec(CodecType::LZ4_RAW),
+        &ColumnData::new(&Vec::<i32>::new()),
+    );
+    assert!(reader.next().unwrap().is_err());
+}
+
+#[test]
+fn test_codec_qcom_unprefixed_frame() {
+    use parquet::compression::QComCodec;
+
+    // buffers written before frames were length-prefixed hold one bare frame, as
+    // q_compress produces it
+    let values: Vec<i64> = random_typed_vec(100);
+    let legacy = q_compress::auto_compress(&values, q_compress::DEFAULT_COMPRESSION_LEVEL);
+
+    let mut codec = create_test_codec(CodecType::QCOM);
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    let n = codec.decompress(&legacy, &mut decompressed, None).unwrap();
+    assert_eq!(n, values.len());
+    assert_eq!(decompressed, ColumnData::new(&values));
+    assert_eq!(QComCodec::peek_count(&legacy, "i64").unwrap(), values.len());
+
+    // a bare frame is prefixed when concatenated with newer frames
+    let mut current = Vec::new();
+    codec.compress(&ColumnData::new(&values), &mut current).unwrap();
+    let merged = codec.concat_frames(&[legacy, current]).unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    codec.decompress(&merged, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, ColumnData::new(&[values.clone(), values].concat()));
+}
+
+#[test]
+fn test_codec_qcom_multiple_frames() {
+    let first: Vec<i64> = random_typed_vec(100);
+    let second: Vec<i64> = random_typed_vec(60);
+
+    let mut codec = create_test_codec(CodecType::QCOM);
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&first), &mut compressed).expect("compress failed");
+    codec.compress(&ColumnData::new(&second), &mut compressed).expect("compress failed");
+
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    let n = codec.decompress(&compressed, &mut decompressed, None).expect("decompress failed");
+
+    let expected: Vec<i64> = first.iter().chain(second.iter()).copied().collect();
+    assert_eq!(n, expected.len());
+    assert_eq!(decompressed, ColumnData::new(&expected));
+}
+
+#[test]
+fn test_convert_matches_big_endian_layout() {
+    // the conversions must be byte-identical to byteorder's big-endian layout
+    let values: Vec<f64> = vec![0.0, -1.5, f64::MAX, f64::MIN_POSITIVE, f64::INFINITY];
+    let data = ColumnData::VecF64(values.clone());
+
+    let mut bytes = Vec::new();
+    data.convert_to_u8(&mut bytes).unwrap();
+    let mut expected = vec![0u8; values.len() * 8];
+    BigEndian::write_f64_into(&values, &mut expected);
+    assert_eq!(bytes, expected);
+
+    let mut decoded = ColumnData::VecF64(Vec::new());
+    decoded.convert_from_u8(&bytes).unwrap();
+    assert_eq!(decoded, data);
+
+    // misaligned input must still decode correctly
+    let mut shifted = vec![0u8];
+    shifted.extend_from_slice(&bytes);
+    let mut decoded = ColumnData::VecF64(Vec::new());
+    decoded.convert_from_u8(&shifted[1..]).unwrap();
+    assert_eq!(decoded, data);
+
+    // on little-endian hosts with the bytemuck feature, the little-endian layout is
+    // converted in bulk and must be byte-identical to the per-element path
+    let mut bytes = Vec::new();
+    data.convert_to_u8_ordered(&mut bytes, true).unwrap();
+    let mut expected = vec![0u8; values.len() * 8];
+    LittleEndian::write_f64_into(&values, &mut expected);
+    assert_eq!(bytes, expected);
+
+    let mut decoded = ColumnData::VecF64(Vec::new());
+    decoded.convert_from_u8_ordered(&bytes, true).unwrap();
+    assert_eq!(decoded, data);
+    let mut shifted = vec![0u8];
+    shifted.extend_from_slice(&bytes);
+    let mut decoded = ColumnData::VecF64(Vec::new());
+    decoded.convert_from_u8_ordered(&shifted[1..], true).unwrap();
+    assert_eq!(decoded, data);
+
+    let ints: Vec<i32> = vec![0, -1, i32::MAX, i32::MIN, 0x01020304];
+    let mut bytes = Vec::new();
+    ColumnData::VecI32(ints.clone())
+        .convert_to_u8_ordered(&mut bytes, true)
+        .unwrap();
+    let mut expected = vec![0u8; ints.len() * 4];
+    LittleEndian::write_i32_into(&ints, &mut expected);
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn test_codec_decompress_with_size_hint() {
+    let values: Vec<i32> = random_typed_vec(2048);
+    let data = ColumnData::new(&values);
+    let uncompress_size = values.len() * std::mem::size_of::<i32>();
+
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).expect("compress failed");
+
+        // a size hint only pre-sizes the output, it must not change the result
+        let mut decompressed = ColumnData::new(&Vec::<i32>::new());
+        let n = codec
+            .decompress(&compressed, &mut decompressed, Some(uncompress_size))
+            .expect("decompress failed");
+        assert_eq!(n, uncompress_size);
+        assert_eq!(decompressed, data);
+    }
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_codec_encrypt_roundtrip() {
+    use parquet::compression::EncryptCodec;
+
+    let mut values: Vec<u32> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+
+    let mut codec = EncryptCodec::new(create_test_codec(CodecType::ZSTD), &[7u8; 32]);
+    let mut encrypted = Vec::new();
+    codec.compress(&data, &mut encrypted).expect("compress failed");
+
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    codec
+        .decompress(&encrypted, &mut decompressed, None)
+        .expect("decompress failed");
+    assert_eq!(decompressed, data);
+
+    // a codec holding a different key cannot read the buffer
+    let mut other = EncryptCodec::new(create_test_codec(CodecType::ZSTD), &[8u8; 32]);
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    assert!(other.decompress(&encrypted, &mut decompressed, None).is_err());
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_codec_encrypt_detects_tampering() {
+    use parquet::compression::EncryptCodec;
+
+    let data = ColumnData::new(&(0..256).collect::<Vec<u64>>());
+
+    let mut codec = EncryptCodec::new(create_test_codec(CodecType::ZSTD), &[7u8; 32]);
+    let mut encrypted = Vec::new();
+    codec.compress(&data, &mut encrypted).expect("compress failed");
+
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 0x01;
+
+    let mut decompressed = ColumnData::new(&Vec::<u64>::new());
+    let err = codec
+        .decompress(&encrypted, &mut decompressed, None)
+        .unwrap_err();
+    assert!(err.to_string().contains("authentication failed"), "{}", err);
+}
+
+#[test]
+fn test_codec_compress_validated() {
+    use parquet::compression::compress_validated;
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+
+    let data = ColumnData::new(&vec![10i32, 20, 30, 40]);
+    let mut compressed = Vec::new();
+    let n = compress_validated(codec.as_mut(), &data, &mut compressed, (0i32, 100i32))
+        .expect("compress failed");
+    assert_eq!(n, compressed.len());
+
+    let data = ColumnData::new(&vec![10i32, 20, 130, 40]);
+    let mut compressed = Vec::new();
+    let err = compress_validated(codec.as_mut(), &data, &mut compressed, (0i32, 100i32))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parquet error: Value 130 at index 2 is outside the valid range [0, 100]"
+    );
+    assert!(compressed.is_empty());
+
+    // bounds must share the column's element type
+    let err = compress_validated(codec.as_mut(), &data, &mut compressed, (0i64, 100i64))
+        .unwrap_err();
+    assert!(err.to_string().contains("does not match i32 column"), "{}", err);
+}
+
+#[test]
+fn test_codec_framing_overhead() {
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+        CodecType::QCOM,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let mut compressed = Vec::new();
+        codec
+            .compress(&ColumnData::new(&vec![42u32]), &mut compressed)
+            .expect("compress failed");
+        assert!(
+            compressed.len() >= codec.framing_overhead(),
+            "{:?}: {} bytes < framing overhead {}",
+            codec_type,
+            compressed.len(),
+            codec.framing_overhead()
+        );
+    }
+    assert_eq!(create_test_codec(CodecType::GZIP).framing_overhead(), 18);
+}
+
+#[test]
+fn test_convert_column_data_to_fields() {
+    use std::sync::Arc;
+
+    use parquet::basic::{ConvertedType, Type as PhysicalType};
+    use parquet::record::Field;
+    use parquet::schema::types::{ColumnDescriptor, ColumnPath, Type};
+
+    let date_type = Type::primitive_type_builder("date", PhysicalType::INT32)
+        .with_converted_type(ConvertedType::DATE)
+        .build()
+        .unwrap();
+    let descr = Arc::new(ColumnDescriptor::new(
+        Arc::new(date_type),
+        0,
+        0,
+        ColumnPath::from("date"),
+    ));
+
+    let days = vec![0i32, 18262, 19000];
+    let mut codec = create_test_codec(CodecType::QCOM);
+    let mut compressed = Vec::new();
+    codec
+        .compress(&ColumnData::new(&days), &mut compressed)
+        .expect("compress failed");
+    let mut decompressed = ColumnData::new(&Vec::<i32>::new());
+    codec
+        .decompress(&compressed, &mut decompressed, None)
+        .expect("decompress failed");
+
+    let fields = Field::convert_column_data(&descr, &decompressed).unwrap();
+    assert_eq!(
+        fields,
+        vec![Field::Date(0), Field::Date(18262), Field::Date(19000)]
+    );
+
+    // 64-bit values do not fit an INT32 column
+    let err = Field::convert_column_data(&descr, &ColumnData::new(&vec![1i64])).unwrap_err();
+    assert!(err.to_string().contains("physical type INT32"), "{}", err);
+
+    // u32 values are converted only while they fit in an i32
+    let fields =
+        Field::convert_column_data(&descr, &ColumnData::new(&vec![i32::MAX as u32]))
+            .unwrap();
+    assert_eq!(fields, vec![Field::Date(i32::MAX)]);
+    let err = Field::convert_column_data(&descr, &ColumnData::new(&vec![u32::MAX]))
+        .unwrap_err();
+    assert!(err.to_string().contains("does not fit in INT32"), "{}", err);
+}
+
+#[test]
+fn test_codec_verify_roundtrip() {
+    let options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(false)
+        .set_verify_roundtrip(true)
+        .build();
+
+    let mut values: Vec<f64> = random_typed_vec(1000);
+    values.push(f64::NAN);
+    let data = ColumnData::new(&values);
+
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+        CodecType::QCOM,
+    ] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec
+            .compress(&data, &mut compressed)
+            .unwrap_or_else(|e| panic!("{:?}: {}", codec_type, e));
+        assert!(!compressed.is_empty());
+    }
+}
+
+#[test]
+fn test_codec_verify_roundtrip_detects_mismatch() {
+    use parquet::compression::VerifyRoundtripCodec;
+
+    /// Stores values verbatim but drops the last one on decompress.
+    struct LossyCodec;
+
+    impl Codec for LossyCodec {
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output_buf_columndata: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> parquet::errors::Result<usize> {
+            let len = input_buf.len().saturating_sub(4);
+            output_buf_columndata.convert_from_u8(&input_buf[..len])?;
+            Ok(len)
+        }
+
+        fn compress(
+            &mut self,
+            input_buf_columndata: &ColumnData,
+            output_buf: &mut Vec<u8>,
+        ) -> parquet::errors::Result<()> {
+            input_buf_columndata.convert_to_u8(output_buf)
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(LossyCodec)
+        }
+    }
+
+    let mut codec = VerifyRoundtripCodec::new(Box::new(LossyCodec));
+    let mut compressed = vec![1, 2, 3];
+    let err = codec
+        .compress(&ColumnData::new(&vec![1u32, 2, 3]), &mut compressed)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Parquet error: roundtrip verification failed");
+    // nothing is left behind from the failed call
+    assert_eq!(compressed, vec![1, 2, 3]);
+
+    let err = codec
+        .compress_within(
+            &ColumnData::new(&vec![1u32, 2, 3]),
+            &mut compressed,
+            std::time::Duration::from_secs(1),
+        )
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Parquet error: roundtrip verification failed");
+    assert_eq!(compressed, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_codec_decompress_file() {
+    use std::io::Write;
+
+    // large enough to span several read chunks
+    let values: Vec<i64> = random_typed_vec(100_000);
+    let data = ColumnData::new(&values);
+
+    for codec_type in [
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::ZSTD,
+        CodecType::SNAPPY,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).expect("compress failed");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&compressed).unwrap();
+        file.flush().unwrap();
+
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        let n = codec
+            .decompress_file(file.path(), &mut decompressed, None)
+            .unwrap_or_else(|e| panic!("{:?}: {}", codec_type, e));
+        assert_eq!(n, values.len() * 8);
+        assert_eq!(decompressed, data, "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_compress_batch_auto() {
+    use parquet::compression::compress_batch_auto;
+
+    let ids: Vec<i64> = random_typed_vec(500);
+    let ids = ColumnData::new(&ids);
+    // strings travel as their UTF-8 bytes, which QCOM cannot compress
+    let names = ColumnData::VecU8("alice,bob,carol,dave".as_bytes().to_vec());
+
+    let options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(false)
+        .build();
+    let compressed =
+        compress_batch_auto(&[("id", &ids), ("name", &names)], &options).unwrap();
+
+    let codecs: Vec<_> = compressed
+        .iter()
+        .map(|(name, codec_type, _)| (name.as_str(), *codec_type))
+        .collect();
+    assert_eq!(codecs, vec![("id", CodecType::QCOM), ("name", CodecType::ZSTD)]);
+
+    for ((_, codec_type, bytes), original) in compressed.iter().zip([&ids, &names]) {
+        let mut codec = create_test_codec(*codec_type);
+        let mut decompressed = original.slice(0, 0);
+        codec.decompress(bytes, &mut decompressed, None).unwrap();
+        assert_eq!(&decompressed, original);
+    }
+}
+
+#[test]
+fn test_codec_decompress_unsupported_output_type() {
+    let mut codec = create_test_codec(CodecType::QCOM);
+
+    // the input is never looked at: the output type is rejected before decoding
+    let garbage = vec![0xffu8; 64];
+    let mut decompressed = ColumnData::new(&Vec::<u8>::new());
+    let err = codec
+        .decompress(&garbage, &mut decompressed, None)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parquet error: QCOM decompress: unsupported output type u8"
+    );
+    assert_eq!(decompressed, ColumnData::new(&Vec::<u8>::new()));
+}
+
+#[test]
+fn test_codec_compress_sparse() {
+    use parquet::compression::{compress_sparse, decompress_sparse};
+
+    // 95% zeros with scattered exceptions
+    let mut rng = thread_rng();
+    let values: Vec<i32> = (0..20_000)
+        .map(|i| if i % 20 == 7 { rng.gen_range(1..1_000_000) } else { 0 })
+        .collect();
+    let data = ColumnData::new(&values);
+    let options = CodecOptionsBuilder::default().build();
+
+    let sparse = compress_sparse(&data, 0i32, CodecType::ZSTD, &options).unwrap();
+    let raw_len = values.len() * std::mem::size_of::<i32>();
+    assert!(sparse.len() * 10 < raw_len, "sparse {} of {} bytes", sparse.len(), raw_len);
+
+    let decompressed = decompress_sparse(&sparse, 0i32, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(decompressed, data);
+
+    // -0.0 differs from a 0.0 default and must survive
+    let floats = ColumnData::new(&vec![0.0f64, -0.0, 0.0, 2.5]);
+    let sparse = compress_sparse(&floats, 0.0f64, CodecType::LZ4_RAW, &options).unwrap();
+    let decompressed = decompress_sparse(&sparse, 0.0f64, CodecType::LZ4_RAW, &options).unwrap();
+    match decompressed {
+        ColumnData::VecF64(x) => assert_eq!(
+            x.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+            vec![0.0f64, -0.0, 0.0, 2.5].iter().map(|v| v.to_bits()).collect::<Vec<_>>()
+        ),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // counts from a corrupt header are errors, not overflows
+    let mut corrupt = sparse.clone();
+    corrupt[..16].copy_from_slice(&[(u64::MAX / 2).to_be_bytes(); 2].concat());
+    assert!(decompress_sparse(&corrupt, 0.0f64, CodecType::LZ4_RAW, &options).is_err());
+    let mut corrupt = sparse;
+    corrupt[8..16].copy_from_slice(&5u64.to_be_bytes());
+    assert!(decompress_sparse(&corrupt, 0.0f64, CodecType::LZ4_RAW, &options).is_err());
+}
+
+#[test]
+fn test_codec_clone_codec() {
+    let values: Vec<u64> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+
+    let options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(false)
+        .set_verify_roundtrip(true)
+        .build();
+    for codec_type in [CodecType::ZSTD, CodecType::LZ4, CodecType::QCOM] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut clone = codec.clone_codec();
+
+        let mut expected = Vec::new();
+        codec.compress(&data, &mut expected).unwrap();
+        // the clone is independent and configured identically
+        let data = data.clone();
+        let handle = std::thread::spawn(move || {
+            let mut compressed = Vec::new();
+            clone.compress(&data, &mut compressed).unwrap();
+            compressed
+        });
+        assert_eq!(handle.join().unwrap(), expected, "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_compress_within() {
+    use std::time::Duration;
+
+    let values: Vec<i64> = random_typed_vec(50_000);
+    let data = ColumnData::new(&values);
+
+    for codec_type in [CodecType::ZSTD, CodecType::BROTLI, CodecType::GZIP] {
+        let mut codec = create_test_codec(codec_type);
+        for budget in [Duration::from_nanos(1), Duration::from_secs(60)] {
+            let mut compressed = vec![0xaa];
+            let n = codec
+                .compress_within(&data, &mut compressed, budget)
+                .expect("compress failed");
+            assert_eq!(n, compressed.len() - 1);
+
+            let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+            codec
+                .decompress(&compressed[1..], &mut decompressed, None)
+                .expect("decompress failed");
+            assert_eq!(decompressed, data, "{:?} within {:?}", codec_type, budget);
+        }
+    }
+
+    // timing-dependent levels are refused when output must be reproducible
+    let options = CodecOptionsBuilder::default().set_deterministic(true).build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let err = codec
+        .compress_within(&data, &mut Vec::new(), Duration::from_secs(1))
+        .unwrap_err();
+    assert!(err.to_string().contains("deterministic"), "{}", err);
+}
+
+#[test]
+fn test_codec_lz4_cross_implementation_fixtures() {
+    // A PLAIN encoded INT32 page compressed with liblz4 1.9.4 through the calls
+    // parquet-cpp makes for each layout: `Lz4HadoopCodec` (big endian decompressed
+    // and compressed sizes, then an `LZ4_compress_default` block), `Lz4FrameCodec`
+    // (`LZ4F_compressFrame` with zeroed preferences, so no checksums) and
+    // `Lz4Codec` for LZ4_RAW (a bare `LZ4_compress_default` block). All three must
+    // decode through the backward compatible LZ4 path of `create_codec`.
+    let expected: Vec<u8> = (0..256i32)
+        .flat_map(|i| ((i * 7) % 100).to_le_bytes())
+        .collect();
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/lz4");
+
+    let options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(true)
+        .build();
+    for fixture in ["lz4_hadoop.bin", "lz4_frame.bin", "lz4_raw.bin"] {
+        let compressed = std::fs::read(dir.join(fixture)).unwrap();
+        let mut codec = create_codec(CodecType::LZ4, &options).unwrap().unwrap();
+        let mut decompressed = ColumnData::VecU8(Vec::new());
+        let n = codec
+            .decompress(&compressed, &mut decompressed, Some(expected.len()))
+            .unwrap_or_else(|e| panic!("{}: {}", fixture, e));
+        assert_eq!(n, expected.len(), "{}", fixture);
+        assert_eq!(decompressed, ColumnData::VecU8(expected.clone()), "{}", fixture);
+    }
+}
+
+#[test]
+fn test_codec_qcom_peek_count() {
+    use parquet::compression::QComCodec;
+
+    let first: Vec<f32> = random_typed_vec(300);
+    let second: Vec<f32> = random_typed_vec(45);
+
+    let mut codec = create_test_codec(CodecType::QCOM);
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&first), &mut compressed).unwrap();
+    codec.compress(&ColumnData::new(&second), &mut compressed).unwrap();
+
+    let count = QComCodec::peek_count(&compressed, "f32").unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<f32>::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(count, decompressed.len());
+    assert_eq!(count, 345);
+
+    assert!(QComCodec::peek_count(&compressed, "u8").is_err());
+
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::VecI24(vec![-5, 0, 7]), &mut compressed).unwrap();
+    assert_eq!(QComCodec::peek_count(&compressed, "i24").unwrap(), 3);
+}
+
+#[test]
+fn test_codec_encoding_hint() {
+    use parquet::compression::EncodingHint;
+
+    // sorted timestamps with irregular gaps
+    let mut rng = thread_rng();
+    let mut ts = 1_600_000_000_000i64;
+    let sorted: Vec<i64> = (0..10_000)
+        .map(|_| {
+            ts += rng.gen_range(1..1000);
+            ts
+        })
+        .collect();
+    let sorted = ColumnData::new(&sorted);
+    let unsorted: Vec<i64> = random_typed_vec(10_000);
+    let unsorted = ColumnData::new(&unsorted);
+
+    let roundtrip = |codec_type, hint, data: &ColumnData| {
+        let options = CodecOptionsBuilder::default()
+            .set_backward_compatible_lz4(false)
+            .set_encoding_hint(hint)
+            .build();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        codec
+            .decompress(&compressed, &mut decompressed, Some(data.len() * 8))
+            .unwrap();
+        assert_eq!(&decompressed, data, "{:?} with {:?}", codec_type, hint);
+        compressed.len()
+    };
+
+    for codec_type in [CodecType::ZSTD, CodecType::LZ4_RAW, CodecType::SNAPPY] {
+        let plain = roundtrip(codec_type, EncodingHint::None, &sorted);
+        let delta = roundtrip(codec_type, EncodingHint::Sorted, &sorted);
+        assert!(delta < plain, "{:?}: delta {} vs plain {}", codec_type, delta, plain);
+
+        // a wrong hint costs ratio, never correctness
+        roundtrip(codec_type, EncodingHint::MostlySorted, &unsorted);
+    }
+    roundtrip(CodecType::QCOM, EncodingHint::Sorted, &sorted);
+    roundtrip(CodecType::QCOM, EncodingHint::Sorted, &unsorted);
+}
+
+#[test]
+fn test_codec_compress_list() {
+    use parquet::compression::{compress_list, decompress_list};
+    use parquet::data_type::ColumnData;
+
+    let lengths = [3usize, 0, 17, 1, 0, 0, 250, 42, 0];
+    let mut next = 0i32;
+    let lists: Vec<Vec<i32>> = lengths
+        .iter()
+        .map(|&n| {
+            (0..n)
+                .map(|_| {
+                    next += 7;
+                    next % 1000
+                })
+                .collect()
+        })
+        .collect();
+    let columns: Vec<ColumnData> = lists.iter().map(|l| ColumnData::new(l)).collect();
+    let element_type = ColumnData::new(&Vec::<i32>::new());
+    let options = CodecOptionsBuilder::default().build();
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW, CodecType::QCOM] {
+        let compressed = compress_list(&columns, codec_type, &options).unwrap();
+        let decompressed =
+            decompress_list(&compressed, &element_type, codec_type, &options).unwrap();
+        assert_eq!(decompressed, columns, "{:?}", codec_type);
+
+        // a list count from a corrupt header is an error, not an overflow
+        let mut corrupt = compressed.clone();
+        corrupt[..8].copy_from_slice(&(u64::MAX / 4).to_be_bytes());
+        assert!(decompress_list(&corrupt, &element_type, codec_type, &options).is_err());
+    }
+
+    // only empty lists, and no lists at all
+    let empties = vec![element_type.clone(); 4];
+    let compressed = compress_list(&empties, CodecType::ZSTD, &options).unwrap();
+    let decompressed =
+        decompress_list(&compressed, &element_type, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(decompressed, empties);
+
+    let compressed = compress_list(&[], CodecType::ZSTD, &options).unwrap();
+    let decompressed =
+        decompress_list(&compressed, &element_type, CodecType::ZSTD, &options).unwrap();
+    assert!(decompressed.is_empty());
+}
+
+#[test]
+fn test_codec_last_was_stored() {
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default()
+        .set_min_compression_ratio(Some(1.5))
+        .build();
+    let mut rng = thread_rng();
+    let noise: Vec<u64> = (0..4096).map(|_| rng.gen()).collect();
+    let noise = ColumnData::new(&noise);
+    let repeated = ColumnData::new(&vec![42u64; 4096]);
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        assert!(!codec.last_was_stored());
+        for (data, stored) in [(&noise, true), (&repeated, false)] {
+            let mut compressed = Vec::new();
+            codec.compress(data, &mut compressed).unwrap();
+            assert_eq!(codec.last_was_stored(), stored, "{:?}", codec_type);
+
+            let mut decompressed = ColumnData::new(&Vec::<u64>::new());
+            codec
+                .decompress(&compressed, &mut decompressed, Some(4096 * 8))
+                .unwrap();
+            assert_eq!(&decompressed, data, "{:?}", codec_type);
+        }
+    }
+}
+
+#[test]
+fn test_codec_24_bit_roundtrip() {
+    use parquet::data_type::ColumnData;
+
+    let signed = ColumnData::VecI24(vec![0, 1, -1, 8_388_607, -8_388_608, -12345, 4242]);
+    let unsigned = ColumnData::VecU24(vec![0, 1, 0x00FF_FFFF, 0x0080_0000, 12345]);
+
+    let mut bytes = Vec::new();
+    signed.convert_to_u8(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 7 * 3);
+    assert_eq!(&bytes[6..9], &[0xFF, 0xFF, 0xFF]);
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW, CodecType::QCOM] {
+        let mut codec = create_test_codec(codec_type);
+        for data in [&signed, &unsigned] {
+            let mut compressed = Vec::new();
+            codec.compress(data, &mut compressed).unwrap();
+            let mut decompressed = data.slice(0, 0);
+            codec
+                .decompress(&compressed, &mut decompressed, Some(data.len() * 3))
+                .unwrap();
+            assert_eq!(&decompressed, data, "{:?} {}", codec_type, data.typename());
+        }
+    }
+
+    // deltas wrap in 24-bit arithmetic
+    let mut delta = signed.clone();
+    delta.delta_encode();
+    delta.delta_decode();
+    assert_eq!(delta, signed);
+    let mut delta = unsigned.clone();
+    delta.delta_encode();
+    delta.delta_decode();
+    assert_eq!(delta, unsigned);
+
+    // values outside the 24-bit range are errors rather than panics or truncation
+    for data in [
+        ColumnData::VecU24(vec![1, 1 << 24]),
+        ColumnData::VecI24(vec![1, 1 << 23]),
+        ColumnData::VecI24(vec![-(1 << 23) - 1]),
+    ] {
+        let mut bytes = vec![7];
+        let err = data.convert_to_u8(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("does not fit in"), "{}", err);
+        assert_eq!(bytes, vec![7]);
+        let mut codec = create_test_codec(CodecType::ZSTD);
+        assert!(codec.compress(&data, &mut Vec::new()).is_err());
+    }
+}
+
+#[test]
+fn test_codec_options_from_env() {
+    use parquet::compression::{CodecOptions, EncodingHint};
+
+    // the only test touching PARQUET_* variables, so it cannot race with another
+    std::env::set_var("PARQUET_DETERMINISTIC", "true");
+    std::env::set_var("PARQUET_ENCODING_HINT", "mostly_sorted");
+    let options = CodecOptions::from_env().unwrap();
+    let expected = CodecOptionsBuilder::default()
+        .set_deterministic(true)
+        .set_encoding_hint(EncodingHint::MostlySorted)
+        .build();
+    assert_eq!(options, expected);
+
+    std::env::set_var("PARQUET_ENCODING_HINT", "shuffled");
+    let err = CodecOptions::from_env().unwrap_err();
+    assert!(err.to_string().contains("PARQUET_ENCODING_HINT"), "{}", err);
+
+    std::env::remove_var("PARQUET_DETERMINISTIC");
+    std::env::remove_var("PARQUET_ENCODING_HINT");
+    assert_eq!(CodecOptions::from_env().unwrap(), CodecOptions::default());
+}
+
+#[test]
+#[cfg(feature = "crc")]
+fn test_codec_frame_checksum() {
+    use parquet::compression::{FrameReader, FrameWriter};
+    use parquet::data_type::ColumnData;
+
+    let mut values: Vec<i64> = random_typed_vec(100_000);
+    let mut one_shot = flate2::Crc::new();
+    let mut bytes = Vec::new();
+    ColumnData::new(&values).convert_to_u8(&mut bytes).unwrap();
+    one_shot.update(&bytes);
+
+    let mut writer = FrameWriter::new(Vec::new(), create_test_codec(CodecType::ZSTD))
+        .with_checksum();
+    for chunk in values.chunks(1000) {
+        writer.write_batch(&ColumnData::new(chunk)).unwrap();
+    }
+    let stream = writer.finish().unwrap();
+    let trailer = &stream[stream.len() - 8..];
+    assert_eq!(&trailer[..4], &u32::MAX.to_be_bytes());
+    assert_eq!(&trailer[4..], &one_shot.sum().to_be_bytes());
+
+    let example = ColumnData::new(&Vec::<i64>::new());
+    let reader = FrameReader::new(&stream[..], create_test_codec(CodecType::ZSTD), &example)
+        .with_checksum();
+    let mut decoded = example.clone();
+    for batch in reader {
+        decoded.extend_from(&batch.unwrap()).unwrap();
+    }
+    assert_eq!(decoded, ColumnData::new(&values));
+
+    // a corrupted trailer fails verification, a missing one too
+    let mut corrupted = stream.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    for stream in [&corrupted[..], &stream[..stream.len() - 8]] {
+        let reader = FrameReader::new(stream, create_test_codec(CodecType::ZSTD), &example)
+            .with_checksum();
+        assert!(reader.collect::<Result<Vec<_>, _>>().is_err());
+    }
+
+    // readers without checksums stop at the trailer
+    let reader = FrameReader::new(&stream[..], create_test_codec(CodecType::ZSTD), &example);
+    assert_eq!(reader.count(), 100);
+}
+
+#[cfg(all(feature = "async", feature = "crc"))]
+#[tokio::test]
+async fn test_codec_frame_checksum_bit_flip() {
+    use parquet::compression::{decompress_async, FrameReader, FrameWriter};
+    use parquet::data_type::ColumnData;
+
+    // random bytes stay snappy literals, which decode whatever their bits
+    let bytes: Vec<u8> = random_typed_vec(100_000);
+    let mut writer = FrameWriter::new(Vec::new(), create_test_codec(CodecType::SNAPPY))
+        .with_checksum();
+    for chunk in bytes.chunks(10_000) {
+        writer.write_batch(&ColumnData::VecU8(chunk.to_vec())).unwrap();
+    }
+    let stream = writer.finish().unwrap();
+
+    let codec = create_test_codec(CodecType::SNAPPY);
+    let decompressed = decompress_async(&stream[..], Vec::new(), codec).await.unwrap();
+    assert_eq!(decompressed, bytes);
+
+    // flip a bit in the last byte of the first record
+    let compressed_size = u32::from_be_bytes(stream[..4].try_into().unwrap()) as usize;
+    let mut corrupted = stream.clone();
+    corrupted[8 + compressed_size - 1] ^= 1;
+
+    let codec = create_test_codec(CodecType::SNAPPY);
+    let err = decompress_async(&corrupted[..], Vec::new(), codec)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Checksum mismatch"), "{}", err);
+
+    let example = ColumnData::VecU8(Vec::new());
+    let codec = create_test_codec(CodecType::SNAPPY);
+    let reader = FrameReader::new(&corrupted[..], codec, &example).with_checksum();
+    let err = reader.collect::<Result<Vec<_>, _>>().unwrap_err();
+    assert!(err.to_string().contains("Checksum mismatch"), "{}", err);
+}
+
+#[test]
+fn test_codec_save_state() {
+    use parquet::compression::available_codecs;
+
+    let data: Vec<u8> = random_typed_vec(100_000);
+    let (first, second) = data.split_at(data.len() / 2);
+    let options = CodecOptionsBuilder::default()
+        .set_verify_roundtrip(true)
+        .build();
+    let resumable = [CodecType::GZIP, CodecType::BZIP2, CodecType::XZ, CodecType::ZSTD];
+    for codec_type in available_codecs() {
+        if !resumable.contains(&codec_type) {
+            continue;
+        }
+        // compress half the stream, then resume it with a new codec
+        let mut compressed = Vec::new();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        codec.compress_stream(&mut &first[..], &mut compressed).unwrap();
+        let state = codec.save_state().unwrap();
+        drop(codec);
+
+        let mut resumed = create_codec(codec_type, &options).unwrap().unwrap();
+        resumed.restore_state(&state).unwrap();
+        resumed.compress_stream(&mut &second[..], &mut compressed).unwrap();
+        assert_ne!(resumed.save_state().unwrap(), state, "{:?}", codec_type);
+
+        let mut decompressed = Vec::new();
+        resumed
+            .decompress_stream(&mut compressed.as_slice(), &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data, "{:?}", codec_type);
+
+        // the state only resumes a codec with the same configuration
+        let mut other = create_test_codec(CodecType::SNAPPY);
+        assert!(other.restore_state(&state).is_err());
+        let level = CodecOptionsBuilder::default().set_zstd_level(19).build();
+        let mut other = create_codec(CodecType::ZSTD, &level).unwrap().unwrap();
+        assert!(other.restore_state(&state).is_err(), "{:?}", codec_type);
+        let mut same = create_codec(codec_type, &options).unwrap().unwrap();
+        assert!(same.restore_state(&state[1..]).is_err(), "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_save_state_unsupported() {
+    // these codecs cannot compress streams, or their streams cannot be resumed by
+    // appending frames
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::LZ4_RAW,
+        CodecType::QCOM,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let err = codec.save_state().unwrap_err();
+        assert!(err.to_string().contains("not supported"), "{}", err);
+        let err = codec.restore_state(&[]).unwrap_err();
+        assert!(err.to_string().contains("not supported"), "{}", err);
+    }
+}
+
+#[test]
+fn test_codec_max_input_len() {
+    let lz4_hadoop = create_test_codec(CodecType::LZ4).max_input_len();
+    assert!(lz4_hadoop <= u32::MAX as usize);
+
+    let lz4_raw = create_test_codec(CodecType::LZ4_RAW).max_input_len();
+    assert!(lz4::block::compress_bound(lz4_raw).is_ok());
+    assert!(lz4::block::compress_bound(lz4_raw + 1).is_err());
+    assert_eq!(lz4_hadoop, lz4_raw);
+
+    let snappy = create_test_codec(CodecType::SNAPPY).max_input_len();
+    assert!(snappy < u32::MAX as usize);
+    assert_ne!(snap::raw::max_compress_len(snappy), 0);
+
+    for codec_type in [CodecType::GZIP, CodecType::BROTLI, CodecType::ZSTD] {
+        assert_eq!(create_test_codec(codec_type).max_input_len(), usize::MAX);
+    }
+}
+
+#[test]
+fn test_codec_allow_partial_decompress() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(100_000);
+    let data = ColumnData::new(&values);
+
+    for codec_type in [CodecType::GZIP, CodecType::BROTLI, CodecType::ZSTD] {
+        let options = CodecOptionsBuilder::default()
+            .set_allow_partial_decompress(true)
+            .build();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let truncated = &compressed[..compressed.len() / 2];
+
+        let mut partial = ColumnData::new(&Vec::<u32>::new());
+        codec.decompress(truncated, &mut partial, None).unwrap();
+        assert!(codec.last_was_truncated(), "{:?}", codec_type);
+        assert!(partial.len() > 0 && partial.len() < values.len(), "{:?}", codec_type);
+        assert_eq!(partial, data.slice(0, partial.len()), "{:?}", codec_type);
+
+        let mut full = ColumnData::new(&Vec::<u32>::new());
+        codec.decompress(&compressed, &mut full, None).unwrap();
+        assert!(!codec.last_was_truncated(), "{:?}", codec_type);
+        assert_eq!(full, data);
+
+        // truncation stays an error by default
+        let mut codec = create_test_codec(codec_type);
+        let mut output = ColumnData::new(&Vec::<u32>::new());
+        assert!(codec.decompress(truncated, &mut output, None).is_err(), "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_compress_with_schema() {
+    use parquet::basic::ConvertedType;
+    use parquet::compression::{read_schema, ColumnSchema};
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<i32> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+    let schema = ColumnSchema::new("event_date", ConvertedType::DATE);
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    let mut compressed = Vec::new();
+    let n = codec.compress_with_schema(&schema, &data, &mut compressed).unwrap();
+    assert_eq!(n, compressed.len());
+
+    assert_eq!(read_schema(&compressed).unwrap(), schema);
+    let mut decompressed = ColumnData::new(&Vec::<i32>::new());
+    codec
+        .decompress_with_schema(&compressed, &mut decompressed, Some(values.len() * 4))
+        .unwrap();
+    assert_eq!(decompressed, data);
+
+    // buffers without a schema header are rejected
+    let mut plain = Vec::new();
+    codec.compress(&data, &mut plain).unwrap();
+    assert!(read_schema(&plain).is_err());
+    assert!(read_schema(&compressed[..10]).is_err());
+}
+
+#[test]
+#[cfg(feature = "allocator")]
+fn test_codec_scratch_allocator() {
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+    use parquet::compression::ScratchAllocator;
+    use parquet::data_type::ColumnData;
+
+    struct CountingAllocator(Arc<AtomicUsize>);
+
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.fetch_add(layout.size(), Ordering::SeqCst);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    let allocated = Arc::new(AtomicUsize::new(0));
+    let options = CodecOptionsBuilder::default()
+        .set_backward_compatible_lz4(false)
+        .set_allocator(ScratchAllocator::new(CountingAllocator(allocated.clone())))
+        .build();
+    let mut values: Vec<i64> = random_typed_vec(10_000);
+    let data = ColumnData::new(&values);
+    let raw_len = values.len() * 8;
+
+    for codec_type in [CodecType::SNAPPY, CodecType::GZIP, CodecType::ZSTD, CodecType::LZ4] {
+        let before = allocated.load(Ordering::SeqCst);
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        // the serialized input went through the counting allocator
+        assert!(allocated.load(Ordering::SeqCst) - before >= raw_len, "{:?}", codec_type);
+
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        codec
+            .decompress(&compressed, &mut decompressed, Some(raw_len))
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    // codecs without the allocator leave it untouched
+    let before = allocated.load(Ordering::SeqCst);
+    let mut compressed = Vec::new();
+    create_test_codec(CodecType::ZSTD)
+        .compress(&data, &mut compressed)
+        .unwrap();
+    assert_eq!(allocated.load(Ordering::SeqCst), before);
+}
+
+#[test]
+fn test_codec_max_expansion_ratio() {
+    use parquet::data_type::ColumnData;
+
+    let mut codec = create_test_codec(CodecType::LZ4_RAW);
+    let ratio = codec.max_expansion_ratio().unwrap();
+    assert!(ratio.is_finite() && ratio > 1.0);
+
+    let data = ColumnData::new(&vec![0u32; 100_000]);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    assert!((data.len() * 4) as f64 <= compressed.len() as f64 * ratio);
+
+    assert_eq!(create_test_codec(CodecType::GZIP).max_expansion_ratio(), None);
+}
+
+#[test]
+fn test_codec_compress_complex() {
+    use parquet::compression::{compress_complex, decompress_complex};
+    use parquet::data_type::ColumnData;
+
+    let mut pairs: Vec<f64> = (0..5_000)
+        .flat_map(|i| {
+            let phase = i as f64 / 100.0;
+            [phase.cos(), phase.sin()]
+        })
+        .collect();
+    pairs.extend_from_slice(&[
+        f64::NAN,
+        -0.0,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::MIN_POSITIVE / 2.0,
+        f64::from_bits(0x7ff4_0000_0000_0001),
+    ]);
+    let data = ColumnData::VecF64(pairs.clone());
+    let options = CodecOptionsBuilder::default().build();
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::QCOM] {
+        for split_streams in [false, true] {
+            let compressed = compress_complex(&data, split_streams, codec_type, &options).unwrap();
+            let decompressed =
+                decompress_complex(&compressed, "complex128", codec_type, &options).unwrap();
+            let values = match decompressed {
+                ColumnData::VecF64(x) => x,
+                other => panic!("expected f64 pairs, got {}", other.typename()),
+            };
+            let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+            assert_eq!(bits(&values), bits(&pairs), "{:?} {}", codec_type, split_streams);
+
+            // a pair count from a corrupt header is an error, not an overflow
+            let mut corrupt = compressed.clone();
+            corrupt[..8].copy_from_slice(&(u64::MAX / 8).to_be_bytes());
+            let result = decompress_complex(&corrupt, "complex128", codec_type, &options);
+            assert!(result.is_err());
+        }
+    }
+
+    let odd = ColumnData::VecF64(vec![1.0, 2.0, 3.0]);
+    assert!(compress_complex(&odd, true, CodecType::ZSTD, &options).is_err());
+}
+
+#[test]
+fn test_codec_estimate_compress_duration() {
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW] {
+        let codec = create_test_codec(codec_type);
+        let small = codec.estimate_compress_duration(1 << 20);
+        let large = codec.estimate_compress_duration(10 << 20);
+        assert!(small > std::time::Duration::ZERO, "{:?}", codec_type);
+
+        let scale = large.as_secs_f64() / small.as_secs_f64();
+        assert!((9.0..=11.0).contains(&scale), "{:?}: {}", codec_type, scale);
+    }
+}
+
+#[test]
+fn test_codec_compress_parquet_column() {
+    use parquet::compression::compress_parquet_column;
+    use parquet::data_type::ColumnData;
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/sensor.parquet");
+    let options = CodecOptionsBuilder::default().build();
+
+    let (codec_type, compressed) =
+        compress_parquet_column(&path, "id", CodecType::ZSTD, &options).unwrap();
+    assert_eq!(codec_type, CodecType::ZSTD);
+    let mut ids = ColumnData::new(&Vec::<i64>::new());
+    create_test_codec(codec_type).decompress(&compressed, &mut ids, None).unwrap();
+    let expected: Vec<i64> = (0..500).map(|i| 1_000 + i * 3).collect();
+    assert_eq!(ids, ColumnData::new(&expected));
+
+    let (_, compressed) =
+        compress_parquet_column(&path, "valid", CodecType::SNAPPY, &options).unwrap();
+    let mut valid = ColumnData::new(&Vec::<u8>::new());
+    create_test_codec(CodecType::SNAPPY).decompress(&compressed, &mut valid, None).unwrap();
+    let expected: Vec<u8> = (0..500).map(|i| (i % 7 != 0) as u8).collect();
+    assert_eq!(valid, ColumnData::new(&expected));
+
+    assert!(compress_parquet_column(&path, "temperature", CodecType::QCOM, &options).is_ok());
+    assert!(compress_parquet_column(&path, "station", CodecType::ZSTD, &options).is_err());
+    assert!(compress_parquet_column(&path, "missing", CodecType::ZSTD, &options).is_err());
+}
+
+#[test]
+fn test_codec_options_validate() {
+    let options = CodecOptionsBuilder::default()
+        .set_min_compression_ratio(Some(1.5))
+        .set_allow_partial_decompress(true)
+        .build();
+    let err = options.validate().unwrap_err().to_string();
+    assert!(err.contains("allow_partial_decompress"), "{}", err);
+    assert!(create_codec(CodecType::ZSTD, &options).is_err());
+
+    for min_ratio in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let options = CodecOptionsBuilder::default()
+            .set_min_compression_ratio(Some(min_ratio))
+            .build();
+        let err = options.validate().unwrap_err().to_string();
+        assert!(err.contains("Minimum compression ratio"), "{}", err);
+    }
+
+    let options = CodecOptionsBuilder::default()
+        .set_min_compression_ratio(Some(1.5))
+        .set_verify_roundtrip(true)
+        .build();
+    assert!(options.validate().is_ok());
+}
+
+#[test]
+#[cfg(feature = "arrow")]
+fn test_codec_compress_record_batch() {
+    use std::sync::Arc;
+
+    use arrow::array::{Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use parquet::compression::{compress_record_batch, decompress_record_batch};
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("value", DataType::Float64, false),
+    ]));
+    let ids = Int32Array::from((0..10_000).collect::<Vec<i32>>());
+    let values = Float64Array::from((0..10_000).map(|i| i as f64 * 0.25).collect::<Vec<_>>());
+    let batch =
+        RecordBatch::try_new(schema.clone(), vec![Arc::new(ids), Arc::new(values)]).unwrap();
+    let options = CodecOptionsBuilder::default().build();
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::QCOM] {
+        let columns = compress_record_batch(&batch, codec_type, &options).unwrap();
+        let names: Vec<_> = columns.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["id", "value"]);
+        let decompressed =
+            decompress_record_batch(&columns, schema.clone(), codec_type, &options).unwrap();
+        assert_eq!(decompressed, batch, "{:?}", codec_type);
+    }
+
+    let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+    let batch =
+        RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec!["a", "b"]))]).unwrap();
+    assert!(compress_record_batch(&batch, CodecType::ZSTD, &options).is_err());
+}
+
+#[test]
+fn test_codec_float_component_split() {
+    use parquet::data_type::ColumnData;
+
+    let mut values: Vec<f64> = (0..50_000).map(|i| 100.0 + (i as f64 / 500.0).sin()).collect();
+    values.extend_from_slice(&[
+        f64::NAN,
+        -f64::NAN,
+        f64::from_bits(0x7ff8_dead_beef_0001),
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        -0.0,
+        f64::MIN_POSITIVE / 4.0,
+        f64::MAX,
+    ]);
+    let data = ColumnData::VecF64(values.clone());
+    let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+
+    for codec_type in [CodecType::SNAPPY, CodecType::GZIP, CodecType::ZSTD, CodecType::LZ4_RAW] {
+        let mut sizes = Vec::new();
+        for float_component_split in [false, true] {
+            let options = CodecOptionsBuilder::default()
+                .set_float_component_split(float_component_split)
+                .build();
+            let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+            let mut compressed = Vec::new();
+            codec.compress(&data, &mut compressed).unwrap();
+            sizes.push(compressed.len());
+
+            let mut decompressed = ColumnData::VecF64(Vec::new());
+            codec
+                .decompress(&compressed, &mut decompressed, Some(values.len() * 8))
+                .unwrap();
+            let decompressed = match decompressed {
+                ColumnData::VecF64(x) => x,
+                other => panic!("expected f64 values, got {}", other.typename()),
+            };
+            assert_eq!(bits(&decompressed), bits(&values), "{:?}", codec_type);
+        }
+        debug!(
+            "float component split {:?}: {} bytes whole, {} bytes split",
+            codec_type, sizes[0], sizes[1]
+        );
+    }
+
+    let options = CodecOptionsBuilder::default()
+        .set_float_component_split(true)
+        .build();
+    let mut floats: Vec<f32> = vec![1.5, f32::NAN, -0.0, f32::INFINITY];
+    floats.extend((0..1_000).map(|i| i as f32 / 3.0));
+    let data = ColumnData::VecF32(floats.clone());
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    let mut decompressed = ColumnData::VecF32(Vec::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    match decompressed {
+        ColumnData::VecF32(x) => assert_eq!(
+            x.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+            floats.iter().map(|v| v.to_bits()).collect::<Vec<_>>()
+        ),
+        other => panic!("expected f32 values, got {}", other.typename()),
+    }
+}
+
+#[test]
+fn test_codec_decompress_with_bytes() {
+    use parquet::compression::EncodingHint;
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<i32> = random_typed_vec(10_000);
+    let data = ColumnData::new(&values);
+
+    for (codec_type, hint) in [
+        (CodecType::SNAPPY, EncodingHint::None),
+        (CodecType::ZSTD, EncodingHint::None),
+        (CodecType::ZSTD, EncodingHint::Sorted),
+        (CodecType::QCOM, EncodingHint::None),
+    ] {
+        let options = CodecOptionsBuilder::default().set_encoding_hint(hint).build();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+
+        let mut decompressed = ColumnData::new(&Vec::<i32>::new());
+        let bytes = codec
+            .decompress_with_bytes(&compressed, &mut decompressed, Some(values.len() * 4))
+            .unwrap();
+        assert_eq!(decompressed, data, "{:?} {:?}", codec_type, hint);
+
+        let mut from_bytes = ColumnData::new(&Vec::<i32>::new());
+        from_bytes.convert_from_u8(&bytes).unwrap();
+        assert_eq!(from_bytes, data, "{:?} {:?}", codec_type, hint);
+    }
+}
+
+#[test]
+fn test_codec_retry_on_transient() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use parquet::compression::{is_transient, BlockSplitCodec, RetryCodec};
+    use parquet::data_type::ColumnData;
+    use parquet::errors::{ParquetError, Result};
+
+    /// Fails its first `failures` calls, leaving partial output behind, and counts
+    /// every call in `attempts`.
+    struct FlakyCodec {
+        failures: u32,
+        error_kind: std::io::ErrorKind,
+        attempts: Arc<AtomicU32>,
+    }
+
+    impl FlakyCodec {
+        fn fail(&mut self) -> Result<()> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            if self.failures == 0 {
+                return Ok(());
+            }
+            self.failures -= 1;
+            Err(std::io::Error::new(self.error_kind, "flaky").into())
+        }
+    }
+
+    impl Codec for FlakyCodec {
+        fn compress(&mut self, input: &ColumnData, output_buf: &mut Vec<u8>) -> Result<()> {
+            output_buf.push(0xff);
+            self.fail()?;
+            output_buf.pop();
+            input.convert_to_u8(output_buf)
+        }
+
+        fn decompress(
+            &mut self,
+            input_buf: &[u8],
+            output: &mut ColumnData,
+            _uncompress_size: Option<usize>,
+        ) -> Result<usize> {
+            output.convert_from_u8(&input_buf[..4])?;
+            self.fail()?;
+            output.convert_from_u8(&input_buf[4..])?;
+            Ok(input_buf.len())
+        }
+
+        fn clone_codec(&self) -> Box<dyn Codec> {
+            Box::new(FlakyCodec {
+                failures: self.failures,
+                error_kind: self.error_kind,
+                attempts: self.attempts.clone(),
+            })
+        }
+    }
+
+    let data = ColumnData::new(&[1u32, 2, 3, 4]);
+    let attempts = Arc::new(AtomicU32::new(0));
+    let flaky = |failures, error_kind| {
+        attempts.store(0, Ordering::SeqCst);
+        Box::new(FlakyCodec {
+            failures,
+            error_kind,
+            attempts: attempts.clone(),
+        })
+    };
+
+    // the first call fails transiently and the retry succeeds without the partial
+    // output of the failed call
+    let mut codec = RetryCodec::new(flaky(1, std::io::ErrorKind::Interrupted), 2);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(compressed.len(), 16);
+
+    let mut codec = RetryCodec::new(flaky(1, std::io::ErrorKind::TimedOut), 1);
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(decompressed, data);
+
+    // more transient failures than retries, and errors that are not transient
+    let mut codec = RetryCodec::new(flaky(3, std::io::ErrorKind::Interrupted), 2);
+    assert!(codec.compress(&data, &mut Vec::new()).is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    let mut codec = RetryCodec::new(flaky(1, std::io::ErrorKind::InvalidData), 2);
+    assert!(codec.compress(&data, &mut Vec::new()).is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    assert!(!is_transient(&ParquetError::General("corrupt".to_string())));
+
+    // codecs built with a retry count still roundtrip when nothing fails
+    let options = CodecOptionsBuilder::default().set_retry_on_transient(3).build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, data);
+
+    // and keep the blocks of seekable codecs
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let inner = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let mut codec = RetryCodec::new(Box::new(BlockSplitCodec::new(inner, 1024)), 2);
+    assert!(codec.supports_seek());
+    let values: Vec<u32> = (0..1000).collect();
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&values), &mut compressed).unwrap();
+    let mut block = ColumnData::new(&Vec::<u32>::new());
+    codec
+        .decompress_block(&compressed, 1, &mut block, Some(4000))
+        .unwrap();
+    assert_eq!(block, ColumnData::new(&values[256..512]));
+}
+
+#[test]
+fn test_codec_mixed_endianness() {
+    use parquet::compression::Endianness;
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default()
+        .set_float_endianness(Endianness::Little)
+        .set_int_endianness(Endianness::Big)
+        .build();
+    let floats: Vec<f64> = vec![1.5, -0.0, f64::INFINITY, 1e-300, 12345.678];
+    let ints: Vec<i64> = vec![1, -2, i64::MAX, i64::MIN, 0];
+
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+
+        let data = ColumnData::new(&floats);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::new(&Vec::<f64>::new());
+        let bytes = codec
+            .decompress_with_bytes(&compressed, &mut decompressed, Some(floats.len() * 8))
+            .unwrap();
+        assert_eq!(decompressed, data, "{:?}", codec_type);
+        let little: Vec<u8> = floats.iter().flat_map(|v| v.to_le_bytes()).collect();
+        assert_eq!(bytes, little, "{:?}", codec_type);
+
+        let data = ColumnData::new(&ints);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        let bytes = codec
+            .decompress_with_bytes(&compressed, &mut decompressed, Some(ints.len() * 8))
+            .unwrap();
+        assert_eq!(decompressed, data, "{:?}", codec_type);
+        let big: Vec<u8> = ints.iter().flat_map(|v| v.to_be_bytes()).collect();
+        assert_eq!(bytes, big, "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_last_element_count() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(100);
+    let data = ColumnData::new(&values);
+
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+        CodecType::QCOM,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        assert_eq!(codec.last_element_count(), 0, "{:?}", codec_type);
+        codec.compress(&data, &mut Vec::new()).unwrap();
+        assert_eq!(codec.last_element_count(), 100, "{:?}", codec_type);
+    }
+
+    let options = CodecOptionsBuilder::default()
+        .set_float_component_split(true)
+        .set_min_compression_ratio(Some(1.1))
+        .build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    codec.compress(&ColumnData::new(&vec![0.5f64; 100]), &mut Vec::new()).unwrap();
+    assert_eq!(codec.last_element_count(), 100);
+}
+
+#[test]
+fn test_codec_auto_block_split() {
+    use parquet::compression::BlockSplitCodec;
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(10_000);
+    let data = ColumnData::new(&values);
+    let size = values.len() * 4;
+
+    let mut codec = BlockSplitCodec::new(create_test_codec(CodecType::LZ4_RAW), 1000);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    assert_eq!(codec.last_element_count(), values.len());
+
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    let len = codec.decompress(&compressed, &mut decompressed, Some(size)).unwrap();
+    assert_eq!(len, size);
+    assert_eq!(decompressed, data);
+
+    // Inputs within the block limit keep the plain codec format.
+    let options = CodecOptionsBuilder::default().build();
+    let mut split = create_codec(CodecType::LZ4_RAW, &options).unwrap().unwrap();
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let mut plain = create_codec(CodecType::LZ4_RAW, &options).unwrap().unwrap();
+    let (mut split_out, mut plain_out) = (Vec::new(), Vec::new());
+    split.compress(&data, &mut split_out).unwrap();
+    plain.compress(&data, &mut plain_out).unwrap();
+    assert_eq!(split_out, plain_out);
+}
+
+#[test]
+fn test_codec_wrong_codec_hint() {
+    use parquet::compression::sniff_codec;
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+
+    let mut gzip = Vec::new();
+    create_test_codec(CodecType::GZIP).compress(&data, &mut gzip).unwrap();
+    assert_eq!(sniff_codec(&gzip), Some(CodecType::GZIP));
+
+    let mut output = ColumnData::new(&Vec::<u32>::new());
+    let err = create_test_codec(CodecType::ZSTD)
+        .decompress(&gzip, &mut output, Some(values.len() * 4))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("ZSTD decompress failed; buffer appears to be GZIP"),
+        "{}",
+        err
+    );
+
+    let mut zstd = Vec::new();
+    create_test_codec(CodecType::ZSTD).compress(&data, &mut zstd).unwrap();
+    assert_eq!(sniff_codec(&zstd), Some(CodecType::ZSTD));
+    assert_eq!(sniff_codec(&[0, 1, 2, 3]), None);
+}
+
+#[test]
+fn test_codec_qcom_deterministic() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u64> = random_typed_vec(10_000);
+    let data = ColumnData::new(&values);
+
+    let mut first = Vec::new();
+    create_test_codec(CodecType::QCOM).compress(&data, &mut first).unwrap();
+    let mut second = Vec::new();
+    create_test_codec(CodecType::QCOM).compress(&data, &mut second).unwrap();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_codec_concat_frames() {
+    use parquet::data_type::ColumnData;
+
+    let first: Vec<u32> = random_typed_vec(1000);
+    let second: Vec<u32> = random_typed_vec(500);
+    let combined: Vec<u32> = first.iter().chain(&second).copied().collect();
+
+    for codec_type in [CodecType::ZSTD, CodecType::GZIP, CodecType::QCOM] {
+        let mut codec = create_test_codec(codec_type);
+        let mut frames = vec![Vec::new(), Vec::new()];
+        codec.compress(&ColumnData::new(&first), &mut frames[0]).unwrap();
+        codec.compress(&ColumnData::new(&second), &mut frames[1]).unwrap();
+        let merged = codec.concat_frames(&frames).unwrap();
+
+        let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+        codec
+            .decompress(&merged, &mut decompressed, Some(combined.len() * 4))
+            .unwrap();
+        assert_eq!(decompressed, ColumnData::new(&combined), "{:?}", codec_type);
+    }
+
+    let codec = create_test_codec(CodecType::ZSTD);
+    assert!(codec.concat_frames(&[vec![1, 2, 3, 4]]).is_err());
+    for codec_type in [CodecType::SNAPPY, CodecType::LZ4_RAW] {
+        let codec = create_test_codec(codec_type);
+        assert!(codec.concat_frames(&[Vec::new()]).is_err(), "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_compress_union() {
+    use parquet::compression::{compress_union, decompress_union};
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default().build();
+    let values: Vec<ColumnData> = (0..300)
+        .map(|i| match i % 3 {
+            0 => ColumnData::VecU8(vec![i as u8]),
+            1 => ColumnData::VecI32(vec![-i * 1000]),
+            _ => ColumnData::VecF64(vec![i as f64 / 7.0]),
+        })
+        .collect();
+
+    let compressed = compress_union(&values, CodecType::SNAPPY, &options).unwrap();
+    let decompressed = decompress_union(&compressed, CodecType::SNAPPY, &options).unwrap();
+    assert_eq!(decompressed, values);
+
+    let empty = compress_union(&[], CodecType::SNAPPY, &options).unwrap();
+    assert!(decompress_union(&empty, CodecType::SNAPPY, &options)
+        .unwrap()
+        .is_empty());
+
+    let pair = [ColumnData::VecU16(vec![1, 2])];
+    assert!(compress_union(&pair, CodecType::SNAPPY, &options).is_err());
+
+    // element counts and sizes from a corrupt header are errors, not huge allocations
+    let huge = [(u64::MAX >> 4).to_be_bytes(), 0u64.to_be_bytes()].concat();
+    assert!(decompress_union(&huge, CodecType::SNAPPY, &options).is_err());
+    let mut huge = compressed.clone();
+    huge[8..16].copy_from_slice(&(u64::MAX >> 4).to_be_bytes());
+    assert!(decompress_union(&huge, CodecType::SNAPPY, &options).is_err());
+
+    // an unknown type tag errors instead of misreading the following bytes
+    let mut corrupt = [1u64.to_be_bytes(), 4u64.to_be_bytes()].concat();
+    let mut codec = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    codec
+        .compress(&ColumnData::VecU8(vec![200, 1, 2, 3]), &mut corrupt)
+        .unwrap();
+    let err = decompress_union(&corrupt, CodecType::SNAPPY, &options).unwrap_err();
+    assert!(err.to_string().contains("Unsupported union type tag 200"), "{}", err);
+
+    // tags are fixed by the format: 6 is i32 and 9 is f64
+    let mut tagged = vec![6];
+    tagged.extend_from_slice(&(-5i32).to_be_bytes());
+    tagged.push(9);
+    tagged.extend_from_slice(&2.5f64.to_be_bytes());
+    let mut fixed = [2u64.to_be_bytes(), (tagged.len() as u64).to_be_bytes()].concat();
+    codec.compress(&ColumnData::VecU8(tagged), &mut fixed).unwrap();
+    assert_eq!(
+        decompress_union(&fixed, CodecType::SNAPPY, &options).unwrap(),
+        vec![ColumnData::VecI32(vec![-5]), ColumnData::VecF64(vec![2.5])]
+    );
+}
+
+#[test]
+fn test_codec_context_prefix() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+    let size = values.len() * 4;
+
+    let options = CodecOptionsBuilder::default()
+        .set_context_prefix(Some(b"temperature".to_vec()))
+        .build();
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4_RAW] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        assert_eq!(codec.last_element_count(), values.len());
+
+        let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+        let len = codec
+            .decompress(&compressed, &mut decompressed, Some(size))
+            .unwrap();
+        assert_eq!(len, size);
+        assert_eq!(decompressed, data, "{:?}", codec_type);
+
+        // the prefix is part of the compressed stream
+        let mut plain = create_test_codec(codec_type);
+        let mut bytes = ColumnData::VecU8(Vec::new());
+        plain
+            .decompress(&compressed, &mut bytes, Some(size + 11))
+            .unwrap();
+        assert_eq!(bytes.slice(0, 11), ColumnData::VecU8(b"temperature".to_vec()));
+    }
+
+    let mut compressed = Vec::new();
+    create_test_codec(CodecType::ZSTD).compress(&data, &mut compressed).unwrap();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    assert!(codec.decompress(&compressed, &mut decompressed, Some(size)).is_err());
+}
+
+#[test]
+fn test_supported_typenames() {
+    use parquet::data_type::{supported_typenames, ColumnData};
+
+    let typenames = supported_typenames();
+    assert!(typenames.contains(&"u8") && typenames.contains(&"f64"));
+    for &typename in typenames {
+        let mut column = ColumnData::from_typename(typename).unwrap();
+        assert_eq!(column.typename(), typename);
+
+        let bytes: Vec<u8> = (1..=column.type_width() as u8 * 4).collect();
+        column.convert_from_u8(&bytes).unwrap();
+        assert_eq!(column.len(), 4, "{}", typename);
+        let mut roundtrip = Vec::new();
+        column.convert_to_u8(&mut roundtrip).unwrap();
+        assert_eq!(roundtrip, bytes, "{}", typename);
+    }
+    assert!(ColumnData::from_typename("f16").is_none());
+}
+
+#[test]
+fn test_codec_brotli_level() {
+    use parquet::data_type::ColumnData;
+
+    let data = ColumnData::VecI64((0..100000).map(|i| i * i % 1009).collect());
+    let options = CodecOptionsBuilder::default().build();
+    let compress = |options: &parquet::compression::CodecOptions| {
+        let mut codec = create_codec(CodecType::BROTLI, options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::VecI64(Vec::new());
+        codec.decompress(&compressed, &mut decompressed, None).unwrap();
+        assert_eq!(decompressed, data);
+        compressed
+    };
+
+    let default = compress(&options);
+    assert_eq!(default, compress(&options.with_brotli_level(1)));
+    let fastest = compress(&options.with_brotli_level(0));
+    let best = compress(&options.with_brotli_level(11));
+    assert!(best.len() < fastest.len());
+
+    let err = create_codec(CodecType::BROTLI, &options.with_brotli_level(12))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("BROTLI level"), "{}", err);
+}
+
+#[test]
+fn test_codec_compress_windows() {
+    use parquet::compression::{compress_windows, decompress_windows};
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default().build();
+    let values: Vec<i64> = random_typed_vec(1000);
+    let series = ColumnData::new(&values);
+
+    let full = compress_windows(&series, 100, 50, false, CodecType::ZSTD, &options).unwrap();
+    let diffed = compress_windows(&series, 100, 50, true, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(full.len(), 19);
+    assert_eq!(diffed.len(), 19);
+    let full_len: usize = full.iter().map(Vec::len).sum();
+    let diffed_len: usize = diffed.iter().map(Vec::len).sum();
+    assert!(diffed_len < full_len);
+
+    for compressed in [full, diffed] {
+        let windows = decompress_windows(&compressed, "i64", CodecType::ZSTD, &options).unwrap();
+        for (i, window) in windows.iter().enumerate() {
+            assert_eq!(window, &series.slice(i * 50, 100), "window {}", i);
+        }
+    }
+
+    // a window count from a corrupt header is an error, not an overflow
+    let mut corrupt = compress_windows(&series, 100, 50, false, CodecType::ZSTD, &options)
+        .unwrap()
+        .remove(0);
+    corrupt[1..9].copy_from_slice(&(u64::MAX / 4).to_be_bytes());
+    assert!(decompress_windows(&[corrupt], "i64", CodecType::ZSTD, &options).is_err());
+
+    assert!(compress_windows(&series, 100, 0, true, CodecType::ZSTD, &options).is_err());
+    let short = compress_windows(&series.slice(0, 10), 100, 50, true, CodecType::ZSTD, &options);
+    assert!(short.unwrap().is_empty());
+}
+
+#[test]
+fn test_codec_options_with() {
+    use parquet::compression::{EncodingHint, Endianness};
+
+    let base = CodecOptionsBuilder::default()
+        .set_deterministic(true)
+        .set_retry_on_transient(2)
+        .build();
+    let derived = base.with_encoding_hint(EncodingHint::Sorted);
+    assert_ne!(derived, base);
+    assert_eq!(derived.with_encoding_hint(EncodingHint::None), base);
+    assert_eq!(
+        derived,
+        CodecOptionsBuilder::default()
+            .set_deterministic(true)
+            .set_retry_on_transient(2)
+            .set_encoding_hint(EncodingHint::Sorted)
+            .build()
+    );
+
+    let little = base
+        .with_int_endianness(Endianness::Little)
+        .with_context_prefix(Some(b"id".to_vec()));
+    assert_eq!(
+        little,
+        CodecOptionsBuilder::default()
+            .set_deterministic(true)
+            .set_retry_on_transient(2)
+            .set_int_endianness(Endianness::Little)
+            .set_context_prefix(Some(b"id".to_vec()))
+            .build()
+    );
+    assert_eq!(base.clone(), base);
+}
+
+#[test]
+fn test_codec_decompress_and_sum() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<i64> = (0..10_000).map(|i| (i * 7919) % 100_003 - 50_000).collect();
+    let data = ColumnData::new(&values);
+    let size = values.len() * 8;
+
+    for codec_type in [CodecType::ZSTD, CodecType::LZ4_RAW, CodecType::QCOM] {
+        let mut codec = create_test_codec(codec_type);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        codec
+            .decompress(&compressed, &mut decompressed, Some(size))
+            .unwrap();
+        let expected = match decompressed {
+            ColumnData::VecI64(x) => x.iter().map(|&v| v as f64).sum::<f64>(),
+            _ => unreachable!(),
+        };
+        let sum = codec
+            .decompress_and_sum(&compressed, "i64", Some(size))
+            .unwrap();
+        assert_eq!(sum, expected, "{:?}", codec_type);
+    }
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&vec![0.25f32; 100]), &mut compressed).unwrap();
+    assert_eq!(codec.decompress_and_sum(&compressed, "f32", None).unwrap(), 25.0);
+    assert!(codec.decompress_and_sum(&compressed, "f16", None).is_err());
+}
+
+#[test]
+fn test_codec_zstd_dictionary() {
+    use parquet::data_type::ColumnData;
+
+    let values: Vec<u32> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+
+    // garbage behind the magic number of a trained dictionary
+    let mut garbage = vec![0x37, 0xa4, 0x30, 0xec];
+    garbage.extend_from_slice(&[0xff; 64]);
+    let options = CodecOptionsBuilder::default()
+        .set_zstd_dictionary(Some(garbage))
+        .build();
+    let err = create_codec(CodecType::ZSTD, &options).err().unwrap();
+    assert!(err.to_string().contains("Invalid ZSTD dictionary"), "{}", err);
+
+    let mut dictionary = Vec::new();
+    for value in &values[..256] {
+        dictionary.extend_from_slice(&value.to_be_bytes());
+    }
+    let options = options.with_zstd_dictionary(Some(dictionary));
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    let mut plain = Vec::new();
+    create_test_codec(CodecType::ZSTD).compress(&data, &mut plain).unwrap();
+    assert!(compressed.len() < plain.len());
+
+    let mut decompressed = ColumnData::new(&Vec::<u32>::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, data);
+}
+
+#[test]
+fn test_codec_content_hash() {
+    use parquet::data_type::ColumnData;
+
+    let mut values: Vec<u64> = random_typed_vec(1000);
+    let data = ColumnData::new(&values);
+    let size = values.len() * 8;
+
+    let mut zstd = create_test_codec(CodecType::ZSTD);
+    let mut gzip = create_test_codec(CodecType::GZIP);
+    let (mut zstd_buf, mut gzip_buf) = (Vec::new(), Vec::new());
+    zstd.compress(&data, &mut zstd_buf).unwrap();
+    gzip.compress(&data, &mut gzip_buf).unwrap();
+    assert_ne!(zstd_buf, gzip_buf);
+
+    let hash = zstd.content_hash(&zstd_buf, Some(size)).unwrap();
+    assert_eq!(gzip.content_hash(&gzip_buf, Some(size)).unwrap(), hash);
+    assert_eq!(zstd.content_hash(&zstd_buf, None).unwrap(), hash);
+
+    values[0] ^= 1;
+    let mut changed = Vec::new();
+    zstd.compress(&ColumnData::new(&values), &mut changed).unwrap();
+    assert_ne!(zstd.content_hash(&changed, Some(size)).unwrap(), hash);
+}
+
+#[test]
+fn test_codec_canonicalize_nan() {
+    use parquet::data_type::ColumnData;
+
+    let payload = f64::from_bits(0x7ff8_0000_dead_beef);
+    let negative = f64::from_bits(0xfff0_0000_0000_0001);
+    let first = ColumnData::VecF64(vec![1.5, f64::NAN, payload, -2.0]);
+    let second = ColumnData::VecF64(vec![1.5, negative, f64::NAN, -2.0]);
+
+    let options = CodecOptionsBuilder::default()
+        .set_canonicalize_nan(true)
+        .set_verify_roundtrip(true)
+        .build();
+    for codec_type in [CodecType::ZSTD, CodecType::SNAPPY, CodecType::QCOM] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        assert!(!codec.is_lossless());
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        codec.compress(&first, &mut a).unwrap();
+        codec.compress(&second, &mut b).unwrap();
+        assert_eq!(a, b, "{:?}", codec_type);
+
+        let mut c = Vec::new();
+        codec
+            .compress_within(&second, &mut c, std::time::Duration::from_secs(1))
+            .unwrap();
+        for compressed in [a, c] {
+            let mut decompressed = ColumnData::VecF64(Vec::new());
+            codec.decompress(&compressed, &mut decompressed, Some(32)).unwrap();
+            match decompressed {
+                ColumnData::VecF64(x) => {
+                    assert_eq!(x[1].to_bits(), f64::NAN.to_bits());
+                    assert_eq!(x[2].to_bits(), f64::NAN.to_bits());
+                    assert_eq!((x[0], x[3]), (1.5, -2.0));
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    assert!(codec.is_lossless());
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    codec.compress(&first, &mut a).unwrap();
+    codec.compress(&second, &mut b).unwrap();
+    assert_ne!(a, b);
+}
+
+#[test]
+fn test_available_codecs() {
+    use parquet::compression::{available_codecs, is_compiled_in};
+
+    let options = CodecOptionsBuilder::default().build();
+    let codecs = available_codecs();
+    assert!(!codecs.contains(&CodecType::UNCOMPRESSED));
+    for codec_type in &codecs {
+        assert!(is_compiled_in(*codec_type));
+        assert!(create_codec(*codec_type, &options).unwrap().is_some());
+    }
+    assert!(is_compiled_in(CodecType::UNCOMPRESSED));
+    assert!(!is_compiled_in(CodecType::LZO));
+}
+
+#[test]
+fn test_codec_compress_scheme() {
+    use parquet::compression::{compress_scheme, decompress_scheme, TransformId};
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default().build();
+    let ints = ColumnData::VecI64((0..1000).map(|i| 1_000_000 + i * 3).collect());
+    let floats = ColumnData::VecF64((0..1000).map(|i| i as f64 / 8.0).collect());
+    let runs = ColumnData::VecI64((0..1000).map(|i| i / 250).collect());
+
+    for (transform, codec, data) in [
+        (TransformId::Identity, CodecType::SNAPPY, &ints),
+        (TransformId::Identity, CodecType::ZSTD, &floats),
+        (TransformId::Delta, CodecType::ZSTD, &ints),
+        (TransformId::Delta, CodecType::LZ4_RAW, &ints),
+        (TransformId::FloatSplit, CodecType::GZIP, &floats),
+        (TransformId::ByteSwap, CodecType::BROTLI, &ints),
+        (TransformId::ZigZag, CodecType::SNAPPY, &ints),
+        (TransformId::Xor, CodecType::ZSTD, &floats),
+        (TransformId::ByteSplit, CodecType::LZ4_RAW, &floats),
+        (TransformId::Rle, CodecType::LZ4_RAW, &ints),
+        (TransformId::Rle, CodecType::GZIP, &runs),
+    ] {
+        let compressed = compress_scheme(transform, codec, data, &options).unwrap();
+        assert_eq!(TransformId::from_id(compressed[0]), Some(transform));
+        let size = data.len() * 8;
+        let decompressed =
+            decompress_scheme(&compressed, data.typename(), Some(size), &options).unwrap();
+        assert_eq!(&decompressed, data, "{:?} + {:?}", transform, codec);
+    }
+
+    let delta = compress_scheme(TransformId::Delta, CodecType::ZSTD, &ints, &options).unwrap();
+    let plain = compress_scheme(TransformId::Identity, CodecType::ZSTD, &ints, &options).unwrap();
+    assert!(delta.len() < plain.len());
+
+    // runs of 250 equal values keep 4 values and 4 run lengths
+    let rle = compress_scheme(TransformId::Rle, CodecType::ZSTD, &runs, &options).unwrap();
+    let plain = compress_scheme(TransformId::Identity, CodecType::ZSTD, &runs, &options).unwrap();
+    assert!(rle.len() < plain.len());
+
+    let mut corrupt = delta;
+    corrupt[0] = 200;
+    assert!(decompress_scheme(&corrupt, "i64", None, &options).is_err());
+    assert!(compress_scheme(TransformId::Identity, CodecType::UNCOMPRESSED, &ints, &options)
+        .is_err());
+}
+
+#[test]
+fn test_codec_warn_on_precompressed() {
+    use std::sync::Mutex;
+
+    use log::{Log, Metadata, Record};
+    use parquet::data_type::ColumnData;
+
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct CaptureLogger;
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.level() <= Level::Warn
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    log::set_logger(&CaptureLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+    let warnings = || {
+        WARNINGS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|w| w.contains("already looks"))
+            .count()
+    };
+
+    let data: Vec<u8> = random_typed_vec(10000);
+    let mut gzip = create_test_codec(CodecType::GZIP);
+    let mut gzipped = Vec::new();
+    gzip.compress(&ColumnData::VecU8(data.clone()), &mut gzipped).unwrap();
+
+    let options = CodecOptionsBuilder::default()
+        .set_warn_on_precompressed(true)
+        .build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::VecU8(data), &mut compressed).unwrap();
+    assert_eq!(warnings(), 0);
+
+    compressed.clear();
+    codec.compress(&ColumnData::VecU8(gzipped.clone()), &mut compressed).unwrap();
+    assert_eq!(warnings(), 1);
+    assert!(WARNINGS.lock().unwrap().iter().any(|w| w.contains("GZIP")));
+
+    // the data is still compressed
+    let mut decompressed = ColumnData::VecU8(Vec::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, ColumnData::VecU8(gzipped.clone()));
+
+    compressed.clear();
+    codec
+        .compress_within(
+            &ColumnData::VecU8(gzipped.clone()),
+            &mut compressed,
+            std::time::Duration::from_secs(1),
+        )
+        .unwrap();
+    assert_eq!(warnings(), 2);
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    compressed.clear();
+    codec.compress(&ColumnData::VecU8(gzipped), &mut compressed).unwrap();
+    assert_eq!(warnings(), 2);
+}
+
+#[test]
+fn test_codec_config_fingerprint() {
+    use parquet::compression::Endianness;
+
+    let fingerprint = |codec_type: CodecType, options: &parquet::compression::CodecOptions| {
+        create_codec(codec_type, options)
+            .unwrap()
+            .unwrap()
+            .config_fingerprint()
+    };
+    let options = CodecOptionsBuilder::default().build();
+    let zstd = fingerprint(CodecType::ZSTD, &options);
+
+    // identical configurations, also across clones
+    assert_eq!(zstd, fingerprint(CodecType::ZSTD, &options));
+    let codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    assert_eq!(codec.clone_codec().config_fingerprint(), zstd);
+
+    // any codec or option change
+    let mut fingerprints = vec![
+        zstd,
+        fingerprint(CodecType::GZIP, &options),
+        fingerprint(CodecType::BROTLI, &options),
+        fingerprint(CodecType::SNAPPY, &options),
+        fingerprint(CodecType::LZ4_RAW, &options),
+        fingerprint(CodecType::ZSTD, &options.with_deterministic(true)),
+        fingerprint(CodecType::ZSTD, &options.with_zstd_dictionary(Some(b"abc".to_vec()))),
+        fingerprint(CodecType::ZSTD, &options.with_zstd_dictionary(Some(b"abd".to_vec()))),
+        fingerprint(CodecType::ZSTD, &options.with_int_endianness(Endianness::Little)),
+        fingerprint(CodecType::ZSTD, &options.with_float_endianness(Endianness::Little)),
+        fingerprint(CodecType::ZSTD, &options.with_min_compression_ratio(Some(1.5))),
+        fingerprint(CodecType::ZSTD, &options.with_context_prefix(Some(b"ctx".to_vec()))),
+        fingerprint(CodecType::ZSTD, &options.with_canonicalize_nan(true)),
+    ];
+    let len = fingerprints.len();
+    fingerprints.sort_unstable();
+    fingerprints.dedup();
+    assert_eq!(fingerprints.len(), len);
+}
+
+#[test]
+fn test_codec_max_output_size() {
+    use parquet::data_type::ColumnData;
+    use rand::RngCore;
+
+    let mut random = vec![0u8; 4096];
+    thread_rng().fill_bytes(&mut random);
+    let random = ColumnData::VecU8(random);
+    let zeros = ColumnData::VecU8(vec![0u8; 4096]);
+
+    for codec_type in [CodecType::SNAPPY, CodecType::GZIP, CodecType::ZSTD] {
+        let options = CodecOptionsBuilder::default()
+            .set_max_output_size(Some(1024))
+            .build();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = vec![7u8; 3];
+        let err = codec.compress(&random, &mut compressed).unwrap_err();
+        assert!(err.to_string().contains("exceeds max_output_size"), "{}", err);
+        assert_eq!(compressed, vec![7u8; 3]);
+
+        let budget = std::time::Duration::from_secs(1);
+        let err = codec
+            .compress_within(&random, &mut compressed, budget)
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeds max_output_size"), "{}", err);
+        assert_eq!(compressed, vec![7u8; 3]);
+
+        // compressible data still fits
+        codec.compress(&zeros, &mut compressed).unwrap();
+        assert!(compressed.len() - 3 <= 1024);
+    }
+}
+
+#[test]
+fn test_codec_zstd_level() {
+    use parquet::data_type::ColumnData;
+
+    let data = ColumnData::VecI64((0..100000).map(|i| i * i % 1009).collect());
+    let compress = |options: &parquet::compression::CodecOptions| {
+        let mut codec = create_codec(CodecType::ZSTD, options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::VecI64(Vec::new());
+        codec.decompress(&compressed, &mut decompressed, None).unwrap();
+        assert_eq!(decompressed, data);
+        compressed
+    };
+
+    let options = CodecOptionsBuilder::default().build();
+    let default = compress(&options);
+    assert_eq!(default, compress(&options.with_zstd_level(1)));
+    let strong = compress(&CodecOptionsBuilder::default().set_zstd_level(19).build());
+    assert!(strong.len() < default.len());
+
+    let fingerprint = |options| {
+        create_codec(CodecType::ZSTD, &options)
+            .unwrap()
+            .unwrap()
+            .config_fingerprint()
+    };
+    assert_ne!(fingerprint(options.clone()), fingerprint(options.with_zstd_level(19)));
+
+    for level in [0, 23, -1] {
+        let err = create_codec(CodecType::ZSTD, &options.with_zstd_level(level))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("ZSTD level"), "{}", err);
+        let err = CodecOptionsBuilder::default()
+            .set_zstd_level(level)
+            .try_build()
+            .unwrap_err();
+        assert!(err.to_string().contains("ZSTD level"), "{}", err);
+    }
+}
+
+#[test]
+fn test_endianness_for_target() {
+    use parquet::compression::{endianness_for_target, Endianness};
+
+    for target in [
+        "x86_64-unknown-linux-gnu",
+        "i686-pc-windows-msvc",
+        "aarch64-apple-darwin",
+        "armv7-unknown-linux-gnueabihf",
+        "riscv64gc-unknown-linux-gnu",
+        "wasm32-unknown-unknown",
+        "mipsel-unknown-linux-gnu",
+        "powerpc64le-unknown-linux-gnu",
+    ] {
+        assert_eq!(endianness_for_target(target), Endianness::Little, "{}", target);
+    }
+    for target in [
+        "mips-unknown-linux-gnu",
+        "powerpc-unknown-linux-gnu",
+        "powerpc64-unknown-linux-gnu",
+        "s390x-unknown-linux-gnu",
+        "sparc64-unknown-linux-gnu",
+        "aarch64_be-unknown-linux-gnu",
+        "armebv7r-none-eabi",
+        "unknown-target",
+    ] {
+        assert_eq!(endianness_for_target(target), Endianness::Big, "{}", target);
+    }
+}
+
+#[test]
+fn test_codec_bit_pack_width() {
+    use parquet::data_type::ColumnData;
+
+    let mut rng = thread_rng();
+    let values = ColumnData::VecU32((0..10000).map(|_| rng.gen_range(0..4096)).collect());
+    let options = CodecOptionsBuilder::default().build();
+    let packed_options = options.with_bit_pack_width(Some(12));
+
+    for codec_type in [CodecType::ZSTD, CodecType::SNAPPY, CodecType::LZ4_RAW] {
+        let mut plain = Vec::new();
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        codec.compress(&values, &mut plain).unwrap();
+
+        let mut packed = Vec::new();
+        let mut codec = create_codec(codec_type, &packed_options).unwrap().unwrap();
+        codec.compress(&values, &mut packed).unwrap();
+        assert!(packed.len() < plain.len(), "{:?}", codec_type);
+
+        let mut decompressed = ColumnData::VecU32(Vec::new());
+        codec
+            .decompress(&packed, &mut decompressed, Some(values.len() * 4))
+            .unwrap();
+        assert_eq!(decompressed, values);
+    }
+
+    // signed values round-trip in two's complement
+    let signed = ColumnData::VecI16(vec![-2048, -1, 0, 1, 2047, -77]);
+    let mut codec = create_codec(CodecType::ZSTD, &packed_options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&signed, &mut compressed).unwrap();
+    let mut decompressed = ColumnData::VecI16(Vec::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, signed);
+
+    for out_of_range in [
+        ColumnData::VecU32(vec![1, 2, 4096]),
+        ColumnData::VecI16(vec![-2049]),
+        ColumnData::VecI16(vec![2048]),
+    ] {
+        let err = codec.compress(&out_of_range, &mut compressed).unwrap_err();
+        assert!(err.to_string().contains("does not fit in 12 bits"), "{}", err);
+    }
+    for width in [0, 65] {
+        assert!(create_codec(CodecType::ZSTD, &options.with_bit_pack_width(Some(width)))
+            .is_err());
+    }
+}
+
+#[test]
+fn test_codec_gzip_level() {
+    use parquet::data_type::ColumnData;
+
+    let data = ColumnData::VecI64((0..100000).map(|i| i * i % 1009).collect());
+    let options = CodecOptionsBuilder::default().build();
+    let compress = |options: &parquet::compression::CodecOptions| {
+        let mut codec = create_codec(CodecType::GZIP, options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::VecI64(Vec::new());
+        codec.decompress(&compressed, &mut decompressed, None).unwrap();
+        assert_eq!(decompressed, data);
+        compressed
+    };
+
+    let default = compress(&options);
+    assert_eq!(default, compress(&options.with_gzip_level(6)));
+    let stored = compress(&options.with_gzip_level(0));
+    let fast = compress(&options.with_gzip_level(1));
+    let best = compress(&options.with_gzip_level(9));
+    assert!(stored.len() > data.len() * 8);
+    assert!(best.len() < stored.len() && fast.len() < stored.len());
+    assert_ne!(fast, best);
+
+    let err = create_codec(CodecType::GZIP, &options.with_gzip_level(10))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("GZIP level"), "{}", err);
+    let err = CodecOptionsBuilder::default()
+        .set_gzip_level(10)
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("GZIP level"), "{}", err);
+}
+
+#[test]
+fn test_codec_decompress_view() {
+    use parquet::data_type::ColumnData;
+
+    let options = CodecOptionsBuilder::default()
+        .set_verify_roundtrip(true)
+        .build();
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+    ] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let first: Vec<u8> = random_typed_vec(10000);
+        let second = vec![42u8; 300];
+        let mut compressed_first = Vec::new();
+        codec
+            .compress(&ColumnData::VecU8(first.clone()), &mut compressed_first)
+            .unwrap();
+        let mut compressed_second = Vec::new();
+        codec
+            .compress(&ColumnData::VecU8(second.clone()), &mut compressed_second)
+            .unwrap();
+
+        let view = codec
+            .decompress_view(&compressed_first, Some(first.len()))
+            .unwrap();
+        assert_eq!(view, &first[..], "{:?}", codec_type);
+        let view = codec
+            .decompress_view(&compressed_second, Some(second.len()))
+            .unwrap();
+        assert_eq!(view, &second[..], "{:?}", codec_type);
+    }
+
+    // wrappers that rewrite the bytes have no view
+    let options = CodecOptionsBuilder::default()
+        .set_context_prefix(Some(b"ctx".to_vec()))
+        .build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    assert!(codec.decompress_view(&[], None).is_err());
+}
+
+#[test]
+fn test_codec_endianness() {
+    use parquet::compression::Endianness;
+    use parquet::data_type::ColumnData;
+
+    let values = ColumnData::VecU32(vec![0x0102_0304, 7, u32::MAX]);
+    let floats = ColumnData::VecF64(vec![1.5, -0.25]);
+    let bytes_of = |endianness: Endianness, values: &ColumnData| {
+        let options = CodecOptionsBuilder::default()
+            .set_endianness(endianness)
+            .build();
+        let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(values, &mut compressed).unwrap();
+
+        let mut decompressed = values.slice(0, 0);
+        codec.decompress(&compressed, &mut decompressed, None).unwrap();
+        assert_eq!(&decompressed, values);
+
+        // the payload bytes, as a reader without byte order conversion sees them
+        let mut bytes = ColumnData::VecU8(Vec::new());
+        create_test_codec(CodecType::ZSTD)
+            .decompress(&compressed, &mut bytes, None)
+            .unwrap();
+        match bytes {
+            ColumnData::VecU8(x) => x,
+            _ => unreachable!(),
+        }
+    };
+
+    assert_eq!(bytes_of(Endianness::Little, &values)[..4], [4, 3, 2, 1]);
+    assert_eq!(bytes_of(Endianness::Big, &values)[..4], [1, 2, 3, 4]);
+    assert_eq!(
+        bytes_of(Endianness::Native, &values)[..4],
+        0x0102_0304u32.to_ne_bytes()
+    );
+    assert_eq!(bytes_of(Endianness::Little, &floats)[..8], 1.5f64.to_le_bytes());
+    assert_eq!(
+        CodecOptionsBuilder::default().build(),
+        CodecOptionsBuilder::default()
+            .set_endianness(Endianness::Big)
+            .build()
+    );
+}
+
+#[test]
+fn test_codec_delta_overflow() {
+    use parquet::compression::{
+        available_codecs, compress_scheme, decompress_scheme, EncodingHint, TransformCodec,
+        TransformId,
+    };
+    use parquet::data_type::ColumnData;
+
+    // jumps between the extremes overflow i16 differences, which are kept as i32
+    let values = ColumnData::VecI16(vec![i16::MIN, i16::MAX, i16::MIN, 0, i16::MAX, -1, 1]);
+    let mut deltas = values.clone();
+    assert!(deltas.delta_encode_promoted());
+    let promoted = vec![-32768, 65535, -65535, 32768, 32767, -32768, 2];
+    assert_eq!(deltas, ColumnData::VecI32(promoted));
+    let mut narrowed = ColumnData::VecI16(Vec::new());
+    deltas.delta_decode();
+    narrowed.extend_narrowed(&deltas).unwrap();
+    assert_eq!(narrowed, values);
+
+    let options = CodecOptionsBuilder::default()
+        .set_encoding_hint(EncodingHint::MostlySorted)
+        .build();
+    let available = available_codecs();
+    for codec_type in [CodecType::ZSTD, CodecType::SNAPPY, CodecType::GZIP, CodecType::LZ4_RAW] {
+        if !available.contains(&codec_type) {
+            continue;
+        }
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&values, &mut compressed).unwrap();
+        assert_eq!(compressed[0], 4, "{:?}", codec_type);
+        let mut decompressed = ColumnData::VecI16(Vec::new());
+        codec.decompress(&compressed, &mut decompressed, Some(14)).unwrap();
+        assert_eq!(decompressed, values, "{:?}", codec_type);
+
+        // a width that is neither i16 nor its promotion is rejected
+        compressed[0] = 8;
+        let err = codec
+            .decompress(&compressed, &mut ColumnData::VecI16(Vec::new()), Some(14))
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot decode into i16"), "{}", err);
+
+        // differences that fit keep the element type
+        let small = ColumnData::VecI16(vec![-5, 100, 7, 7, -300]);
+        let mut compressed = Vec::new();
+        codec.compress(&small, &mut compressed).unwrap();
+        assert_eq!(compressed[0], 2, "{:?}", codec_type);
+        let mut decompressed = ColumnData::VecI16(Vec::new());
+        codec.decompress(&compressed, &mut decompressed, Some(10)).unwrap();
+        assert_eq!(decompressed, small, "{:?}", codec_type);
+    }
+
+    // unsigned columns promote when a difference does not fit the signed type
+    let bytes = ColumnData::VecU8(vec![0, 255, 0, 128, 127]);
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut compressed = Vec::new();
+    codec.compress(&bytes, &mut compressed).unwrap();
+    assert_eq!(compressed[0], 2);
+    let mut decompressed = ColumnData::VecU8(Vec::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, bytes);
+
+    // transforms after the delta apply to the promoted values
+    let options = CodecOptionsBuilder::default().build();
+    let inner = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let transforms = vec![TransformId::Xor, TransformId::Delta, TransformId::ZigZag];
+    let mut codec = TransformCodec::new(inner, transforms);
+    let mut compressed = Vec::new();
+    codec.compress(&values, &mut compressed).unwrap();
+    assert_eq!(compressed[0], 4);
+    let mut decompressed = ColumnData::VecI16(Vec::new());
+    codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(decompressed, values);
+
+    let compressed =
+        compress_scheme(TransformId::Delta, CodecType::ZSTD, &values, &options).unwrap();
+    let decompressed = decompress_scheme(&compressed, "i16", None, &options).unwrap();
+    assert_eq!(decompressed, values);
+}
+
+#[test]
+fn test_compress_as_data_page() {
+    use parquet::compression::compress_as_data_page;
+    use parquet::data_type::ColumnData;
+    use parquet::file::statistics::Statistics;
+
+    let options = CodecOptionsBuilder::default().build();
+    let mut rng = thread_rng();
+    let ints: Vec<i32> = (0..1000).map(|_| rng.gen_range(-5000..5000)).collect();
+    let values = ColumnData::VecI32(ints.clone());
+
+    let page = compress_as_data_page(&values, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(page.num_values, 1000);
+    assert_eq!(page.uncompressed_size, 4000);
+    let min = *ints.iter().min().unwrap();
+    let max = *ints.iter().max().unwrap();
+    assert_eq!(page.statistics, Statistics::int32(Some(min), Some(max), None, 0, false));
+    assert_eq!(page.statistics.null_count(), 0);
+
+    let mut decompressed = ColumnData::VecI32(Vec::new());
+    create_test_codec(CodecType::ZSTD)
+        .decompress(&page.buf, &mut decompressed, Some(page.uncompressed_size))
+        .unwrap();
+    assert_eq!(decompressed, values);
+
+    // NaNs are ignored and zero bounds are signed as Parquet requires
+    let floats = ColumnData::VecF64(vec![f64::NAN, 0.0, 2.5, f64::NAN, 1.0]);
+    let page = compress_as_data_page(&floats, CodecType::SNAPPY, &options).unwrap();
+    match page.statistics {
+        Statistics::Double(stats) => {
+            assert!(stats.min().is_sign_negative() && *stats.min() == 0.0);
+            assert_eq!(*stats.max(), 2.5);
+        }
+        stats => panic!("unexpected statistics {:?}", stats),
+    }
+
+    // unsigned values are ordered as unsigned
+    let unsigned = ColumnData::VecU32(vec![1, u32::MAX, 7]);
+    let page = compress_as_data_page(&unsigned, CodecType::GZIP, &options).unwrap();
+    assert_eq!(page.statistics, Statistics::int32(Some(1), Some(-1), None, 0, false));
+
+    let empty = ColumnData::VecI64(Vec::new());
+    let page = compress_as_data_page(&empty, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(page.num_values, 0);
+    assert!(!page.statistics.has_min_max_set());
+    assert!(compress_as_data_page(&values, CodecType::UNCOMPRESSED, &options).is_err());
+}
+
+#[test]
+fn test_column_data_try_new() {
+    use std::any::Any;
+    use std::fmt;
+
+    use parquet::data_type::{ColumnData, DataTypeConstraint};
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+    struct Flag(bool);
+
+    impl fmt::Display for Flag {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl DataTypeConstraint for Flag {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    let err = ColumnData::try_new(&vec![Flag(true)]).unwrap_err();
+    assert!(err.to_string().contains("Unsupported data type"), "{}", err);
+    assert!(err.to_string().contains("Flag"), "{}", err);
+    assert!(std::panic::catch_unwind(|| ColumnData::new(&vec![Flag(false)])).is_err());
+
+    assert_eq!(
+        ColumnData::try_new(&vec![1u16, 2]).unwrap(),
+        ColumnData::VecU16(vec![1, 2])
+    );
+}
+
+#[test]
+fn test_select_codec() {
+    use parquet::compression::{select_codec, Preference};
+
+    for preference in [Preference::Fastest, Preference::Balanced, Preference::Smallest] {
+        for typename in ["i64", "u8"] {
+            let codec_type = select_codec(preference, typename);
+            assert_ne!(codec_type, CodecType::UNCOMPRESSED, "{:?} {}", preference, typename);
+            let codec = create_test_codec(codec_type);
+            assert!(codec.supports_type(typename), "{:?} {}", preference, typename);
+        }
+    }
+    assert_eq!(select_codec(Preference::Fastest, "i64"), CodecType::LZ4_RAW);
+    assert_eq!(select_codec(Preference::Balanced, "i64"), CodecType::ZSTD);
+    assert_eq!(select_codec(Preference::Smallest, "i64"), CodecType::QCOM);
+    // QCOM cannot compress bytes
+    assert_eq!(select_codec(Preference::Smallest, "u8"), CodecType::BROTLI);
+    assert_eq!(select_codec(Preference::Smallest, "bool"), CodecType::UNCOMPRESSED);
+}
+
+#[test]
+fn test_codec_misaligned_u32() {
+    let data = ColumnData::VecU8(vec![1, 2, 3, 4, 5, 6, 7]);
+
+    let mut direct = ColumnData::VecU32(Vec::new());
+    let err = direct.convert_from_u8(&vec![1, 2, 3, 4, 5, 6, 7]).unwrap_err();
+    assert!(err.to_string().contains("7 bytes is not a whole number of u32"), "{}", err);
+    assert_eq!(direct, ColumnData::VecU32(Vec::new()));
+
+    for codec_type in [
+        CodecType::SNAPPY,
+        CodecType::GZIP,
+        CodecType::BROTLI,
+        CodecType::LZ4,
+        CodecType::ZSTD,
+        CodecType::LZ4_RAW,
+    ] {
+        let mut codec = create_test_codec(codec_type);
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).expect("compress failed");
+
+        let mut decompressed = ColumnData::VecU32(Vec::new());
+        let err = codec
+            .decompress(&compressed, &mut decompressed, Some(7))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("conversion to u32 failed"), "{:?}: {}", codec_type, err);
+    }
+}
+
+#[test]
+fn test_codec_reset() {
+    use parquet::data_type::ColumnData;
+
+    let data = ColumnData::VecU8(random_typed_vec(10000));
+
+    let options = CodecOptionsBuilder::default()
+        .set_verify_roundtrip(true)
+        .build();
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut first = Vec::new();
+        codec.compress(&data, &mut first).unwrap();
+        assert_eq!(codec.last_element_count(), 10000);
+
+        codec.reset();
+        // the caller's buffer is untouched
+        assert!(!first.is_empty());
+        let mut second = Vec::new();
+        codec.compress(&data, &mut second).unwrap();
+        assert_eq!(first, second);
+        let mut decompressed = ColumnData::VecU8(Vec::new());
+        codec.decompress(&second, &mut decompressed, Some(10000)).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    let mut codec = create_test_codec(CodecType::SNAPPY);
+    codec.compress(&data, &mut Vec::new()).unwrap();
+    codec.reset();
+    assert_eq!(codec.last_element_count(), 0);
+}
+
+#[test]
+fn test_codec_skip_corrupt_frames() {
+    use parquet::data_type::ColumnData;
+
+    let first = ColumnData::VecU8((0..1000).map(|i| (i * i % 251) as u8).collect());
+    let second = ColumnData::VecU8((0..1000).map(|i| (i % 7) as u8).collect());
+    let options = CodecOptionsBuilder::default()
+        .set_skip_corrupt_frames(true)
+        .build();
+    let mut codec = create_codec(CodecType::LZ4, &options).unwrap().unwrap();
+
+    // two Hadoop frames back to back, the first with its compressed data overwritten
+    let mut compressed = Vec::new();
+    codec.compress(&first, &mut compressed).unwrap();
+    let first_len = compressed.len();
+    codec.compress(&second, &mut compressed).unwrap();
+    compressed[8..first_len].fill(0xFF);
+
+    let mut decompressed = ColumnData::VecU8(Vec::new());
+    let n = codec.decompress(&compressed, &mut decompressed, Some(2000)).unwrap();
+    assert_eq!(n, 1000);
+    assert_eq!(decompressed, second);
+    assert_eq!(codec.last_skipped_frames(), &[0]);
+
+    let mut decompressed = ColumnData::VecU8(Vec::new());
+    let mut intact = Vec::new();
+    codec.compress(&first, &mut intact).unwrap();
+    codec.decompress(&intact, &mut decompressed, Some(1000)).unwrap();
+    assert_eq!(decompressed, first);
+    assert!(codec.last_skipped_frames().is_empty());
+
+    let options = options.with_skip_corrupt_frames(false);
+    let mut codec = create_codec(CodecType::LZ4, &options).unwrap().unwrap();
+    let mut decompressed = ColumnData::VecU8(Vec::new());
+    assert!(codec.decompress(&compressed, &mut decompressed, Some(2000)).is_err());
+}
+
+#[test]
+fn test_create_codec_enum() {
+    use parquet::compression::{available_codecs, create_codec_enum, CodecKind};
+    use parquet::data_type::ColumnData;
+
+    let values = ColumnData::VecU64((0..10_000).map(|i| i * i % 1009).collect());
+    let options = CodecOptionsBuilder::default().build();
+    for codec_type in available_codecs() {
+        let mut kind = create_codec_enum(codec_type, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        kind.compress(&values, &mut compressed).unwrap();
+
+        // the same format as the boxed codec
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut decompressed = ColumnData::VecU64(Vec::new());
+        codec
+            .decompress(&compressed, &mut decompressed, Some(80_000))
+            .unwrap();
+        assert_eq!(decompressed, values, "{:?}", codec_type);
+
+        let mut boxed = Vec::new();
+        codec.compress(&values, &mut boxed).unwrap();
+        let mut decompressed = ColumnData::VecU64(Vec::new());
+        kind.decompress(&boxed, &mut decompressed, Some(80_000))
+            .unwrap();
+        assert_eq!(decompressed, values, "{:?}", codec_type);
+        assert_eq!(
+            kind.as_codec().config_fingerprint(),
+            kind.into_codec().config_fingerprint()
+        );
+    }
+
+    let kind = create_codec_enum(CodecType::SNAPPY, &options).unwrap().unwrap();
+    assert!(matches!(kind, CodecKind::Snappy(_)));
+    assert!(create_codec_enum(CodecType::UNCOMPRESSED, &options)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_codec_intra_dedup() {
+    use parquet::data_type::ColumnData;
+
+    // a 4KB block repeated further apart than the windows of these codecs, at a
+    // multiple of the 1KB dedup block
+    let mut rng = thread_rng();
+    let block: Vec<u8> = (0..4096).map(|_| rng.gen()).collect();
+    let mut bytes = block.clone();
+    bytes.extend((0..70 * 1024).map(|_| rng.gen::<u8>()));
+    bytes.extend_from_slice(&block);
+    bytes.extend((0..100).map(|_| rng.gen::<u8>()));
+    let values = ColumnData::VecU8(bytes);
+
+    let options = CodecOptionsBuilder::default().build();
+    let dedup_options = options.with_intra_dedup(true);
+    for codec_type in [CodecType::SNAPPY, CodecType::GZIP, CodecType::LZ4_RAW] {
+        let mut codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let mut plain = Vec::new();
+        codec.compress(&values, &mut plain).unwrap();
+
+        let mut codec = create_codec(codec_type, &dedup_options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&values, &mut compressed).unwrap();
+        assert!(
+            compressed.len() + 4000 < plain.len(),
+            "{:?}: {} vs {} bytes",
+            codec_type,
+            compressed.len(),
+            plain.len()
+        );
+
+        let mut decompressed = ColumnData::VecU8(Vec::new());
+        codec
+            .decompress(&compressed, &mut decompressed, Some(values.len()))
+            .unwrap();
+        assert_eq!(decompressed, values, "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_backend_version() {
+    use parquet::compression::available_codecs;
+
+    let options = CodecOptionsBuilder::default()
+        .set_verify_roundtrip(true)
+        .build();
+    for codec_type in available_codecs() {
+        let codec = create_codec(codec_type, &options).unwrap().unwrap();
+        let version = codec.backend_version();
+        assert!(!version.is_empty(), "{:?}", codec_type);
+        assert_ne!(version, "unknown", "{:?}", codec_type);
+        assert!(
+            version.split('.').all(|part| part.parse::<u32>().is_ok()),
+            "{:?}: {}",
+            codec_type,
+            version
+        );
+    }
+}
+
+#[test]
+fn test_codec_qcom_u8() {
+    let mut rng = thread_rng();
+    let bytes: Vec<u8> = (0..100).map(|_| rng.gen()).collect();
+
+    // q_compress has no 8-bit number type, so byte columns are rejected up front
+    let mut codec = create_test_codec(CodecType::QCOM);
+    assert!(!codec.supports_type("u8"));
+    let mut compressed = Vec::new();
+    let err = codec
+        .compress(&ColumnData::new(&bytes), &mut compressed)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parquet error: QCOM compress: unsupported input type u8"
+    );
+    assert!(compressed.is_empty());
+
+    let err = codec
+        .compress(&ColumnData::VecI8(vec![-1, 0, 1]), &mut compressed)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Parquet error: QCOM compress: unsupported input type i8"
+    );
+}
+
+#[test]
+fn test_create_codec_by_name() {
+    use parquet::compression::{available_codecs, create_codec_by_name};
+
+    let options = CodecOptionsBuilder::default().build();
+    let values = ColumnData::VecU32((0..1000).collect());
+    for codec_type in available_codecs() {
+        let name = codec_type.to_string();
+        for name in [name.to_lowercase(), name] {
+            let mut codec = create_codec_by_name(&name, &options).unwrap().unwrap();
+            let expected = create_codec(codec_type, &options).unwrap().unwrap();
+            assert_eq!(codec.config_fingerprint(), expected.config_fingerprint());
+
+            let mut compressed = Vec::new();
+            codec.compress(&values, &mut compressed).unwrap();
+            let mut decompressed = values.slice(0, 0);
+            codec
+                .decompress(&compressed, &mut decompressed, Some(4000))
+                .unwrap();
+            assert_eq!(decompressed, values, "{}", name);
+        }
+    }
+
+    assert!(create_codec_by_name("Uncompressed", &options)
+        .unwrap()
+        .is_none());
+    let err = create_codec_by_name("snappy2", &options).err().unwrap();
+    assert!(err.to_string().contains("Unknown codec name snappy2"), "{}", err);
+    assert!(create_codec_by_name("lzo", &options).is_err());
+}
+
+#[test]
+fn test_compress_into_n_blocks() {
+    use parquet::compression::{compress_into_n_blocks, decompress_n_blocks};
+
+    let options = CodecOptionsBuilder::default().build();
+    // 1001 values do not split evenly into 4 blocks
+    let values = random_column::<i64>(1001);
+    for codec_type in [CodecType::SNAPPY, CodecType::ZSTD, CodecType::LZ4, CodecType::QCOM] {
+        let blocks = compress_into_n_blocks(&values, 4, codec_type, &options).unwrap();
+        assert_eq!(blocks.len(), 4);
+        let decompressed = decompress_n_blocks(&blocks, "i64", codec_type, &options).unwrap();
+        assert_eq!(decompressed, values, "{:?}", codec_type);
+
+        // every block reads back on its own, with counts 251, 250, 250, 250
+        let mut concatenated = ColumnData::new(&Vec::<i64>::new());
+        for (block, count) in blocks.iter().zip([251, 250, 250, 250]) {
+            let part =
+                decompress_n_blocks(std::slice::from_ref(block), "i64", codec_type, &options)
+                    .unwrap();
+            assert_eq!(part.len(), count);
+            concatenated.extend_from(&part).unwrap();
+        }
+        assert_eq!(concatenated, values, "{:?}", codec_type);
+
+        // a block count from a corrupt header is an error, not an overflow
+        let mut corrupt = blocks[0].clone();
+        corrupt[..8].copy_from_slice(&(u64::MAX / 4).to_be_bytes());
+        assert!(decompress_n_blocks(&[corrupt], "i64", codec_type, &options).is_err());
+    }
+
+    // more blocks than values leaves the last blocks empty
+    let few = ColumnData::new(&vec![1u32, 2, 3]);
+    let blocks = compress_into_n_blocks(&few, 4, CodecType::ZSTD, &options).unwrap();
+    assert_eq!(blocks.len(), 4);
+    let decompressed = decompress_n_blocks(&blocks, "u32", CodecType::ZSTD, &options).unwrap();
+    assert_eq!(decompressed, few);
+
+    assert!(compress_into_n_blocks(&few, 0, CodecType::ZSTD, &options).is_err());
+}
+
+#[test]
+fn test_codec_name() {
+    let expected = [
+        (CodecType::SNAPPY, "SNAPPY"),
+        (CodecType::GZIP, "GZIP"),
+        (CodecType::BROTLI, "BROTLI"),
+        (CodecType::LZ4, "LZ4_HADOOP"),
+        (CodecType::ZSTD, "ZSTD"),
+        (CodecType::LZ4_RAW, "LZ4_RAW"),
+        (CodecType::QCOM, "QCOM"),
+    ];
+    // wrapping codecs report the codec they wrap
+    let wrapped = CodecOptionsBuilder::default()
+        .set_verify_roundtrip(true)
+        .set_retry_on_transient(2)
+        .build();
+    for (codec_type, name) in expected {
+        assert_eq!(create_test_codec(codec_type).name(), name);
+        let codec = create_codec(codec_type, &wrapped).unwrap().unwrap();
+        assert_eq!(codec.name(), name);
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_codec_async() {
+    use parquet::compression::{compress_async, decompress_async, FrameReader};
+
+    // several chunks, the last one partial
+    let mut rng = thread_rng();
+    let bytes: Vec<u8> = (0..200_000).map(|_| rng.gen_range(0..16)).collect();
+    for codec_type in [CodecType::GZIP, CodecType::BROTLI, CodecType::ZSTD] {
+        let codec = create_test_codec(codec_type);
+        let compressed = compress_async(bytes.as_slice(), Vec::new(), codec)
+            .await
+            .unwrap();
+        assert!(compressed.len() < bytes.len(), "{:?}", codec_type);
+
+        let codec = create_test_codec(codec_type);
+        let decompressed = decompress_async(compressed.as_slice(), Vec::new(), codec)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, bytes, "{:?}", codec_type);
+
+        // the records are the ones FrameReader reads
+        let codec = create_test_codec(codec_type);
+        let example = ColumnData::VecU8(Vec::new());
+        let mut read = Vec::new();
+        for record in FrameReader::new(compressed.as_slice(), codec, &example) {
+            match record.unwrap() {
+                ColumnData::VecU8(x) => read.extend(x),
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(read, bytes, "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_decompress_block() {
+    use parquet::compression::BlockSplitCodec;
+
+    // 1000 u32 values in blocks of 256 values
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let inner = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let mut codec = BlockSplitCodec::new(inner, 1024);
+    assert!(codec.supports_seek());
+    let data: Vec<u32> = (0..1000).collect();
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut compressed).unwrap();
+
+    let mut block = ColumnData::new(&Vec::<u32>::new());
+    let n = codec
+        .decompress_block(&compressed, 2, &mut block, Some(4000))
+        .unwrap();
+    assert_eq!(n, 1024);
+    assert_eq!(block, ColumnData::new(&data[512..768]));
+
+    // the last block is partial
+    let mut block = ColumnData::new(&Vec::<u32>::new());
+    codec
+        .decompress_block(&compressed, 3, &mut block, Some(4000))
+        .unwrap();
+    assert_eq!(block, ColumnData::new(&data[768..]));
+    let mut block = ColumnData::new(&Vec::<u32>::new());
+    assert!(codec
+        .decompress_block(&compressed, 4, &mut block, Some(4000))
+        .is_err());
+
+    // codecs without blocks cannot seek
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    assert!(!codec.supports_seek());
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut compressed).unwrap();
+    let mut block = ColumnData::new(&Vec::<u32>::new());
+    assert!(codec
+        .decompress_block(&compressed, 0, &mut block, Some(4000))
+        .is_err());
+}
+
+#[test]
+fn test_codec_lz4_raw_without_size() {
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let mut codec = create_codec(CodecType::LZ4_RAW, &options).unwrap().unwrap();
+
+    // highly compressible data needs several rounds of buffer growth
+    let data: Vec<u64> = (0..100_000).map(|i| i / 1000).collect();
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut compressed).unwrap();
+    assert!(compressed.len() * 4 < data.len() * 8);
+
+    let mut decompressed = ColumnData::new(&Vec::<u64>::new());
+    let n = codec.decompress(&compressed, &mut decompressed, None).unwrap();
+    assert_eq!(n, data.len() * 8);
+    assert_eq!(decompressed, ColumnData::new(&data));
+
+    // garbage still fails once the buffer reaches the expansion limit
+    let mut decompressed = ColumnData::new(&Vec::<u8>::new());
+    assert!(codec
+        .decompress(&[0xff; 16], &mut decompressed, None)
+        .is_err());
+}
+
+#[test]
+fn test_codec_compress_typed() {
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    let data: Vec<f32> = (0..1000).map(|i| i as f32 * 0.5).collect();
+    let mut compressed = Vec::new();
+    codec.compress_typed(&data, &mut compressed).unwrap();
+
+    // the typed bytes match compressing the same column
+    let mut expected = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut expected).unwrap();
+    assert_eq!(compressed, expected);
+
+    let mut decompressed = vec![-1.0f32];
+    let n = codec
+        .decompress_typed(&compressed, &mut decompressed, Some(data.len() * 4))
+        .unwrap();
+    assert_eq!(n, data.len() * 4);
+    assert_eq!(decompressed[0], -1.0);
+    assert_eq!(&decompressed[1..], &data[..]);
+
+    // a column only converts back to its own element type
+    assert!(ColumnData::new(&data).try_into_vec::<f64>().is_err());
+    assert_eq!(ColumnData::new(&data).try_into_vec::<f32>().unwrap(), data);
+}
+
+#[test]
+fn test_compress_lossy_float() {
+    use parquet::compression::{
+        compress_lossy_float, decompress_lossy_float, LossyFloatCodec,
+    };
+
+    let options = CodecOptionsBuilder::default().build();
+    let mut rng = thread_rng();
+    let data: Vec<f64> = (0..10_000).map(|_| rng.gen_range(-50.0..150.0)).collect();
+    let min = data.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = data.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    for bits in [4u8, 12, 24] {
+        let compressed =
+            compress_lossy_float(&data, bits, CodecType::ZSTD, &options).unwrap();
+        assert!(compressed.len() < data.len() * bits as usize / 8 + 64);
+        let decompressed =
+            decompress_lossy_float(&compressed, CodecType::ZSTD, &options).unwrap();
+        assert_eq!(decompressed.len(), data.len());
+
+        let bound = (max - min) / ((1u64 << bits) - 1) as f64 / 2.0;
+        for (original, approx) in data.iter().zip(&decompressed) {
+            assert!((original - approx).abs() <= bound * (1.0 + 1e-9));
+        }
+    }
+
+    // a constant column comes back exactly
+    let constant = vec![3.25; 100];
+    let compressed =
+        compress_lossy_float(&constant, 8, CodecType::SNAPPY, &options).unwrap();
+    assert_eq!(
+        decompress_lossy_float(&compressed, CodecType::SNAPPY, &options).unwrap(),
+        constant
+    );
+
+    // an element count from a corrupt header is an error, not an overflow
+    for count in [u64::MAX / 4, 1 << 40] {
+        let mut corrupt = compressed.clone();
+        corrupt[1..9].copy_from_slice(&count.to_be_bytes());
+        assert!(decompress_lossy_float(&corrupt, CodecType::SNAPPY, &options).is_err());
+    }
+
+    assert!(compress_lossy_float(&data, 0, CodecType::ZSTD, &options).is_err());
+    assert!(compress_lossy_float(&[f64::NAN], 8, CodecType::ZSTD, &options).is_err());
+
+    let inner = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    assert!(inner.is_lossless());
+    let codec = LossyFloatCodec::new(inner, 12);
+    assert!(!codec.is_lossless());
+    assert!(codec.supports_type("f32"));
+    assert!(!codec.supports_type("i32"));
+}
+
+#[test]
+fn test_transform_roundtrip() {
+    use parquet::compression::{
+        compress_scheme, decompress_scheme, DeltaCodec, Transform, TransformCodec,
+        TransformId,
+    };
+
+    let transforms = [
+        TransformId::Identity,
+        TransformId::Delta,
+        TransformId::FloatSplit,
+        TransformId::ByteSwap,
+        TransformId::ZigZag,
+        TransformId::Xor,
+        TransformId::ByteSplit,
+        TransformId::Rle,
+    ];
+    let mut rng = thread_rng();
+    let columns = vec![
+        ColumnData::new(&(0..1000).map(|_| rng.gen::<u8>()).collect::<Vec<_>>()),
+        ColumnData::new(&(0..1000).map(|i| i as u16 * 3).collect::<Vec<_>>()),
+        ColumnData::new(&(0..1000).map(|_| rng.gen::<i8>()).collect::<Vec<_>>()),
+        ColumnData::new(&(0..1000).map(|_| rng.gen::<i16>()).collect::<Vec<_>>()),
+        ColumnData::new(&(-500..500).collect::<Vec<i32>>()),
+        ColumnData::new(&(0..1000).map(|_| rng.gen::<i64>()).collect::<Vec<_>>()),
+        ColumnData::new(&(0..1000).map(|_| rng.gen::<u64>()).collect::<Vec<_>>()),
+        ColumnData::VecI24(vec![-(1 << 23), -1, 0, 1, (1 << 23) - 1]),
+        ColumnData::VecU24(vec![0, 1, 0x00FF_FFFF, 0x0012_3456]),
+        ColumnData::new(&(0..1000).map(|i| i as f32 * 0.25).collect::<Vec<_>>()),
+        ColumnData::new(&vec![f64::NAN, -0.0, 1.5, f64::MIN, f64::MAX, 1e-310]),
+        ColumnData::new(&(0..1000).map(|i| i / 100).collect::<Vec<i32>>()),
+        ColumnData::new(&vec![7u8; 1000]),
+        ColumnData::new(&vec![0.0f32, -0.0, -0.0, f32::NAN, f32::NAN]),
+    ];
+
+    for column in &columns {
+        for transform in transforms {
+            let mut values = column.clone();
+            transform.forward(&mut values).unwrap();
+            if transform != TransformId::Rle {
+                assert_eq!(values.len(), column.len());
+            }
+            transform.inverse(&mut values).unwrap();
+            let mut expected = Vec::new();
+            let mut actual = Vec::new();
+            column.convert_to_u8(&mut expected).unwrap();
+            values.convert_to_u8(&mut actual).unwrap();
+            assert_eq!(actual, expected, "{:?} on {}", transform, column.typename());
+        }
+
+        // transforms compose and reverse in the opposite order
+        let mut values = column.clone();
+        transforms[..].forward(&mut values).unwrap();
+        transforms[..].inverse(&mut values).unwrap();
+        let mut expected = Vec::new();
+        let mut actual = Vec::new();
+        column.convert_to_u8(&mut expected).unwrap();
+        values.convert_to_u8(&mut actual).unwrap();
+        assert_eq!(actual, expected, "composed on {}", column.typename());
+    }
+
+    // rle keeps a value and a length per run, splitting runs too long for the type
+    let mut values = ColumnData::new(&(0..1000).map(|i| i / 100).collect::<Vec<i32>>());
+    TransformId::Rle.forward(&mut values).unwrap();
+    assert_eq!(values.len(), 20);
+    let mut values = ColumnData::new(&vec![7u8; 1000]);
+    TransformId::Rle.forward(&mut values).unwrap();
+    let runs = vec![7u8, 255, 7, 255, 7, 255, 7, 235];
+    assert_eq!(values, ColumnData::new(&runs));
+    let mut values = ColumnData::new(&vec![7u8, 0]);
+    assert!(TransformId::Rle.inverse(&mut values).is_err());
+    let mut values = ColumnData::new(&vec![7u8, 1, 7]);
+    assert!(TransformId::Rle.inverse(&mut values).is_err());
+
+    // zigzag maps small negative values to small non-negative ones
+    let mut values = ColumnData::new(&vec![0i32, -1, 1, -2, 2]);
+    TransformId::ZigZag.forward(&mut values).unwrap();
+    assert_eq!(values, ColumnData::new(&vec![0i32, 1, 2, 3, 4]));
+
+    let options = CodecOptionsBuilder::default().build();
+    let inner = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let mut codec = TransformCodec::new(inner, vec![TransformId::Delta, TransformId::ZigZag]);
+    let data = ColumnData::new(&(0..1000).map(|i| 1000 - i * 2).collect::<Vec<i64>>());
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    codec.decompress(&compressed, &mut decompressed, Some(8000)).unwrap();
+    assert_eq!(decompressed, data);
+
+    for transform in [TransformId::ZigZag, TransformId::Xor, TransformId::ByteSplit] {
+        let compressed = compress_scheme(transform, CodecType::LZ4_RAW, &data, &options).unwrap();
+        let decompressed = decompress_scheme(&compressed, "i64", Some(8000), &options).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    // the delta wrapper create_codec adds writes the same bytes as the delta transform
+    let overflowing = ColumnData::VecI16(vec![i16::MIN, i16::MAX, 0, 1, 2]);
+    for data in [&data, &overflowing] {
+        let inner = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+        let mut wrapper = Vec::new();
+        DeltaCodec::new(inner).compress(data, &mut wrapper).unwrap();
+        let inner = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+        let mut transform = Vec::new();
+        TransformCodec::new(inner, vec![TransformId::Delta])
+            .compress(data, &mut transform)
+            .unwrap();
+        assert_eq!(wrapper, transform, "{}", data.typename());
+    }
+
+    // delta then rle turns a sorted column with a regular step into a few runs
+    let inner = create_codec(CodecType::LZ4_RAW, &options).unwrap().unwrap();
+    let transforms = vec![TransformId::Delta, TransformId::Rle];
+    let mut codec = TransformCodec::new(inner, transforms);
+    let mut compressed = Vec::new();
+    codec.compress(&data, &mut compressed).unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+    codec.decompress(&compressed, &mut decompressed, Some(8000)).unwrap();
+    assert_eq!(decompressed, data);
+    // the residual width, then the 4 values [1000, 1, -2, 999] compressed
+    assert_eq!(&compressed[..9], &[8, 0, 0, 0, 0, 0, 0, 0, 4]);
+}
+
+#[test]
+fn test_transform_forwarding() {
+    use parquet::compression::{BlockSplitCodec, TransformCodec, TransformId};
+
+    // element-wise transforms keep the blocks of the inner codec seekable
+    let options = CodecOptionsBuilder::default()
+        .set_auto_block_split(false)
+        .build();
+    let inner = create_codec(CodecType::SNAPPY, &options).unwrap().unwrap();
+    let block_split = Box::new(BlockSplitCodec::new(inner, 1024));
+    let mut codec =
+        TransformCodec::new(block_split.clone_codec(), vec![TransformId::ZigZag]);
+    assert!(codec.supports_seek());
+    let data: Vec<i32> = (-500..500).collect();
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut compressed).unwrap();
+    let mut block = ColumnData::new(&Vec::<i32>::new());
+    codec
+        .decompress_block(&compressed, 2, &mut block, Some(4000))
+        .unwrap();
+    assert_eq!(block, ColumnData::new(&data[512..768]));
+
+    let mut codec = TransformCodec::new(block_split, vec![TransformId::Delta]);
+    assert!(!codec.supports_seek());
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&data), &mut compressed).unwrap();
+    let mut block = ColumnData::new(&Vec::<i32>::new());
+    assert!(codec
+        .decompress_block(&compressed, 0, &mut block, Some(4000))
+        .is_err());
+
+    // and their frames can be merged
+    let zigzag_codec = || {
+        let inner = create_test_codec(CodecType::ZSTD);
+        TransformCodec::new(inner, vec![TransformId::ZigZag])
+    };
+    let mut codec = zigzag_codec();
+    let mut frames = vec![Vec::new(), Vec::new()];
+    codec.compress(&ColumnData::new(&data[..300]), &mut frames[0]).unwrap();
+    codec.compress(&ColumnData::new(&data[300..]), &mut frames[1]).unwrap();
+    let merged = codec.concat_frames(&frames).unwrap();
+    let mut decompressed = ColumnData::new(&Vec::<i32>::new());
+    codec
+        .decompress(&merged, &mut decompressed, Some(4000))
+        .unwrap();
+    assert_eq!(decompressed, ColumnData::new(&data));
+    let inner = create_test_codec(CodecType::ZSTD);
+    let codec = TransformCodec::new(inner, vec![TransformId::Rle]);
+    assert!(codec.concat_frames(&frames).is_err());
+
+    // only identity transforms pass the bytes through to a view
+    let bytes: Vec<u8> = random_typed_vec(1000);
+    let inner = create_test_codec(CodecType::ZSTD);
+    let mut codec = TransformCodec::new(inner, vec![TransformId::Identity]);
+    let mut compressed = Vec::new();
+    codec.compress(&ColumnData::new(&bytes), &mut compressed).unwrap();
+    let view = codec.decompress_view(&compressed, Some(1000)).unwrap();
+    assert_eq!(view, &bytes[..]);
+    let mut codec = zigzag_codec();
+    assert!(codec.decompress_view(&compressed, Some(1000)).is_err());
+}
+
+#[test]
+fn test_schema_codec_cache() {
+    use parquet::compression::{recommend_codec, SchemaCodecCache};
+
+    let options = CodecOptionsBuilder::default().build();
+    let cache = SchemaCodecCache::new(options.clone());
+    let ids = ColumnData::new(&(0..4096).collect::<Vec<i64>>());
+    let mut rng = thread_rng();
+    let noise = ColumnData::new(&(0..4096).map(|_| rng.gen::<f64>()).collect::<Vec<_>>());
+
+    let first = cache.recommend_cached("id", "i64", &ids);
+    assert_eq!(first, recommend_codec(&ids, &options));
+    assert_eq!(cache.sample_count(), 1);
+
+    // the same key is served from the cache, whatever the sample
+    assert_eq!(cache.recommend_cached("id", "i64", &noise), first);
+    assert_eq!(cache.sample_count(), 1);
+
+    // another column or type is sampled on its own
+    cache.recommend_cached("value", "f64", &noise);
+    cache.recommend_cached("id", "i32", &ColumnData::new(&vec![1i32; 100]));
+    assert_eq!(cache.sample_count(), 3);
+    cache.recommend_cached("value", "f64", &noise);
+    assert_eq!(cache.sample_count(), 3);
+
+    cache.clear();
+    cache.recommend_cached("id", "i64", &ids);
+    assert_eq!(cache.sample_count(), 4);
+}
+
+#[test]
+fn test_codec_qcom_level() {
+    let mut rng = thread_rng();
+    let data: Vec<i64> = (0..10_000).map(|i| i * 3 + rng.gen_range(-5..5)).collect();
+    let data = ColumnData::new(&data);
+
+    for level in [0, 12] {
+        let options = CodecOptionsBuilder::default().set_qcom_level(level).build();
+        let mut codec = create_codec(CodecType::QCOM, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        codec.compress(&data, &mut compressed).unwrap();
+        let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+        codec.decompress(&compressed, &mut decompressed, None).unwrap();
+        assert_eq!(decompressed, data, "level {}", level);
+    }
+
+    let options = CodecOptionsBuilder::default().set_qcom_level(13).build();
+    let err = create_codec(CodecType::QCOM, &options).err().unwrap();
+    assert!(err.to_string().contains("QCOM level"), "{}", err);
+    let err = CodecOptionsBuilder::default()
+        .set_qcom_level(13)
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("QCOM level"), "{}", err);
+    assert!(CodecOptionsBuilder::default()
+        .set_qcom_level(12)
+        .try_build()
+        .is_ok());
+}
+
+#[test]
+fn test_codec_stream() {
+    use std::io::Cursor;
+
+    use parquet::compression::{EncodingHint, Endianness};
+
+    let data: Vec<u8> = (0..1 << 20).map(|i| (i / 16 % 251) as u8).collect();
+
+    let mut codec = create_test_codec(CodecType::ZSTD);
+    let mut compressed = Vec::new();
+    let read = codec.compress_stream(&mut Cursor::new(&data), &mut compressed).unwrap();
+    assert_eq!(read, data.len() as u64);
+    assert!(compressed.len() < data.len());
+
+    let mut decompressed = Vec::new();
+    let written = codec
+        .decompress_stream(&mut Cursor::new(&compressed), &mut decompressed)
+        .unwrap();
+    assert_eq!(written, data.len() as u64);
+    assert_eq!(decompressed, data);
+
+    // the stream is in the same format `compress` produces
+    let mut column = ColumnData::new(&Vec::<u8>::new());
+    codec.decompress(&compressed, &mut column, None).unwrap();
+    assert_eq!(column, ColumnData::new(&data));
+
+    let mut codec = create_test_codec(CodecType::LZ4_RAW);
+    assert!(codec.compress_stream(&mut Cursor::new(&data), &mut Vec::new()).is_err());
+
+    // every wrapper passes the stream to the codec it wraps; bit packing conflicts
+    // with delta encoding and little-endian integers, so it gets its own options
+    let wrapped = CodecOptionsBuilder::default()
+        .set_intra_dedup(true)
+        .set_retry_on_transient(2)
+        .set_context_prefix(Some(b"ctx".to_vec()))
+        .set_int_endianness(Endianness::Little)
+        .set_encoding_hint(EncodingHint::Sorted)
+        .set_float_component_split(true)
+        .set_min_compression_ratio(Some(1.5))
+        .set_verify_roundtrip(true)
+        .set_canonicalize_nan(true)
+        .set_warn_on_precompressed(true)
+        .set_max_output_size(Some(data.len()));
+    #[cfg(feature = "allocator")]
+    let wrapped = wrapped.set_allocator(parquet::compression::ScratchAllocator::new(
+        allocator_api2::alloc::Global,
+    ));
+    let bit_packed = CodecOptionsBuilder::default()
+        .set_bit_pack_width(Some(12))
+        .set_verify_roundtrip(true);
+    for options in [wrapped.build(), bit_packed.build()] {
+        let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+        let mut compressed = Vec::new();
+        let read = codec
+            .compress_stream(&mut Cursor::new(&data), &mut compressed)
+            .unwrap();
+        assert_eq!(read, data.len() as u64);
+
+        let mut decompressed = Vec::new();
+        codec
+            .decompress_stream(&mut Cursor::new(&compressed), &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    // the output cap still applies to streams
+    let options = CodecOptionsBuilder::default().set_max_output_size(Some(16)).build();
+    let mut codec = create_codec(CodecType::ZSTD, &options).unwrap().unwrap();
+    let err = codec
+        .compress_stream(&mut Cursor::new(&data), &mut Vec::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("max_output_size"), "{}", err);
+}
+
+#[test]
+fn test_column_data_mixed_types() {
+    use std::any::Any;
+    use std::fmt;
+
+    use parquet::data_type::{ColumnData, DataTypeConstraint};
+
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    enum Mixed {
+        U16(u16),
+        U32(u32),
+    }
+
+    impl Default for Mixed {
+        fn default() -> Self {
+            Mixed::U16(0)
+        }
+    }
+
+    impl fmt::Display for Mixed {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    impl DataTypeConstraint for Mixed {
+        fn typename(&self) -> &'static str {
+            match self {
+                Mixed::U16(x) => x.typename(),
+                Mixed::U32(x) => x.typename(),
+            }
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            match self {
+                Mixed::U16(x) => x,
+                Mixed::U32(x) => x,
+            }
+        }
+    }
+
+    assert_eq!(
+        ColumnData::try_new(&vec![Mixed::U16(1), Mixed::U16(2)]).unwrap(),
+        ColumnData::VecU16(vec![1, 2])
+    );
+
+    let err = ColumnData::try_new(&vec![Mixed::U16(1), Mixed::U32(2)]).unwrap_err();
+    assert!(err.to_string().contains("expected u16, found u32 at index 1"), "{}", err);
+}
+
+#[test]
+fn test_codec_element_type_mismatch() {
+    use std::any::Any;
+    use std::fmt;
+
+    use parquet::compression::available_codecs;
+    use parquet::data_type::{ColumnData, DataTypeConstraint};
+
+    // claims to be a u8 but holds u32 values
+    #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+    struct Wide(u32);
+
+    impl fmt::Display for Wide {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl DataTypeConstraint for Wide {
+        fn typename(&self) -> &'static str {
+            "u8"
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            &self.0
+        }
+    }
+
+    let err = ColumnData::try_new(&[Wide(1), Wide(300)]).unwrap_err();
+    assert!(err.to_string().contains("Mixed element types"), "{}", err);
+
+    // decompressing into a column of another type errors instead of reinterpreting
+    // the bytes
+    let bytes = ColumnData::VecU8(vec![1, 2, 3]);
+    for codec_type in available_codecs() {
+        let mut codec = create_test_codec(codec_type);
+        if !codec.supports_type("u8") {
+            continue;
+        }
+        let mut compressed = Vec::new();
+        codec.compress(&bytes, &mut compressed).unwrap();
+        let mut wide = ColumnData::VecU32(Vec::new());
+        let result = codec.decompress(&compressed, &mut wide, Some(3));
+        assert!(result.is_err(), "{:?}", codec_type);
+    }
+}
+
+#[test]
+fn test_codec_empty_input() {
+    use parquet::compression::available_codecs;
+
+    for codec_type in available_codecs() {
+        let mut codec = create_test_codec(codec_type);
+        let empty = ColumnData::new(&Vec::<i64>::new());
+        let mut compressed = Vec::new();
+        codec.compress(&empty, &mut compressed).unwrap();
+
+        // the LZ4 Hadoop codec cannot decompress without knowing the size
+        let sizes = match codec_type {
+            CodecType::LZ4 => vec![Some(0)],
+            _ => vec![Some(0), None],
+        };
+        for size in sizes {
+            let mut decompressed = ColumnData::new(&Vec::<i64>::new());
+            let n = codec.decompress(&compressed, &mut decompressed, size).unwrap();
+            assert_eq!(n, 0, "{:?}", codec_type);
+            assert_eq!(decompressed, empty, "{:?}", codec_type);
+        }
+    }
+}
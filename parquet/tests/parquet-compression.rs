@@ -109,4 +109,18 @@ fn test_codec_zstd() {
 #[test]
 fn test_codec_lz4_raw() {
     test_codec_with_size(CodecType::LZ4_RAW);
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn test_codec_bzip2() {
+    test_codec_with_size(CodecType::BZIP2);
+    test_codec_without_size(CodecType::BZIP2);
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn test_codec_xz() {
+    test_codec_with_size(CodecType::XZ);
+    test_codec_without_size(CodecType::XZ);
 }
\ No newline at end of file